@@ -0,0 +1,1502 @@
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Context;
+use base64::Engine;
+use base64::prelude::BASE64_STANDARD;
+use chrono::{DateTime, Utc};
+use futures::stream::{self, Stream, TryStreamExt};
+use reqwest::header::{COOKIE, HeaderMap, HeaderName, HeaderValue, SET_COOKIE, USER_AGENT};
+use reqwest::{Client, Method, Response, StatusCode};
+use reqwest_middleware::{ClientBuilder, ClientWithMiddleware, Middleware, RequestBuilder};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use tracing::instrument;
+use zeroize::Zeroizing;
+
+use crate::body::{self, PageDecoder};
+use crate::error::{ApiError, ClientError, Result, is_retryable_status};
+use crate::read_only::ReadOnlyViolation;
+use crate::schema::{self, Schema};
+use crate::secret::SecretString;
+use crate::trace::Spans;
+use crate::validation::Limits;
+
+/// Sent as `X-Client-Version` and in the `User-Agent` so server logs can
+/// segment traffic by client release.
+pub const CLIENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Todo {
+    pub id: i64,
+    pub user_id: i64,
+    pub title: String,
+    pub completed: bool,
+    pub created_at: String,
+    pub updated_at: String,
+    /// RFC 3339 deadline, from servers that support due dates
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub due_at: Option<String>,
+    /// Labels, from servers that support tags
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Workflow status, from servers that track more than `completed`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<TodoStatus>,
+    /// Why the todo is blocked, alongside a `blocked` status
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blocked_reason: Option<String>,
+    /// IDs of todos this one waits on, from servers that support
+    /// dependencies
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub depends_on: Vec<i64>,
+    /// Expected effort in minutes, from servers that support estimates
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub estimate_minutes: Option<i64>,
+    /// Typed links to other todos and web pages, from servers that
+    /// support them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub links: Vec<Link>,
+}
+
+impl Todo {
+    /// The parsed due date, if the todo has a valid one.
+    pub fn due(&self) -> Option<DateTime<Utc>> {
+        let due = self.due_at.as_deref()?;
+        DateTime::parse_from_rfc3339(due)
+            .ok()
+            .map(|due| due.with_timezone(&Utc))
+    }
+
+    /// Open and past its due date as of `now`.
+    pub fn is_overdue(&self, now: DateTime<Utc>) -> bool {
+        !self.completed && self.due().is_some_and(|due| due < now)
+    }
+
+    /// The server's status, or one derived from `completed` on servers
+    /// that don't track it.
+    pub fn status(&self) -> TodoStatus {
+        match self.status {
+            Some(status) => status,
+            None if self.completed => TodoStatus::Done,
+            None => TodoStatus::Todo,
+        }
+    }
+}
+
+/// Where a todo is in its workflow. Older servers only know `completed`,
+/// which maps to `todo` and `done`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TodoStatus {
+    Todo,
+    InProgress,
+    Blocked,
+    Done,
+}
+
+impl TodoStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TodoStatus::Todo => "todo",
+            TodoStatus::InProgress => "in-progress",
+            TodoStatus::Blocked => "blocked",
+            TodoStatus::Done => "done",
+        }
+    }
+}
+
+impl std::fmt::Display for TodoStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for TodoStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().replace('_', "-").as_str() {
+            "todo" => Ok(TodoStatus::Todo),
+            "in-progress" => Ok(TodoStatus::InProgress),
+            "blocked" => Ok(TodoStatus::Blocked),
+            "done" => Ok(TodoStatus::Done),
+            _ => Err(format!(
+                "Unknown status '{}' (use todo, in-progress, blocked or done)",
+                s
+            )),
+        }
+    }
+}
+
+/// A link from one todo to another todo or to a web page.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum Link {
+    /// Related work, without any order between the two
+    RelatesTo { todo_id: i64 },
+    /// The same work as another todo, which is the one to keep
+    Duplicates { todo_id: i64 },
+    /// A page elsewhere: an issue, a document, a thread
+    External { url: String },
+}
+
+impl Link {
+    /// The linked todo, for links between todos.
+    pub fn todo_id(&self) -> Option<i64> {
+        match *self {
+            Link::RelatesTo { todo_id } | Link::Duplicates { todo_id } => Some(todo_id),
+            Link::External { .. } => None,
+        }
+    }
+}
+
+/// One page of a paginated collection endpoint.
+///
+/// The server names the collection after the resource (`todos`, `users`,
+/// `comments`, `sessions`, `workspaces`, `members`, `invitations`), so
+/// `items` accepts any of those keys.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Page<T> {
+    #[serde(
+        alias = "todos",
+        alias = "users",
+        alias = "comments",
+        alias = "sessions",
+        alias = "workspaces",
+        alias = "members",
+        alias = "invitations"
+    )]
+    pub items: Vec<T>,
+    pub total: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+impl<T> Page<T> {
+    pub fn has_more(&self) -> bool {
+        self.next_cursor.is_some()
+    }
+
+    /// Streams the items of this page and every following page, calling
+    /// `fetch_next` with the cursor whenever the current page runs out.
+    pub fn into_stream<F, Fut>(self, fetch_next: F) -> impl Stream<Item = Result<T>>
+    where
+        F: FnMut(String) -> Fut,
+        Fut: Future<Output = Result<Page<T>>>,
+    {
+        let state = (self.items.into_iter(), self.next_cursor, fetch_next);
+        stream::unfold(Some(state), |state| async move {
+            let (mut items, mut cursor, mut fetch_next) = state?;
+            loop {
+                if let Some(item) = items.next() {
+                    return Some((Ok(item), Some((items, cursor, fetch_next))));
+                }
+                match fetch_next(cursor.take()?).await {
+                    Ok(page) => {
+                        items = page.items.into_iter();
+                        cursor = page.next_cursor;
+                    }
+                    Err(err) => return Some((Err(err), None)),
+                }
+            }
+        })
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct CreateTodoRequest {
+    title: String,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct UpdateTodoRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    completed: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    due_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tags: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    status: Option<TodoStatus>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    blocked_reason: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    depends_on: Option<Vec<i64>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    estimate_minutes: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    links: Option<Vec<Link>>,
+}
+
+#[derive(Debug, Serialize)]
+struct UpdateProfileRequest<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    email: Option<&'a str>,
+}
+
+/// The authenticated user's profile.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct User {
+    pub id: i64,
+    pub email: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub created_at: String,
+    /// A requested email change awaiting confirmation from the new address
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pending_email: Option<String>,
+}
+
+/// A shared space whose todos are visible to all its members.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Workspace {
+    pub id: i64,
+    pub name: String,
+    pub created_at: String,
+    /// The authenticated user's role here, from servers that report it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub role: Option<Role>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Member {
+    pub user_id: i64,
+    pub email: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub role: Role,
+}
+
+#[derive(Debug, Serialize)]
+struct CreateWorkspaceRequest<'a> {
+    name: &'a str,
+}
+
+/// What a workspace member may do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    Admin,
+    Member,
+    Viewer,
+}
+
+impl Role {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Role::Admin => "admin",
+            Role::Member => "member",
+            Role::Viewer => "viewer",
+        }
+    }
+}
+
+impl std::fmt::Display for Role {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for Role {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "admin" => Ok(Role::Admin),
+            "member" => Ok(Role::Member),
+            "viewer" => Ok(Role::Viewer),
+            _ => Err(format!("Unknown role '{}' (use admin, member or viewer)", s)),
+        }
+    }
+}
+
+/// An invitation to join a workspace, as seen by the invitee or the inviter.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Invitation {
+    pub id: i64,
+    pub workspace_id: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace_name: Option<String>,
+    pub email: String,
+    pub role: Role,
+    /// `pending`, `accepted`, `declined` or `expired`
+    pub status: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub invited_by: Option<String>,
+    pub created_at: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<String>,
+}
+
+impl Invitation {
+    pub fn is_pending(&self) -> bool {
+        self.status == "pending"
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct InviteRequest<'a> {
+    email: &'a str,
+    role: Role,
+}
+
+#[derive(Debug, Serialize)]
+struct SetRoleRequest {
+    role: Role,
+}
+
+/// Quota state from the `X-RateLimit-*` headers of a response. A field is
+/// `None` when the server doesn't send that header.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RateLimit {
+    pub limit: Option<u64>,
+    pub remaining: Option<u64>,
+    pub reset_at: Option<DateTime<Utc>>,
+}
+
+impl RateLimit {
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let number = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.trim().parse::<u64>().ok())
+        };
+
+        // Servers send either seconds until the reset or a Unix timestamp;
+        // anything past 2001 can only be the latter
+        let reset_at = number("X-RateLimit-Reset").and_then(|reset| {
+            let reset = i64::try_from(reset).ok()?;
+            if reset > 1_000_000_000 {
+                DateTime::from_timestamp(reset, 0)
+            } else {
+                Some(Utc::now() + chrono::Duration::seconds(reset))
+            }
+        });
+
+        Self {
+            limit: number("X-RateLimit-Limit"),
+            remaining: number("X-RateLimit-Remaining"),
+            reset_at,
+        }
+    }
+
+    pub fn is_reported(&self) -> bool {
+        self.limit.is_some() || self.remaining.is_some() || self.reset_at.is_some()
+    }
+}
+
+/// Current rate-limit state alongside the server's configured limits.
+#[derive(Debug, Clone, Serialize)]
+pub struct Quota {
+    pub rate_limit: RateLimit,
+    pub limits: Limits,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LoginRequest {
+    pub email: String,
+    pub password: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RegisterRequest {
+    pub email: String,
+    pub password: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[allow(dead_code)]
+pub struct AuthResponse {
+    /// Empty from session-only servers, which authenticate with `session`
+    #[serde(default)]
+    pub token: SecretString,
+    #[serde(default)]
+    pub user_id: Option<i64>,
+    /// `Cookie` header value built from the response's `Set-Cookie` headers
+    #[serde(skip)]
+    pub session: Option<String>,
+}
+
+impl AuthResponse {
+    /// Reads a login or register response. One that sets cookies comes from
+    /// a session deployment and may carry no token, so it skips the schema.
+    async fn from_response(client: &ApiClient, response: Response, schema: Schema) -> Result<Self> {
+        let Some(session) = session_cookie(response.headers()) else {
+            return client.parse(response, schema).await;
+        };
+        let mut auth: Self = client.json(response).await.unwrap_or_default();
+        auth.session = Some(session);
+        Ok(auth)
+    }
+}
+
+/// An `Authorization` value marked sensitive, so it prints as `Sensitive`
+/// in debug output of the request and is left out of HTTP/2 header
+/// compression tables.
+fn sensitive_header(value: Zeroizing<String>) -> Option<HeaderValue> {
+    match HeaderValue::from_str(&value) {
+        Ok(mut header) => {
+            header.set_sensitive(true);
+            Some(header)
+        }
+        Err(_) => {
+            tracing::warn!("Not sending credentials that aren't a valid header value");
+            None
+        }
+    }
+}
+
+/// Joins the `name=value` part of every `Set-Cookie` header into a single
+/// `Cookie` header value, or `None` if the response set no cookies.
+fn session_cookie(headers: &HeaderMap) -> Option<String> {
+    let cookies: Vec<&str> = headers
+        .get_all(SET_COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .filter_map(|value| value.split(';').next())
+        .map(str::trim)
+        .filter(|pair| !pair.is_empty())
+        .collect();
+    (!cookies.is_empty()).then(|| cookies.join("; "))
+}
+
+/// Per-call overrides for timeout, retries, idempotency and preconditions.
+///
+/// Bulk operations can ask for aggressive retries while interactive commands
+/// fail fast; anything left unset falls back to the client defaults.
+#[derive(Debug, Clone, Default)]
+pub struct RequestOptions {
+    timeout: Option<Duration>,
+    retries: u32,
+    idempotency_key: Option<String>,
+    if_match: Option<String>,
+}
+
+impl RequestOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Retries transport failures and retryable statuses up to `retries` times.
+    /// Pair with an idempotency key when retrying non-idempotent calls.
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    pub fn idempotency_key(mut self, key: impl Into<String>) -> Self {
+        self.idempotency_key = Some(key.into());
+        self
+    }
+
+    /// Sends `If-Match` with the `updated_at` the change was based on, so
+    /// servers that version todos reject it with 412 if another write got
+    /// there first. Older servers ignore the header.
+    pub fn if_match(mut self, updated_at: impl Into<String>) -> Self {
+        self.if_match = Some(updated_at.into());
+        self
+    }
+}
+
+pub struct ApiClient {
+    client: ClientWithMiddleware,
+    base_url: String,
+    token: Option<SecretString>,
+    session: Option<String>,
+    basic_auth: Option<(String, String)>,
+    workspace: Option<i64>,
+    validate_responses: bool,
+    max_response_bytes: usize,
+}
+
+/// Configures an `ApiClient` beyond the base URL and token.
+#[derive(Default)]
+pub struct ApiClientBuilder {
+    base_url: String,
+    token: Option<SecretString>,
+    session: Option<String>,
+    basic_auth: Option<(String, String)>,
+    workspace: Option<i64>,
+    headers: Vec<(String, String)>,
+    middleware: Vec<Arc<dyn Middleware>>,
+    user_agent_suffix: Option<String>,
+    timeout: Option<Duration>,
+    validate_responses: bool,
+    max_response_bytes: Option<usize>,
+}
+
+impl ApiClientBuilder {
+    pub fn token(mut self, token: Option<SecretString>) -> Self {
+        self.token = token;
+        self
+    }
+
+    /// Session cookie sent instead of a bearer token, for servers that
+    /// authenticate with cookies.
+    pub fn session(mut self, session: Option<String>) -> Self {
+        self.session = session;
+        self
+    }
+
+    /// HTTP Basic credentials for an API fronted by a basic-auth proxy.
+    /// They take the `Authorization` header, so the bearer token isn't sent.
+    pub fn basic_auth(mut self, username: String, password: String) -> Self {
+        self.basic_auth = Some((username, password));
+        self
+    }
+
+    /// Scopes todo requests to a workspace instead of the user's own list.
+    pub fn workspace(mut self, workspace: Option<i64>) -> Self {
+        self.workspace = workspace;
+        self
+    }
+
+    /// Sends an extra header with every request, e.g. for header-based
+    /// routing or an access proxy. Later values replace earlier ones.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Appends a layer to the middleware stack. Logging, auth refresh,
+    /// metrics and caching go here instead of into each endpoint method.
+    pub fn middleware(mut self, middleware: Arc<dyn Middleware>) -> Self {
+        self.middleware.push(middleware);
+        self
+    }
+
+    /// Extra product token appended to the `User-Agent`, e.g. `acme-sync/2.1`.
+    pub fn user_agent_suffix(mut self, suffix: Option<String>) -> Self {
+        self.user_agent_suffix = suffix;
+        self
+    }
+
+    /// Client-wide request timeout, 30 seconds when unset. `RequestOptions`
+    /// can still override it per call.
+    pub fn timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Check every response body against the bundled JSON Schemas before
+    /// deserializing it, reporting drift instead of an opaque serde error.
+    pub fn validate_responses(mut self, validate: bool) -> Self {
+        self.validate_responses = validate;
+        self
+    }
+
+    /// Fails any response whose body runs past this many bytes, 16 MiB
+    /// when unset, so a broken or hostile server can't exhaust memory.
+    pub fn max_response_bytes(mut self, limit: Option<usize>) -> Self {
+        self.max_response_bytes = limit;
+        self
+    }
+
+    pub fn build(self) -> anyhow::Result<ApiClient> {
+        let mut user_agent = format!(
+            "todo-cli/{} ({}; {})",
+            CLIENT_VERSION,
+            std::env::consts::OS,
+            std::env::consts::ARCH
+        );
+        if let Some(suffix) = self.user_agent_suffix {
+            user_agent = format!("{} {}", user_agent, suffix);
+        }
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            USER_AGENT,
+            HeaderValue::from_str(&user_agent).context("Invalid User-Agent suffix")?,
+        );
+        headers.insert("X-Client-Version", HeaderValue::from_static(CLIENT_VERSION));
+        for (name, value) in &self.headers {
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .with_context(|| format!("Invalid header name '{}'", name))?;
+            let value = HeaderValue::from_str(value)
+                .with_context(|| format!("Invalid value for header {}", name))?;
+            headers.insert(name, value);
+        }
+
+        let builder = Client::builder().default_headers(headers);
+        // The fetch API behind reqwest on wasm32 has no client-wide timeout
+        #[cfg(not(target_arch = "wasm32"))]
+        let builder = builder.timeout(self.timeout.unwrap_or(Duration::from_secs(30)));
+        let inner = builder.build().context("Failed to create HTTP client")?;
+
+        // Every call gets a tracing span, outermost so it covers the rest
+        let tracing: Arc<dyn Middleware> = Arc::new(Spans);
+        let client = std::iter::once(tracing)
+            .chain(self.middleware)
+            .fold(ClientBuilder::new(inner), |builder, layer| builder.with_arc(layer))
+            .build();
+
+        Ok(ApiClient {
+            client,
+            base_url: self.base_url.trim_end_matches('/').to_string(),
+            token: self.token,
+            session: self.session,
+            basic_auth: self.basic_auth,
+            workspace: self.workspace,
+            validate_responses: self.validate_responses,
+            max_response_bytes: self.max_response_bytes.unwrap_or(body::DEFAULT_MAX_BYTES),
+        })
+    }
+}
+
+impl ApiClient {
+    pub fn new(base_url: &str, token: Option<String>) -> Self {
+        Self::builder(base_url)
+            .token(token.map(SecretString::from))
+            .build()
+            .expect("Failed to create HTTP client")
+    }
+
+    pub fn builder(base_url: &str) -> ApiClientBuilder {
+        ApiClientBuilder {
+            base_url: base_url.to_string(),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_token(&self, token: SecretString) -> Self {
+        Self {
+            client: self.client.clone(),
+            base_url: self.base_url.clone(),
+            token: Some(token),
+            session: self.session.clone(),
+            basic_auth: self.basic_auth.clone(),
+            workspace: self.workspace,
+            validate_responses: self.validate_responses,
+            max_response_bytes: self.max_response_bytes,
+        }
+    }
+
+    fn auth_header(&self) -> Option<HeaderValue> {
+        self.basic_auth_header().or_else(|| {
+            let token = self.token.as_ref()?;
+            sensitive_header(Zeroizing::new(format!("Bearer {}", token.expose())))
+        })
+    }
+
+    fn basic_auth_header(&self) -> Option<HeaderValue> {
+        let (username, password) = self.basic_auth.as_ref()?;
+        let credentials = Zeroizing::new(format!("{}:{}", username, password));
+        let credentials = Zeroizing::new(BASE64_STANDARD.encode(credentials.as_bytes()));
+        sensitive_header(Zeroizing::new(format!("Basic {}", credentials.as_str())))
+    }
+
+    /// Request without the bearer token, for login and registration. Basic
+    /// credentials still go along, since the proxy guards those routes too.
+    fn anonymous_request(&self, method: Method, url: &str) -> RequestBuilder {
+        let request = self.client.request(method, url);
+        match self.basic_auth_header() {
+            Some(auth) => request.header("Authorization", auth),
+            None => request,
+        }
+    }
+
+    fn request(&self, method: Method, url: &str) -> RequestBuilder {
+        let mut request = self.client.request(method, url);
+        if let Some(auth) = self.auth_header() {
+            request = request.header("Authorization", auth);
+        }
+        if let Some(ref session) = self.session {
+            request = request.header(COOKIE, session);
+        }
+        if let Some(workspace) = self.workspace {
+            request = request.header("X-Workspace-ID", workspace.to_string());
+        }
+        request
+    }
+
+    /// Deserializes a success body, first checking it against `schema` when
+    /// response validation is enabled.
+    async fn parse<T: DeserializeOwned>(&self, response: Response, schema: Schema) -> Result<T> {
+        if !self.validate_responses {
+            return self.json(response).await;
+        }
+        let body: serde_json::Value = self.json(response).await?;
+        schema::validate(schema, &body).map_err(|err| ApiError::InvalidResponse(err.into()))?;
+        serde_json::from_value(body).map_err(|err| ApiError::InvalidResponse(err.into()))
+    }
+
+    /// Deserializes a success body of at most `max_response_bytes`.
+    async fn json<T: DeserializeOwned>(&self, response: Response) -> Result<T> {
+        let body = body::read_to_vec(response, self.max_response_bytes)
+            .await
+            .map_err(ApiError::InvalidResponse)?;
+        serde_json::from_slice(&body).map_err(|err| ApiError::InvalidResponse(err.into()))
+    }
+
+    /// Deserializes a page item by item as the body streams in. Validation
+    /// needs the whole document, so with it on the page is buffered.
+    async fn page<T: DeserializeOwned>(
+        &self,
+        response: Response,
+        schema: Schema,
+    ) -> Result<Page<T>> {
+        if self.validate_responses {
+            return self.parse(response, schema).await;
+        }
+        let mut decoder = PageDecoder::new();
+        body::read_chunks(response, self.max_response_bytes, |chunk| decoder.push(chunk))
+            .await
+            .map_err(ApiError::InvalidResponse)?;
+        decoder.finish().map_err(ApiError::InvalidResponse)
+    }
+
+    /// Sends `body` as an update to todo `id`. Servers drop fields they
+    /// don't know instead of rejecting them, so `dropped` looks at the todo
+    /// that comes back and names the feature when the sent field is missing.
+    async fn put_todo(
+        &self,
+        id: i64,
+        body: &UpdateTodoRequest,
+        opts: &RequestOptions,
+        dropped: impl FnOnce(&Todo) -> Option<&'static str>,
+    ) -> Result<Todo> {
+        let url = format!("{}/api/v1/todos/{}", self.base_url, id);
+
+        let request = self.request(Method::PUT, &url).json(body);
+
+        let response = self.send(request, opts).await?;
+
+        if !response.status().is_success() {
+            return Err(ApiError::from_response(response).await.for_todo(id));
+        }
+
+        let todo: Todo = self.parse(response, Schema::Todo).await?;
+        match dropped(&todo) {
+            Some(feature) => Err(ApiError::Unsupported(feature)),
+            None => Ok(todo),
+        }
+    }
+
+    /// Sends `request` with the overrides in `opts` applied, retrying with
+    /// exponential backoff while attempts remain.
+    async fn send(
+        &self,
+        request: RequestBuilder,
+        opts: &RequestOptions,
+    ) -> reqwest_middleware::Result<Response> {
+        let mut request = request;
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(timeout) = opts.timeout {
+            request = request.timeout(timeout);
+        }
+        if let Some(ref key) = opts.idempotency_key {
+            request = request.header("Idempotency-Key", key);
+        }
+        if let Some(ref updated_at) = opts.if_match {
+            request = request.header("If-Match", format!("\"{}\"", updated_at));
+        }
+
+        let mut attempt = 0;
+        loop {
+            // Streaming bodies can't be replayed, so they get a single attempt
+            let Some(current) = request.try_clone() else {
+                return request.send().await;
+            };
+
+            match current.send().await {
+                Ok(response)
+                    if is_retryable_status(response.status()) && attempt < opts.retries => {}
+                Err(ref err) if attempt < opts.retries && !refused(err) => {}
+                result => return result,
+            }
+
+            attempt += 1;
+            tracing::debug!(attempt, "Retrying request");
+            futures_timer::Delay::new(Duration::from_millis(200 * 2u64.pow(attempt))).await;
+        }
+    }
+}
+
+/// Whether read-only mode stopped the request, which every retry would repeat.
+fn refused(err: &reqwest_middleware::Error) -> bool {
+    matches!(err, reqwest_middleware::Error::Middleware(err) if err.is::<ReadOnlyViolation>())
+}
+
+/// Operations exposed by the go-api-starter todo API.
+///
+/// `ApiClient` is the HTTP implementation; command handlers are generic over
+/// this trait so they can run against an in-memory fake in tests.
+///
+/// The futures are deliberately not `Send`: the CLI awaits them on a single
+/// task, and wasm32 futures can't be `Send` anyway.
+#[allow(async_fn_in_trait)]
+pub trait TodoApi {
+    /// Server this client talks to, used to key stored credentials.
+    fn base_url(&self) -> &str;
+    /// Workspace that todo requests are scoped to, if any.
+    fn workspace(&self) -> Option<i64>;
+
+    async fn login(
+        &self,
+        email: &str,
+        password: &str,
+        opts: &RequestOptions,
+    ) -> Result<AuthResponse>;
+    async fn register(
+        &self,
+        email: &str,
+        password: &str,
+        opts: &RequestOptions,
+    ) -> Result<AuthResponse>;
+    async fn list_todos_page(
+        &self,
+        completed: Option<bool>,
+        cursor: Option<&str>,
+        opts: &RequestOptions,
+    ) -> Result<Page<Todo>>;
+    async fn get_todo(&self, id: i64, opts: &RequestOptions) -> Result<Todo>;
+    async fn create_todo(&self, title: &str, opts: &RequestOptions) -> Result<Todo>;
+    async fn update_todo(
+        &self,
+        id: i64,
+        title: Option<&str>,
+        completed: Option<bool>,
+        opts: &RequestOptions,
+    ) -> Result<Todo>;
+    /// Sets the deadline; fails on servers without due-date support.
+    async fn set_due(&self, id: i64, due: DateTime<Utc>, opts: &RequestOptions) -> Result<Todo>;
+    /// Replaces the tags; fails on servers without tag support.
+    async fn set_tags(&self, id: i64, tags: &[String], opts: &RequestOptions) -> Result<Todo>;
+    /// Sets the expected effort; fails on servers without estimates.
+    async fn set_estimate(&self, id: i64, minutes: i64, opts: &RequestOptions) -> Result<Todo>;
+    /// Replaces the todos this one depends on; fails on servers without
+    /// dependency support.
+    async fn set_dependencies(
+        &self,
+        id: i64,
+        depends_on: &[i64],
+        opts: &RequestOptions,
+    ) -> Result<Todo>;
+    /// Replaces the todo's links; fails on servers without link support.
+    async fn set_links(&self, id: i64, links: &[Link], opts: &RequestOptions) -> Result<Todo>;
+    /// Moves the todo to `status`, with a reason when blocking it. Servers
+    /// that only track `completed` take `todo` and `done` and refuse the rest.
+    async fn set_status(
+        &self,
+        id: i64,
+        status: TodoStatus,
+        reason: Option<&str>,
+        opts: &RequestOptions,
+    ) -> Result<Todo>;
+    async fn delete_todo(&self, id: i64, opts: &RequestOptions) -> Result<()>;
+    async fn limits(&self, opts: &RequestOptions) -> Result<Limits>;
+    /// Reads the rate-limit headers off a `/config` request, which also
+    /// returns the configured limits.
+    async fn quota(&self, opts: &RequestOptions) -> Result<Quota>;
+    /// Permanently deletes the authenticated user and all their data.
+    async fn delete_account(&self, opts: &RequestOptions) -> Result<()>;
+    async fn profile(&self, opts: &RequestOptions) -> Result<User>;
+    /// Replaces the avatar with a PNG image.
+    async fn set_avatar(&self, png: Vec<u8>, opts: &RequestOptions) -> Result<()>;
+    async fn remove_avatar(&self, opts: &RequestOptions) -> Result<()>;
+    /// Changes the name and/or email. An email change may only take effect
+    /// once confirmed, in which case the new address is `pending_email`.
+    async fn update_profile(
+        &self,
+        name: Option<&str>,
+        email: Option<&str>,
+        opts: &RequestOptions,
+    ) -> Result<User>;
+    /// Workspaces the authenticated user belongs to.
+    async fn list_workspaces(&self, opts: &RequestOptions) -> Result<Vec<Workspace>>;
+    async fn create_workspace(&self, name: &str, opts: &RequestOptions) -> Result<Workspace>;
+    async fn workspace_members(&self, id: i64, opts: &RequestOptions) -> Result<Vec<Member>>;
+    async fn invite_member(
+        &self,
+        workspace: i64,
+        email: &str,
+        role: Role,
+        opts: &RequestOptions,
+    ) -> Result<Invitation>;
+    async fn set_member_role(
+        &self,
+        workspace: i64,
+        user_id: i64,
+        role: Role,
+        opts: &RequestOptions,
+    ) -> Result<Member>;
+    /// Invitations addressed to the authenticated user.
+    async fn list_invitations(&self, opts: &RequestOptions) -> Result<Vec<Invitation>>;
+    /// Accepts or declines an invitation addressed to the authenticated user.
+    async fn respond_to_invitation(
+        &self,
+        id: i64,
+        accept: bool,
+        opts: &RequestOptions,
+    ) -> Result<Invitation>;
+
+    /// Fetches every todo, following `next_cursor` across pages.
+    async fn list_todos(
+        &self,
+        completed: Option<bool>,
+        opts: &RequestOptions,
+    ) -> Result<Vec<Todo>> {
+        let first = self.list_todos_page(completed, None, opts).await?;
+        if !first.has_more() {
+            return Ok(first.items);
+        }
+
+        first
+            .into_stream(move |cursor| async move {
+                self.list_todos_page(completed, Some(&cursor), opts).await
+            })
+            .try_collect()
+            .await
+    }
+}
+
+impl TodoApi for ApiClient {
+    fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    fn workspace(&self) -> Option<i64> {
+        self.workspace
+    }
+
+    #[instrument(level = "debug", skip(self, password, opts))]
+    async fn login(
+        &self,
+        email: &str,
+        password: &str,
+        opts: &RequestOptions,
+    ) -> Result<AuthResponse> {
+        let url = format!("{}/api/v1/auth/login", self.base_url);
+
+        let request = self.anonymous_request(Method::POST, &url).json(&LoginRequest {
+            email: email.to_string(),
+            password: password.to_string(),
+        });
+
+        let response = self.send(request, opts).await?;
+
+        if !response.status().is_success() {
+            return Err(ApiError::from_response(response).await);
+        }
+
+        AuthResponse::from_response(self, response, Schema::LoginResponse).await
+    }
+
+    #[instrument(level = "debug", skip(self, password, opts))]
+    async fn register(
+        &self,
+        email: &str,
+        password: &str,
+        opts: &RequestOptions,
+    ) -> Result<AuthResponse> {
+        let url = format!("{}/api/v1/users", self.base_url);
+
+        let request = self.anonymous_request(Method::POST, &url).json(&RegisterRequest {
+            email: email.to_string(),
+            password: password.to_string(),
+        });
+
+        let response = self.send(request, opts).await?;
+
+        if !response.status().is_success() {
+            return Err(ApiError::from_response(response).await);
+        }
+
+        AuthResponse::from_response(self, response, Schema::User).await
+    }
+
+    #[instrument(level = "debug", skip(self, opts))]
+    async fn list_todos_page(
+        &self,
+        _completed: Option<bool>,
+        cursor: Option<&str>,
+        opts: &RequestOptions,
+    ) -> Result<Page<Todo>> {
+        let url = format!("{}/api/v1/todos", self.base_url);
+
+        let mut request = self.request(Method::GET, &url);
+        if let Some(cursor) = cursor {
+            request = request.query(&[("cursor", cursor)]);
+        }
+
+        let response = self.send(request, opts).await?;
+
+        if !response.status().is_success() {
+            return Err(ApiError::from_response(response).await);
+        }
+
+        self.page(response, Schema::TodoList).await
+    }
+
+    #[instrument(level = "debug", skip(self, opts))]
+    async fn get_todo(&self, id: i64, opts: &RequestOptions) -> Result<Todo> {
+        let url = format!("{}/api/v1/todos/{}", self.base_url, id);
+
+        let request = self.request(Method::GET, &url);
+
+        let response = self.send(request, opts).await?;
+
+        if !response.status().is_success() {
+            return Err(ApiError::from_response(response).await.for_todo(id));
+        }
+
+        self.parse(response, Schema::Todo).await
+    }
+
+    #[instrument(level = "debug", skip(self, opts))]
+    async fn create_todo(&self, title: &str, opts: &RequestOptions) -> Result<Todo> {
+        let url = format!("{}/api/v1/todos", self.base_url);
+
+        let request = self.request(Method::POST, &url).json(&CreateTodoRequest {
+            title: title.to_string(),
+        });
+
+        let response = self.send(request, opts).await?;
+
+        if !response.status().is_success() {
+            return Err(ApiError::from_response(response).await);
+        }
+
+        self.parse(response, Schema::Todo).await
+    }
+
+    #[instrument(level = "debug", skip(self, opts))]
+    async fn update_todo(
+        &self,
+        id: i64,
+        title: Option<&str>,
+        completed: Option<bool>,
+        opts: &RequestOptions,
+    ) -> Result<Todo> {
+        let body = UpdateTodoRequest {
+            title: title.map(|s| s.to_string()),
+            completed,
+            ..Default::default()
+        };
+        self.put_todo(id, &body, opts, |_| None).await
+    }
+
+    #[instrument(level = "debug", skip(self, opts))]
+    async fn set_due(&self, id: i64, due: DateTime<Utc>, opts: &RequestOptions) -> Result<Todo> {
+        let body = UpdateTodoRequest {
+            due_at: Some(due.to_rfc3339()),
+            ..Default::default()
+        };
+        self.put_todo(id, &body, opts, |todo| todo.due_at.is_none().then_some("due dates"))
+            .await
+    }
+
+    #[instrument(level = "debug", skip(self, opts))]
+    async fn set_tags(&self, id: i64, tags: &[String], opts: &RequestOptions) -> Result<Todo> {
+        let body = UpdateTodoRequest {
+            tags: Some(tags.to_vec()),
+            ..Default::default()
+        };
+        self.put_todo(id, &body, opts, |todo| {
+            (!tags.is_empty() && todo.tags.is_empty()).then_some("tags")
+        })
+        .await
+    }
+
+    #[instrument(level = "debug", skip(self, opts))]
+    async fn set_estimate(&self, id: i64, minutes: i64, opts: &RequestOptions) -> Result<Todo> {
+        let body = UpdateTodoRequest {
+            estimate_minutes: Some(minutes),
+            ..Default::default()
+        };
+        self.put_todo(id, &body, opts, |todo| {
+            todo.estimate_minutes.is_none().then_some("estimates")
+        })
+        .await
+    }
+
+    #[instrument(level = "debug", skip(self, opts))]
+    async fn set_dependencies(
+        &self,
+        id: i64,
+        depends_on: &[i64],
+        opts: &RequestOptions,
+    ) -> Result<Todo> {
+        let body = UpdateTodoRequest {
+            depends_on: Some(depends_on.to_vec()),
+            ..Default::default()
+        };
+        self.put_todo(id, &body, opts, |todo| {
+            (!depends_on.is_empty() && todo.depends_on.is_empty())
+                .then_some("dependencies between todos")
+        })
+        .await
+    }
+
+    #[instrument(level = "debug", skip(self, opts))]
+    async fn set_links(&self, id: i64, links: &[Link], opts: &RequestOptions) -> Result<Todo> {
+        let body = UpdateTodoRequest {
+            links: Some(links.to_vec()),
+            ..Default::default()
+        };
+        self.put_todo(id, &body, opts, |todo| {
+            (!links.is_empty() && todo.links.is_empty()).then_some("links between todos")
+        })
+        .await
+    }
+
+    #[instrument(level = "debug", skip(self, opts))]
+    async fn set_status(
+        &self,
+        id: i64,
+        status: TodoStatus,
+        reason: Option<&str>,
+        opts: &RequestOptions,
+    ) -> Result<Todo> {
+        let open_or_done = matches!(status, TodoStatus::Todo | TodoStatus::Done);
+        let body = UpdateTodoRequest {
+            // Keeps older servers in step; the others derive it from status
+            completed: open_or_done.then_some(status == TodoStatus::Done),
+            status: Some(status),
+            blocked_reason: reason
+                .filter(|_| status == TodoStatus::Blocked)
+                .map(str::to_string),
+            ..Default::default()
+        };
+        self.put_todo(id, &body, opts, |todo| {
+            (todo.status.is_none() && !open_or_done)
+                .then_some("statuses other than todo and done")
+        })
+        .await
+    }
+
+    #[instrument(level = "debug", skip(self, opts))]
+    async fn delete_todo(&self, id: i64, opts: &RequestOptions) -> Result<()> {
+        let url = format!("{}/api/v1/todos/{}", self.base_url, id);
+
+        let request = self.request(Method::DELETE, &url);
+
+        let response = self.send(request, opts).await?;
+
+        if !response.status().is_success() {
+            return Err(ApiError::from_response(response).await.for_todo(id));
+        }
+
+        Ok(())
+    }
+
+    /// Servers without a `/config` endpoint get the default limits.
+    #[instrument(level = "debug", skip(self, opts))]
+    async fn limits(&self, opts: &RequestOptions) -> Result<Limits> {
+        let url = format!("{}/api/v1/config", self.base_url);
+
+        let request = self.request(Method::GET, &url);
+
+        let response = self.send(request, opts).await?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(Limits::default());
+        }
+        if !response.status().is_success() {
+            return Err(ApiError::from_response(response).await);
+        }
+
+        self.json(response).await
+    }
+
+    #[instrument(level = "debug", skip(self, opts))]
+    async fn quota(&self, opts: &RequestOptions) -> Result<Quota> {
+        let url = format!("{}/api/v1/config", self.base_url);
+
+        let request = self.request(Method::GET, &url);
+
+        let response = self.send(request, opts).await?;
+
+        let rate_limit = RateLimit::from_headers(response.headers());
+        // An exhausted quota is exactly what this reports, not an error
+        let limits = match response.status() {
+            StatusCode::NOT_FOUND | StatusCode::TOO_MANY_REQUESTS => Limits::default(),
+            status if status.is_success() => self.json(response).await?,
+            _ => return Err(ApiError::from_response(response).await),
+        };
+
+        Ok(Quota { rate_limit, limits })
+    }
+
+    #[instrument(level = "debug", skip(self, opts))]
+    async fn delete_account(&self, opts: &RequestOptions) -> Result<()> {
+        let url = format!("{}/api/v1/users/me", self.base_url);
+
+        let request = self.request(Method::DELETE, &url);
+
+        let response = self.send(request, opts).await?;
+
+        if matches!(
+            response.status(),
+            StatusCode::NOT_FOUND | StatusCode::METHOD_NOT_ALLOWED
+        ) {
+            return Err(ApiError::Unsupported("deleting accounts"));
+        }
+        if !response.status().is_success() {
+            return Err(ApiError::from_response(response).await);
+        }
+
+        Ok(())
+    }
+
+    #[instrument(level = "debug", skip(self, opts))]
+    async fn profile(&self, opts: &RequestOptions) -> Result<User> {
+        let url = format!("{}/api/v1/users/me", self.base_url);
+
+        let request = self.request(Method::GET, &url);
+
+        let response = self.send(request, opts).await?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            return Err(ApiError::Unsupported("user profiles"));
+        }
+        if !response.status().is_success() {
+            return Err(ApiError::from_response(response).await);
+        }
+
+        self.parse(response, Schema::User).await
+    }
+
+    #[instrument(level = "debug", skip(self, png, opts))]
+    async fn set_avatar(&self, png: Vec<u8>, opts: &RequestOptions) -> Result<()> {
+        let url = format!("{}/api/v1/users/me/avatar", self.base_url);
+
+        let part = reqwest::multipart::Part::bytes(png)
+            .file_name("avatar.png")
+            .mime_str("image/png")
+            .expect("image/png is a valid MIME type");
+        let form = reqwest::multipart::Form::new().part("avatar", part);
+        let request = self.request(Method::PUT, &url).multipart(form);
+
+        let response = self.send(request, opts).await?;
+
+        if matches!(
+            response.status(),
+            StatusCode::NOT_FOUND | StatusCode::METHOD_NOT_ALLOWED
+        ) {
+            return Err(ApiError::Unsupported("avatars"));
+        }
+        if !response.status().is_success() {
+            return Err(ApiError::from_response(response).await);
+        }
+
+        Ok(())
+    }
+
+    #[instrument(level = "debug", skip(self, opts))]
+    async fn remove_avatar(&self, opts: &RequestOptions) -> Result<()> {
+        let url = format!("{}/api/v1/users/me/avatar", self.base_url);
+
+        let request = self.request(Method::DELETE, &url);
+
+        let response = self.send(request, opts).await?;
+
+        if response.status() == StatusCode::METHOD_NOT_ALLOWED {
+            return Err(ApiError::Unsupported("avatars"));
+        }
+        // Removing an avatar that isn't set is not an error
+        if !response.status().is_success() && response.status() != StatusCode::NOT_FOUND {
+            return Err(ApiError::from_response(response).await);
+        }
+
+        Ok(())
+    }
+
+    #[instrument(level = "debug", skip(self, opts))]
+    async fn update_profile(
+        &self,
+        name: Option<&str>,
+        email: Option<&str>,
+        opts: &RequestOptions,
+    ) -> Result<User> {
+        let url = format!("{}/api/v1/users/me", self.base_url);
+
+        let request = self
+            .request(Method::PATCH, &url)
+            .json(&UpdateProfileRequest { name, email });
+
+        let response = self.send(request, opts).await?;
+
+        if matches!(
+            response.status(),
+            StatusCode::NOT_FOUND | StatusCode::METHOD_NOT_ALLOWED
+        ) {
+            return Err(ApiError::Unsupported("profile updates"));
+        }
+        if !response.status().is_success() {
+            return Err(ApiError::from_response(response).await);
+        }
+
+        self.parse(response, Schema::User).await
+    }
+
+    #[instrument(level = "debug", skip(self, opts))]
+    async fn list_workspaces(&self, opts: &RequestOptions) -> Result<Vec<Workspace>> {
+        let url = format!("{}/api/v1/workspaces", self.base_url);
+
+        let request = self.request(Method::GET, &url);
+
+        let response = self.send(request, opts).await?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            return Err(ApiError::Unsupported("workspaces"));
+        }
+        if !response.status().is_success() {
+            return Err(ApiError::from_response(response).await);
+        }
+
+        let page: Page<Workspace> = self.json(response).await?;
+        Ok(page.items)
+    }
+
+    #[instrument(level = "debug", skip(self, opts))]
+    async fn create_workspace(&self, name: &str, opts: &RequestOptions) -> Result<Workspace> {
+        let url = format!("{}/api/v1/workspaces", self.base_url);
+
+        let request = self
+            .request(Method::POST, &url)
+            .json(&CreateWorkspaceRequest { name });
+
+        let response = self.send(request, opts).await?;
+
+        if matches!(
+            response.status(),
+            StatusCode::NOT_FOUND | StatusCode::METHOD_NOT_ALLOWED
+        ) {
+            return Err(ApiError::Unsupported("workspaces"));
+        }
+        if !response.status().is_success() {
+            return Err(ApiError::from_response(response).await);
+        }
+
+        self.json(response).await
+    }
+
+    #[instrument(level = "debug", skip(self, opts))]
+    async fn workspace_members(&self, id: i64, opts: &RequestOptions) -> Result<Vec<Member>> {
+        let url = format!("{}/api/v1/workspaces/{}/members", self.base_url, id);
+
+        let request = self.request(Method::GET, &url);
+
+        let response = self.send(request, opts).await?;
+
+        if !response.status().is_success() {
+            return Err(ApiError::from_response(response).await);
+        }
+
+        let page: Page<Member> = self.json(response).await?;
+        Ok(page.items)
+    }
+
+    #[instrument(level = "debug", skip(self, opts))]
+    async fn invite_member(
+        &self,
+        workspace: i64,
+        email: &str,
+        role: Role,
+        opts: &RequestOptions,
+    ) -> Result<Invitation> {
+        let url = format!("{}/api/v1/workspaces/{}/invitations", self.base_url, workspace);
+
+        let request = self
+            .request(Method::POST, &url)
+            .json(&InviteRequest { email, role });
+
+        let response = self.send(request, opts).await?;
+
+        if response.status() == StatusCode::METHOD_NOT_ALLOWED {
+            return Err(ApiError::Unsupported("invitations"));
+        }
+        if response.status() == StatusCode::FORBIDDEN {
+            let mut error = ClientError::from_response(response).await;
+            error.message = "Only workspace admins can invite members".to_string();
+            return Err(ApiError::Forbidden(error));
+        }
+        if !response.status().is_success() {
+            return Err(ApiError::from_response(response).await);
+        }
+
+        self.json(response).await
+    }
+
+    #[instrument(level = "debug", skip(self, opts))]
+    async fn set_member_role(
+        &self,
+        workspace: i64,
+        user_id: i64,
+        role: Role,
+        opts: &RequestOptions,
+    ) -> Result<Member> {
+        let url = format!(
+            "{}/api/v1/workspaces/{}/members/{}",
+            self.base_url, workspace, user_id
+        );
+
+        let request = self.request(Method::PATCH, &url).json(&SetRoleRequest { role });
+
+        let response = self.send(request, opts).await?;
+
+        if response.status() == StatusCode::FORBIDDEN {
+            let mut error = ClientError::from_response(response).await;
+            error.message = "Only workspace admins can change roles".to_string();
+            return Err(ApiError::Forbidden(error));
+        }
+        if !response.status().is_success() {
+            return Err(ApiError::from_response(response).await);
+        }
+
+        self.json(response).await
+    }
+
+    #[instrument(level = "debug", skip(self, opts))]
+    async fn list_invitations(&self, opts: &RequestOptions) -> Result<Vec<Invitation>> {
+        let url = format!("{}/api/v1/invitations", self.base_url);
+
+        let request = self.request(Method::GET, &url);
+
+        let response = self.send(request, opts).await?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            return Err(ApiError::Unsupported("invitations"));
+        }
+        if !response.status().is_success() {
+            return Err(ApiError::from_response(response).await);
+        }
+
+        let page: Page<Invitation> = self.json(response).await?;
+        Ok(page.items)
+    }
+
+    #[instrument(level = "debug", skip(self, opts))]
+    async fn respond_to_invitation(
+        &self,
+        id: i64,
+        accept: bool,
+        opts: &RequestOptions,
+    ) -> Result<Invitation> {
+        let action = if accept { "accept" } else { "decline" };
+        let url = format!("{}/api/v1/invitations/{}/{}", self.base_url, id, action);
+
+        let request = self.request(Method::POST, &url);
+
+        let response = self.send(request, opts).await?;
+
+        if !response.status().is_success() {
+            return Err(ApiError::from_response(response).await);
+        }
+
+        self.json(response).await
+    }
+}
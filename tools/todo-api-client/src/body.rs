@@ -0,0 +1,345 @@
+//! Response bodies, read with a size cap so a misbehaving server can't
+//! exhaust memory, and list pages decoded item by item as they arrive
+//! rather than from one buffered body.
+
+use anyhow::{Result, bail};
+use reqwest::Response;
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+
+use crate::api::Page;
+use crate::error::ResponseTooLarge;
+
+/// Default cap on a response body, far above any real page.
+pub const DEFAULT_MAX_BYTES: usize = 16 * 1024 * 1024;
+
+/// Keys a page's collection may come under; see [`Page`].
+const ITEM_KEYS: &[&str] = &[
+    "items",
+    "todos",
+    "users",
+    "comments",
+    "sessions",
+    "workspaces",
+    "members",
+    "invitations",
+];
+
+/// Calls `f` with each chunk of the body as it arrives, failing as soon as
+/// more than `limit` bytes have been read.
+pub async fn read_chunks(
+    response: Response,
+    limit: usize,
+    mut f: impl FnMut(&[u8]) -> Result<()>,
+) -> Result<()> {
+    // No point reading what the server already says is too big
+    if response.content_length().is_some_and(|length| length > limit as u64) {
+        return Err(ResponseTooLarge { limit }.into());
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let mut response = response;
+        let mut read = 0;
+        while let Some(chunk) = response.chunk().await? {
+            read += chunk.len();
+            if read > limit {
+                return Err(ResponseTooLarge { limit }.into());
+            }
+            f(&chunk)?;
+        }
+    }
+    // fetch hands over the whole body at once
+    #[cfg(target_arch = "wasm32")]
+    {
+        let body = response.bytes().await?;
+        if body.len() > limit {
+            return Err(ResponseTooLarge { limit }.into());
+        }
+        f(&body)?;
+    }
+    Ok(())
+}
+
+/// The whole body, up to `limit` bytes.
+pub async fn read_to_vec(response: Response, limit: usize) -> Result<Vec<u8>> {
+    let mut body = Vec::new();
+    read_chunks(response, limit, |chunk| {
+        body.extend_from_slice(chunk);
+        Ok(())
+    })
+    .await?;
+    Ok(body)
+}
+
+/// Decodes a page pushed in arbitrary chunks. Each item is deserialized as
+/// soon as its last byte arrives, so only the item in flight is buffered;
+/// the other members (`total`, `next_cursor`) are small and kept as JSON.
+pub struct PageDecoder<T> {
+    state: State,
+    scanner: Scanner,
+    key: String,
+    items: Option<Vec<T>>,
+    fields: Map<String, Value>,
+}
+
+#[derive(Clone, Copy)]
+enum State {
+    /// Before the opening `{`
+    Start,
+    /// Expecting a key, or the closing `}`
+    Key,
+    InKey,
+    Colon,
+    /// Expecting a member value
+    Value,
+    InValue,
+    /// Inside the collection, expecting an item or the closing `]`
+    Items,
+    InItem,
+    /// After the closing `}`
+    End,
+}
+
+impl<T: DeserializeOwned> PageDecoder<T> {
+    pub fn new() -> Self {
+        Self {
+            state: State::Start,
+            scanner: Scanner::default(),
+            key: String::new(),
+            items: None,
+            fields: Map::new(),
+        }
+    }
+
+    pub fn push(&mut self, chunk: &[u8]) -> Result<()> {
+        chunk.iter().try_for_each(|&byte| self.byte(byte))
+    }
+
+    /// The decoded page, once the body has ended.
+    pub fn finish(mut self) -> Result<Page<T>> {
+        if !matches!(self.state, State::End) {
+            bail!("Response body ended in the middle of the page");
+        }
+        let items = self.items.take();
+        if items.is_some() {
+            self.fields.insert("items".to_string(), Value::Array(Vec::new()));
+        }
+        let mut page: Page<T> = serde_json::from_value(Value::Object(self.fields))?;
+        page.items = items.unwrap_or_default();
+        Ok(page)
+    }
+
+    fn byte(&mut self, byte: u8) -> Result<()> {
+        let whitespace = byte.is_ascii_whitespace();
+        match self.state {
+            State::Start if whitespace => {}
+            State::Start if byte == b'{' => self.state = State::Key,
+            State::Key if whitespace || byte == b',' => {}
+            State::Key if byte == b'}' => self.state = State::End,
+            State::Key if byte == b'"' => {
+                self.scanner.push(byte);
+                self.state = State::InKey;
+            }
+            State::InKey => {
+                if let Step::Done = self.scanner.push(byte) {
+                    self.key = serde_json::from_slice(&self.scanner.take())?;
+                    self.state = State::Colon;
+                }
+            }
+            State::Colon if whitespace => {}
+            State::Colon if byte == b':' => self.state = State::Value,
+            State::Value if whitespace => {}
+            State::Value if byte == b'[' && ITEM_KEYS.contains(&self.key.as_str()) => {
+                self.items.get_or_insert_with(Vec::new);
+                self.state = State::Items;
+            }
+            State::Value => {
+                self.state = State::InValue;
+                self.collect(byte)?;
+            }
+            State::Items if whitespace || byte == b',' => {}
+            State::Items if byte == b']' => self.state = State::Key,
+            State::Items => {
+                self.state = State::InItem;
+                self.collect(byte)?;
+            }
+            State::InValue | State::InItem => self.collect(byte)?,
+            State::End if whitespace => {}
+            _ => bail!("Unexpected '{}' in response body", byte.escape_ascii()),
+        }
+        Ok(())
+    }
+
+    fn collect(&mut self, byte: u8) -> Result<()> {
+        match self.scanner.push(byte) {
+            Step::More => Ok(()),
+            Step::Done => self.finish_value(),
+            // A number or literal ends at the delimiter after it, which
+            // belongs to the enclosing object or array
+            Step::Ended => {
+                self.finish_value()?;
+                self.byte(byte)
+            }
+        }
+    }
+
+    fn finish_value(&mut self) -> Result<()> {
+        let value = self.scanner.take();
+        if let State::InItem = self.state {
+            let item = serde_json::from_slice(&value)?;
+            self.items.get_or_insert_with(Vec::new).push(item);
+            self.state = State::Items;
+        } else {
+            let key = std::mem::take(&mut self.key);
+            self.fields.insert(key, serde_json::from_slice(&value)?);
+            self.state = State::Key;
+        }
+        Ok(())
+    }
+}
+
+/// Finds where one JSON value ends, buffering its bytes meanwhile.
+#[derive(Default)]
+struct Scanner {
+    buf: Vec<u8>,
+    depth: usize,
+    in_string: bool,
+    escaped: bool,
+}
+
+enum Step {
+    More,
+    /// The value ended with this byte
+    Done,
+    /// The value ended just before this byte, which isn't part of it
+    Ended,
+}
+
+impl Scanner {
+    fn push(&mut self, byte: u8) -> Step {
+        if self.in_string {
+            self.buf.push(byte);
+            if self.escaped {
+                self.escaped = false;
+            } else if byte == b'\\' {
+                self.escaped = true;
+            } else if byte == b'"' {
+                self.in_string = false;
+                if self.depth == 0 {
+                    return Step::Done;
+                }
+            }
+            return Step::More;
+        }
+        match byte {
+            b'"' => self.in_string = true,
+            b'{' | b'[' => self.depth += 1,
+            b'}' | b']' if self.depth > 0 => {
+                self.depth -= 1;
+                if self.depth == 0 {
+                    self.buf.push(byte);
+                    return Step::Done;
+                }
+            }
+            b',' | b'}' | b']' if self.depth == 0 => return Step::Ended,
+            _ if byte.is_ascii_whitespace() && self.depth == 0 => return Step::Ended,
+            _ => {}
+        }
+        self.buf.push(byte);
+        Step::More
+    }
+
+    fn take(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn decode(body: &str, chunk_size: usize) -> Result<Page<Value>> {
+        let mut decoder = PageDecoder::new();
+        for chunk in body.as_bytes().chunks(chunk_size) {
+            decoder.push(chunk)?;
+        }
+        decoder.finish()
+    }
+
+    #[test]
+    fn any_chunking_decodes_the_same_page() {
+        let body = r#" {
+            "items": [
+                {"id": 1, "title": "say \"hi\" \\ é {[,]}", "tags": ["a", "b"]},
+                {"id": -20, "estimate": 1.5e3, "done": true, "due": null}
+            ],
+            "total": 2,
+            "next_cursor": "c\"2"
+        } "#;
+        let items = [
+            json!({"id": 1, "title": "say \"hi\" \\ é {[,]}", "tags": ["a", "b"]}),
+            json!({"id": -20, "estimate": 1500.0, "done": true, "due": null}),
+        ];
+        for size in 1..=body.len() {
+            let page =
+                decode(body, size).unwrap_or_else(|err| panic!("chunks of {}: {}", size, err));
+            assert_eq!(page.items, items, "chunks of {}", size);
+            assert_eq!(page.total, 2);
+            assert_eq!(page.next_cursor.as_deref(), Some("c\"2"));
+        }
+    }
+
+    #[test]
+    fn a_number_may_end_right_at_the_closing_brace() {
+        let page = decode(r#"{"items":[{"id":7}],"total":1}"#, 1).unwrap();
+        assert_eq!(page.items, [json!({"id": 7})]);
+        assert_eq!(page.total, 1);
+
+        let page = decode(r#"{"total":2,"items":[3,4]}"#, 1).unwrap();
+        assert_eq!(page.items, [json!(3), json!(4)]);
+    }
+
+    #[test]
+    fn items_come_under_any_of_the_alias_keys() {
+        for key in ITEM_KEYS {
+            let body = format!(r#"{{"{}":[{{"id":1}},{{"id":2}}],"total":2}}"#, key);
+            let page = decode(&body, 3).unwrap();
+            assert_eq!(page.items.len(), 2, "{}", key);
+        }
+    }
+
+    #[test]
+    fn other_arrays_are_not_items() {
+        let page = decode(r#"{"warnings":["slow"],"items":[],"total":0}"#, 4).unwrap();
+        assert!(page.items.is_empty());
+    }
+
+    #[test]
+    fn a_truncated_body_is_an_error() {
+        let body = r#"{"items":[{"id":1,"title":"a\"b"}],"total":1}"#;
+        for end in 0..body.len() {
+            assert!(decode(&body[..end], 5).is_err(), "{:?}", &body[..end]);
+        }
+        assert!(decode(body, 5).is_ok());
+    }
+
+    #[test]
+    fn trailing_garbage_is_an_error() {
+        assert!(decode(r#"{"items":[],"total":0} x"#, 1).is_err());
+    }
+
+    #[tokio::test]
+    async fn bodies_over_the_cap_are_refused() {
+        let response = |body: &'static str| Response::from(http::Response::new(body));
+
+        let body = read_to_vec(response("12345678"), 8).await.unwrap();
+        assert_eq!(body, b"12345678");
+
+        let err = read_to_vec(response("123456789"), 8).await.unwrap_err();
+        let too_large = err.downcast_ref::<ResponseTooLarge>().expect("size error");
+        assert_eq!(too_large.limit, 8);
+    }
+}
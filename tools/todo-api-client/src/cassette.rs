@@ -0,0 +1,237 @@
+//! VCR-style record and replay of API traffic. Recording writes every
+//! request and response to a YAML cassette with credentials scrubbed;
+//! replaying answers requests from the cassette instead of the network, so
+//! tests can run against realistic server responses without a server.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Mutex, PoisonError};
+
+use anyhow::{Context, anyhow};
+use http::Extensions;
+use reqwest::{Request, Response};
+use reqwest_middleware::{Middleware, Next};
+use serde::{Deserialize, Serialize};
+
+/// Stands in for every scrubbed value.
+pub const REDACTED: &str = "REDACTED";
+
+/// Framing headers that stop matching once a body is scrubbed.
+const DROPPED_HEADERS: &[&str] = &["content-length", "transfer-encoding", "connection"];
+/// Headers whose values never reach a cassette.
+const SENSITIVE_HEADERS: &[&str] = &["authorization", "cookie", "set-cookie", "x-signature"];
+/// JSON fields whose values never reach a cassette, at any depth.
+const SENSITIVE_FIELDS: &[&str] = &[
+    "token",
+    "access_token",
+    "refresh_token",
+    "password",
+    "secret",
+    "api_key",
+];
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Tape {
+    interactions: Vec<Interaction>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Interaction {
+    pub request: RecordedRequest,
+    pub response: RecordedResponse,
+}
+
+/// Requests are matched on method and path (with query); the body is kept
+/// for reading the cassette, not for matching.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedResponse {
+    pub status: u16,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default)]
+    pub body: String,
+}
+
+enum Mode {
+    Record,
+    Replay,
+}
+
+/// Middleware that records to or replays from a cassette file. Add it last
+/// so it sits closest to the network, after signing and other layers.
+pub struct Cassette {
+    path: PathBuf,
+    mode: Mode,
+    /// Recorded so far, or still unplayed
+    interactions: Mutex<Vec<Interaction>>,
+}
+
+impl Cassette {
+    /// Records to `path`, replacing any cassette already there.
+    pub fn record(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            mode: Mode::Record,
+            interactions: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Replays `path`. Each interaction answers one request, in order among
+    /// those with the same method and path.
+    pub fn replay(path: impl Into<PathBuf>) -> anyhow::Result<Self> {
+        let path = path.into();
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read cassette {}", path.display()))?;
+        let tape: Tape = serde_yaml::from_str(&content)
+            .with_context(|| format!("Invalid cassette {}", path.display()))?;
+
+        Ok(Self {
+            path,
+            mode: Mode::Replay,
+            interactions: Mutex::new(tape.interactions),
+        })
+    }
+
+    fn play(&self, request: &RecordedRequest) -> anyhow::Result<Response> {
+        let mut interactions = self.interactions.lock().unwrap_or_else(PoisonError::into_inner);
+        let index = interactions
+            .iter()
+            .position(|recorded| {
+                recorded.request.method == request.method && recorded.request.path == request.path
+            })
+            .ok_or_else(|| {
+                anyhow!(
+                    "No recorded response for {} {} in {}",
+                    request.method,
+                    request.path,
+                    self.path.display()
+                )
+            })?;
+        let recorded = interactions.remove(index).response;
+
+        let mut response = http::Response::builder().status(recorded.status);
+        for (name, value) in &recorded.headers {
+            response = response.header(name, value);
+        }
+        let response = response
+            .body(recorded.body)
+            .context("Invalid response in cassette")?;
+        Ok(Response::from(response))
+    }
+
+    fn save(&self, interaction: Interaction) -> anyhow::Result<()> {
+        let mut interactions = self.interactions.lock().unwrap_or_else(PoisonError::into_inner);
+        interactions.push(interaction);
+        // Rewritten each time, so a crash mid-run keeps what was recorded
+        let tape = Tape {
+            interactions: interactions.clone(),
+        };
+        fs::write(&self.path, serde_yaml::to_string(&tape)?)
+            .with_context(|| format!("Failed to write cassette {}", self.path.display()))
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl Middleware for Cassette {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> reqwest_middleware::Result<Response> {
+        let request = RecordedRequest {
+            method: req.method().to_string(),
+            path: match req.url().query() {
+                Some(query) => format!("{}?{}", req.url().path(), query),
+                None => req.url().path().to_string(),
+            },
+            body: req
+                .body()
+                .and_then(|body| body.as_bytes())
+                .map(|body| scrub_body(&String::from_utf8_lossy(body))),
+        };
+
+        if matches!(self.mode, Mode::Replay) {
+            return self.play(&request).map_err(reqwest_middleware::Error::Middleware);
+        }
+
+        let response = next.run(req, extensions).await?;
+        let status = response.status();
+        let version = response.version();
+        let headers = response.headers().clone();
+        let body = response.bytes().await?;
+
+        let recorded = RecordedResponse {
+            status: status.as_u16(),
+            headers: headers
+                .iter()
+                .filter(|(name, _)| !DROPPED_HEADERS.contains(&name.as_str()))
+                .filter_map(|(name, value)| {
+                    let value = value.to_str().ok()?;
+                    Some((name.to_string(), scrub_header(name.as_str(), value)))
+                })
+                .collect(),
+            body: scrub_body(&String::from_utf8_lossy(&body)),
+        };
+        self.save(Interaction {
+            request,
+            response: recorded,
+        })
+        .map_err(reqwest_middleware::Error::Middleware)?;
+
+        // The body was consumed for the cassette, so hand back a copy
+        let mut rebuilt = http::Response::new(body);
+        *rebuilt.status_mut() = status;
+        *rebuilt.version_mut() = version;
+        *rebuilt.headers_mut() = headers;
+        Ok(Response::from(rebuilt))
+    }
+}
+
+/// Keeps a cookie's name so session logins still replay, but not its value.
+fn scrub_header(name: &str, value: &str) -> String {
+    if !SENSITIVE_HEADERS.contains(&name) {
+        return value.to_string();
+    }
+    match (name, value.split_once('=')) {
+        ("set-cookie", Some((cookie, _))) => format!("{}={}", cookie, REDACTED),
+        _ => REDACTED.to_string(),
+    }
+}
+
+/// Replaces sensitive fields in a JSON body; other bodies are kept as is.
+fn scrub_body(body: &str) -> String {
+    match serde_json::from_str::<serde_json::Value>(body) {
+        Ok(mut json) => {
+            scrub_json(&mut json);
+            json.to_string()
+        }
+        Err(_) => body.to_string(),
+    }
+}
+
+fn scrub_json(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(fields) => {
+            for (key, value) in fields {
+                if SENSITIVE_FIELDS.contains(&key.as_str()) && value.is_string() {
+                    *value = REDACTED.into();
+                } else {
+                    scrub_json(value);
+                }
+            }
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(scrub_json),
+        _ => {}
+    }
+}
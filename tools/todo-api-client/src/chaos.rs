@@ -0,0 +1,159 @@
+//! Fault injection for resilience testing. Outbound requests are delayed
+//! and some fail with a synthetic error response before reaching the
+//! network, so retry handling can be exercised without a misbehaving server.
+//! With a fixed seed the same requests fail on every run.
+
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use anyhow::{Context, anyhow, bail};
+use http::Extensions;
+use reqwest::{Request, Response, StatusCode};
+use reqwest_middleware::{Middleware, Next};
+
+/// Added to the generator state per draw (splitmix64's increment).
+const GAMMA: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// What to inject, parsed from `p=0.2,latency=500ms,status=503,seed=42`.
+/// Every key is optional.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Faults {
+    /// Chance in `0..=1` that a request fails instead of being sent
+    pub probability: f64,
+    /// Each request waits a random time up to this before going out
+    pub latency: Duration,
+    /// Status of the injected failures; a retryable one by default
+    pub status: StatusCode,
+    /// Fixes the sequence of delays and failures; random when unset
+    pub seed: Option<u64>,
+}
+
+impl Default for Faults {
+    fn default() -> Self {
+        Self {
+            probability: 0.0,
+            latency: Duration::ZERO,
+            status: StatusCode::SERVICE_UNAVAILABLE,
+            seed: None,
+        }
+    }
+}
+
+impl FromStr for Faults {
+    type Err = anyhow::Error;
+
+    fn from_str(spec: &str) -> anyhow::Result<Self> {
+        let mut faults = Self::default();
+        for pair in spec.split(',').map(str::trim).filter(|pair| !pair.is_empty()) {
+            let (key, value) = pair
+                .split_once('=')
+                .ok_or_else(|| anyhow!("Expected key=value, got '{}'", pair))?;
+            match key.trim() {
+                "p" => {
+                    faults.probability = value.parse().context("p must be a number")?;
+                    if !(0.0..=1.0).contains(&faults.probability) {
+                        bail!("p must be between 0 and 1");
+                    }
+                }
+                "latency" => faults.latency = parse_duration(value)?,
+                "status" => {
+                    faults.status = value
+                        .parse::<u16>()
+                        .ok()
+                        .and_then(|code| StatusCode::from_u16(code).ok())
+                        .filter(|status| status.is_client_error() || status.is_server_error())
+                        .ok_or_else(|| anyhow!("status must be an HTTP error status"))?;
+                }
+                "seed" => faults.seed = Some(value.parse().context("seed must be an integer")?),
+                other => bail!(
+                    "Unknown chaos setting '{}' (expected p, latency, status or seed)",
+                    other
+                ),
+            }
+        }
+        Ok(faults)
+    }
+}
+
+/// `500ms` or `2s`; a bare number is milliseconds.
+fn parse_duration(value: &str) -> anyhow::Result<Duration> {
+    let value = value.trim();
+    let (number, scale) = if let Some(ms) = value.strip_suffix("ms") {
+        (ms, 1)
+    } else if let Some(secs) = value.strip_suffix('s') {
+        (secs, 1000)
+    } else {
+        (value, 1)
+    };
+    let number: u64 = number
+        .parse()
+        .map_err(|_| anyhow!("latency must look like 500ms or 2s, got '{}'", value))?;
+    Ok(Duration::from_millis(number * scale))
+}
+
+/// Middleware that injects [`Faults`]. Add it after signing and other
+/// layers that shape the request, and before a cassette so replays can be
+/// run under chaos too.
+pub struct Chaos {
+    faults: Faults,
+    state: AtomicU64,
+}
+
+impl Chaos {
+    pub fn new(faults: Faults) -> Self {
+        let seed = faults.seed.unwrap_or_else(|| {
+            chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default() as u64
+        });
+        Self {
+            faults,
+            state: AtomicU64::new(seed),
+        }
+    }
+
+    /// The next number in `0..1` from a splitmix64 sequence.
+    fn next(&self) -> f64 {
+        let mut z = self.state.fetch_add(GAMMA, Ordering::Relaxed).wrapping_add(GAMMA);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+        (z >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    fn failure(&self) -> Response {
+        let body = serde_json::json!({
+            "error": format!("Injected fault (chaos mode): {}", self.faults.status),
+        });
+        let response = http::Response::builder()
+            .status(self.faults.status)
+            .header(http::header::CONTENT_TYPE, "application/json")
+            .body(body.to_string())
+            .expect("static response is valid");
+        Response::from(response)
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl Middleware for Chaos {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> reqwest_middleware::Result<Response> {
+        // Both draws happen for every request, so the failures a seed picks
+        // don't shift when only the latency changes
+        let delay = self.faults.latency.mul_f64(self.next());
+        let fail = self.next() < self.faults.probability;
+
+        if !delay.is_zero() {
+            futures_timer::Delay::new(delay).await;
+        }
+        if fail {
+            tracing::debug!(method = %req.method(), url = %req.url(), "Injecting failure");
+            return Ok(self.failure());
+        }
+        next.run(req, extensions).await
+    }
+}
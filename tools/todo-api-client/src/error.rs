@@ -0,0 +1,225 @@
+use reqwest::{Response, StatusCode};
+use serde::Deserialize;
+
+use crate::body;
+use crate::read_only::ReadOnlyViolation;
+
+/// A single field that failed server-side validation.
+#[derive(Debug, Clone, Deserialize)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+/// Error returned by the API for a non-success response, carrying whatever
+/// metadata the server provided alongside the message.
+#[derive(Debug, thiserror::Error)]
+#[error("{message}")]
+pub struct ClientError {
+    pub status: StatusCode,
+    pub code: Option<String>,
+    pub message: String,
+    pub fields: Vec<FieldError>,
+    pub request_id: Option<String>,
+}
+
+/// What a call to the API failed with, by kind, so callers can react to a
+/// missing todo or a rejected token without reading messages. The
+/// server's status, code, field errors and request ID stay available
+/// through [`ApiError::client_error`].
+#[derive(Debug, thiserror::Error)]
+pub enum ApiError {
+    /// 401: the credentials are missing, expired or were rejected
+    #[error("{0}")]
+    Unauthorized(ClientError),
+    /// 403: signed in, but not allowed to do this
+    #[error("{0}")]
+    Forbidden(ClientError),
+    /// 404, with the todo's ID when the request was about one
+    #[error("{}", not_found(.id, .error))]
+    NotFound { id: Option<i64>, error: ClientError },
+    /// 400 or 422: the input was rejected, field by field in `fields`
+    #[error("{0}")]
+    Validation(ClientError),
+    /// 409 or 412: another write got there first
+    #[error("{0}")]
+    Conflict(ClientError),
+    /// 429: over the rate limit
+    #[error("{0}")]
+    RateLimited(ClientError),
+    /// A server failure, or any status not covered above
+    #[error("{0}")]
+    Server(ClientError),
+    /// The server has no endpoint for this, or ignores the field
+    #[error("This server doesn't support {0}")]
+    Unsupported(&'static str),
+    /// Read-only mode refused to send a request that could change something
+    #[error("{0}")]
+    ReadOnly(ReadOnlyViolation),
+    /// No response arrived: DNS, connection, TLS or timeout failures, and
+    /// middleware that refused to send the request
+    #[error("Request failed")]
+    Network(#[source] reqwest_middleware::Error),
+    /// A success response that couldn't be read or parsed
+    #[error("Invalid response: {0:#}")]
+    InvalidResponse(anyhow::Error),
+}
+
+fn not_found(id: &Option<i64>, error: &ClientError) -> String {
+    match id {
+        Some(id) => format!("Todo #{} not found", id),
+        None => error.message.clone(),
+    }
+}
+
+impl ApiError {
+    /// Classifies a failure response by its status.
+    pub async fn from_response(response: Response) -> Self {
+        let error = ClientError::from_response(response).await;
+        match error.status {
+            StatusCode::BAD_REQUEST | StatusCode::UNPROCESSABLE_ENTITY => {
+                ApiError::Validation(error)
+            }
+            StatusCode::UNAUTHORIZED => ApiError::Unauthorized(error),
+            StatusCode::FORBIDDEN => ApiError::Forbidden(error),
+            StatusCode::NOT_FOUND => ApiError::NotFound { id: None, error },
+            StatusCode::CONFLICT | StatusCode::PRECONDITION_FAILED => ApiError::Conflict(error),
+            StatusCode::TOO_MANY_REQUESTS => ApiError::RateLimited(error),
+            _ => ApiError::Server(error),
+        }
+    }
+
+    /// Names the todo a 404 was about.
+    pub fn for_todo(self, todo: i64) -> Self {
+        match self {
+            ApiError::NotFound { error, .. } => ApiError::NotFound {
+                id: Some(todo),
+                error,
+            },
+            other => other,
+        }
+    }
+
+    /// The server's response, for errors that came with one.
+    pub fn client_error(&self) -> Option<&ClientError> {
+        match self {
+            ApiError::Unauthorized(error)
+            | ApiError::Forbidden(error)
+            | ApiError::NotFound { error, .. }
+            | ApiError::Validation(error)
+            | ApiError::Conflict(error)
+            | ApiError::RateLimited(error)
+            | ApiError::Server(error) => Some(error),
+            ApiError::Unsupported(_)
+            | ApiError::ReadOnly(_)
+            | ApiError::Network(_)
+            | ApiError::InvalidResponse(_) => None,
+        }
+    }
+
+    /// Field errors the server gave for rejected input.
+    pub fn fields(&self) -> &[FieldError] {
+        match self.client_error() {
+            Some(error) => &error.fields,
+            None => &[],
+        }
+    }
+
+    /// Whether another attempt might succeed: transport failures, rate
+    /// limiting and server errors.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            ApiError::Network(_) => true,
+            other => other.client_error().is_some_and(ClientError::is_retryable),
+        }
+    }
+}
+
+impl From<reqwest_middleware::Error> for ApiError {
+    fn from(err: reqwest_middleware::Error) -> Self {
+        match err {
+            reqwest_middleware::Error::Middleware(err) => match err.downcast() {
+                Ok(violation) => ApiError::ReadOnly(violation),
+                Err(err) => ApiError::Network(reqwest_middleware::Error::Middleware(err)),
+            },
+            err => ApiError::Network(err),
+        }
+    }
+}
+
+/// The result of an API call.
+pub type Result<T, E = ApiError> = std::result::Result<T, E>;
+
+/// A response body bigger than the client's limit; the rest of it was
+/// never read.
+#[derive(Debug, thiserror::Error)]
+#[error("Response body exceeds the {limit}-byte limit")]
+pub struct ResponseTooLarge {
+    pub limit: usize,
+}
+
+/// Error bodies are a line or two of JSON; anything longer is ignored.
+const MAX_ERROR_BYTES: usize = 64 * 1024;
+
+/// Error bodies come in two shapes: the handlers' `{"error": ...}` and the
+/// apperrors `{"code", "message", "details"}` envelope.
+#[derive(Debug, Default, Deserialize)]
+struct ErrorBody {
+    #[serde(default)]
+    error: Option<String>,
+    #[serde(default)]
+    code: Option<String>,
+    #[serde(default)]
+    message: Option<String>,
+    #[serde(default)]
+    details: Option<String>,
+    #[serde(default, alias = "errors")]
+    fields: Vec<FieldError>,
+}
+
+impl ClientError {
+    pub async fn from_response(response: Response) -> Self {
+        let status = response.status();
+        let request_id = response
+            .headers()
+            .get("X-Request-ID")
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+
+        let body: ErrorBody = body::read_to_vec(response, MAX_ERROR_BYTES)
+            .await
+            .ok()
+            .and_then(|body| serde_json::from_slice(&body).ok())
+            .unwrap_or_default();
+        let message = body
+            .error
+            .or(body.message)
+            .or(body.details)
+            .unwrap_or_else(|| {
+                status
+                    .canonical_reason()
+                    .unwrap_or("Unknown error")
+                    .to_string()
+            });
+
+        Self {
+            status,
+            code: body.code,
+            message,
+            fields: body.fields,
+            request_id,
+        }
+    }
+
+    pub fn is_retryable(&self) -> bool {
+        is_retryable_status(self.status)
+    }
+}
+
+/// Timeouts, rate limiting and server-side failures are worth another attempt;
+/// other client errors will fail the same way again.
+pub fn is_retryable_status(status: StatusCode) -> bool {
+    status == StatusCode::REQUEST_TIMEOUT
+        || status == StatusCode::TOO_MANY_REQUESTS
+        || status.is_server_error()
+}
@@ -0,0 +1,43 @@
+//! Typed bindings for the go-api-starter REST API.
+//!
+//! [`api::ApiClient`] talks to the server; commands written against the
+//! [`api::TodoApi`] trait can run against an in-memory fake in tests.
+//! Requests take [`api::RequestOptions`] for per-call timeouts, retries
+//! and idempotency keys, and fail with an [`error::ApiError`] that says
+//! what kind of failure it was and carries whatever the server said.
+//!
+//! ```no_run
+//! use todo_api_client::api::{ApiClient, RequestOptions, TodoApi};
+//!
+//! # async fn run() -> anyhow::Result<()> {
+//! let client = ApiClient::builder("http://localhost:8080").build()?;
+//! let auth = client
+//!     .login("ada@example.com", "hunter2", &RequestOptions::default())
+//!     .await?;
+//! let client = client.with_token(auth.token);
+//! for todo in client.list_todos(Some(false), &RequestOptions::new().retries(2)).await? {
+//!     println!("#{} {}", todo.id, todo.title);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The middleware modules plug into [`api::ApiClientBuilder::middleware`]:
+//! request signing, read-only mode, and behind features, record/replay
+//! (`vcr`) and fault injection (`chaos`). Built without default features
+//! the crate also compiles for `wasm32-unknown-unknown` (browsers,
+//! Cloudflare Workers).
+
+pub mod api;
+mod body;
+#[cfg(feature = "chaos")]
+pub mod chaos;
+#[cfg(feature = "vcr")]
+pub mod cassette;
+pub mod error;
+pub mod read_only;
+pub mod schema;
+pub mod secret;
+pub mod signing;
+mod trace;
+pub mod validation;
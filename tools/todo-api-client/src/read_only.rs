@@ -0,0 +1,50 @@
+//! Read-only mode, for pointing the CLI at a production tenant to look
+//! around: every request that could change something fails before it is
+//! sent.
+
+use http::Extensions;
+use reqwest::{Method, Request, Response};
+use reqwest_middleware::{Middleware, Next};
+
+/// Refused by [`ReadOnly`]; the command never reached the server.
+#[derive(Debug, thiserror::Error)]
+#[error("Read-only mode: refusing to send {method} {path}")]
+pub struct ReadOnlyViolation {
+    pub method: Method,
+    pub path: String,
+}
+
+/// Lets reads and logging in through and fails everything else. Runs as
+/// the first middleware so nothing downstream sees a refused request.
+pub struct ReadOnly;
+
+impl ReadOnly {
+    /// Logging in changes nothing on the server, so a token can still be
+    /// fetched for a read-only session.
+    fn allows(method: &Method, path: &str) -> bool {
+        matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS)
+            || (*method == Method::POST && path.ends_with("/auth/login"))
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl Middleware for ReadOnly {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> reqwest_middleware::Result<Response> {
+        if !Self::allows(req.method(), req.url().path()) {
+            return Err(reqwest_middleware::Error::Middleware(
+                ReadOnlyViolation {
+                    method: req.method().clone(),
+                    path: req.url().path().to_string(),
+                }
+                .into(),
+            ));
+        }
+        next.run(req, extensions).await
+    }
+}
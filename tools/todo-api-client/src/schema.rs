@@ -0,0 +1,69 @@
+//! Validation of API responses against the JSON Schemas bundled in
+//! `schemas/`, which are generated from `docs/openapi.yaml`.
+//!
+//! A mismatch is reported as a list of offending JSON pointers rather than
+//! the first serde error, which makes server/spec drift easy to pin down.
+
+use serde_json::Value;
+
+/// The response shapes the client knows how to check.
+#[derive(Debug, Clone, Copy)]
+pub enum Schema {
+    Todo,
+    TodoList,
+    LoginResponse,
+    User,
+}
+
+impl Schema {
+    pub fn name(self) -> &'static str {
+        match self {
+            Schema::Todo => "Todo",
+            Schema::TodoList => "TodoListResponse",
+            Schema::LoginResponse => "LoginResponse",
+            Schema::User => "UserResponse",
+        }
+    }
+
+    fn source(self) -> &'static str {
+        match self {
+            Schema::Todo => include_str!("../schemas/todo.json"),
+            Schema::TodoList => include_str!("../schemas/todo_list.json"),
+            Schema::LoginResponse => include_str!("../schemas/login_response.json"),
+            Schema::User => include_str!("../schemas/user.json"),
+        }
+    }
+}
+
+/// A response body that does not conform to its schema.
+#[derive(Debug, thiserror::Error)]
+#[error("Response does not match the {schema} schema:\n{}", .violations.join("\n"))]
+pub struct SchemaDrift {
+    pub schema: &'static str,
+    pub violations: Vec<String>,
+}
+
+pub fn validate(schema: Schema, body: &Value) -> Result<(), SchemaDrift> {
+    let definition: Value =
+        serde_json::from_str(schema.source()).expect("bundled schema is valid JSON");
+    let validator =
+        jsonschema::validator_for(&definition).expect("bundled schema is a valid JSON Schema");
+
+    let violations: Vec<String> = validator
+        .iter_errors(body)
+        .map(|error| {
+            let path = error.instance_path.to_string();
+            let path = if path.is_empty() { "/".to_string() } else { path };
+            format!("  • {}: {}", path, error)
+        })
+        .collect();
+
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(SchemaDrift {
+            schema: schema.name(),
+            violations,
+        })
+    }
+}
@@ -0,0 +1,69 @@
+//! A tracing span around every HTTP call. With the `otel` feature the
+//! span's context also goes out as `traceparent`, so the API's spans join
+//! the client's trace.
+
+use http::Extensions;
+use reqwest::{Request, Response};
+use reqwest_middleware::{Middleware, Next};
+use tracing::Instrument;
+
+/// Opens an `HTTP request` span named after the method, recording the URL
+/// and the response status.
+pub struct Spans;
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl Middleware for Spans {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> reqwest_middleware::Result<Response> {
+        let span = tracing::info_span!(
+            "HTTP request",
+            otel.name = %req.method(),
+            otel.kind = "client",
+            http.request.method = %req.method(),
+            url.full = %req.url(),
+            http.response.status_code = tracing::field::Empty,
+        );
+        #[cfg(feature = "otel")]
+        let req = propagate(&span, req);
+
+        let result = next.run(req, extensions).instrument(span.clone()).await;
+        match result {
+            Ok(ref response) => {
+                span.record("http.response.status_code", response.status().as_u16());
+            }
+            Err(ref err) => span.in_scope(|| tracing::debug!(error = %err, "Request failed")),
+        }
+        result
+    }
+}
+
+/// Adds `span`'s trace context to `req` through the global propagator,
+/// which the application sets up along with its exporter.
+#[cfg(feature = "otel")]
+fn propagate(span: &tracing::Span, mut req: Request) -> Request {
+    use opentelemetry::propagation::Injector;
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+    struct Headers<'a>(&'a mut http::HeaderMap);
+
+    impl Injector for Headers<'_> {
+        fn set(&mut self, key: &str, value: String) {
+            let name = http::HeaderName::from_bytes(key.as_bytes());
+            let value = http::HeaderValue::from_str(&value);
+            if let (Ok(name), Ok(value)) = (name, value) {
+                self.0.insert(name, value);
+            }
+        }
+    }
+
+    let context = span.context();
+    opentelemetry::global::get_text_map_propagator(|propagator| {
+        propagator.inject_context(&context, &mut Headers(req.headers_mut()));
+    });
+    req
+}
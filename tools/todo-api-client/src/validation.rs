@@ -0,0 +1,98 @@
+//! Client-side checks mirroring the server's validation rules, so bad input
+//! fails instantly instead of after a 400 round-trip.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::error::FieldError;
+
+/// Input limits advertised by the server's `/config` endpoint. Servers that
+/// don't expose one get the defaults, which match the Go model's tags.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Limits {
+    pub max_title_length: usize,
+    pub max_tag_length: usize,
+    /// Plan the account is on, from servers with per-plan quotas
+    pub plan: Option<String>,
+    /// Request quota for that plan
+    pub requests_per_minute: Option<u64>,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_title_length: 255,
+            max_tag_length: 50,
+            plan: None,
+            requests_per_minute: None,
+        }
+    }
+}
+
+/// One or more fields failed client-side validation.
+#[derive(Debug, thiserror::Error)]
+#[error("Invalid input")]
+pub struct InvalidInput {
+    pub fields: Vec<FieldError>,
+}
+
+/// The user-supplied parts of a create or update request.
+#[derive(Debug, Default)]
+pub struct TodoInput<'a> {
+    pub title: Option<&'a str>,
+    pub due: Option<DateTime<Utc>>,
+    pub tags: &'a [String],
+}
+
+impl TodoInput<'_> {
+    pub fn validate(&self, limits: &Limits) -> Result<(), InvalidInput> {
+        let mut fields = Vec::new();
+        let mut reject = |field: &str, message: String| {
+            fields.push(FieldError {
+                field: field.to_string(),
+                message,
+            })
+        };
+
+        if let Some(title) = self.title {
+            if title.trim().is_empty() {
+                reject("title", "must not be empty".to_string());
+            } else if title.chars().count() > limits.max_title_length {
+                reject(
+                    "title",
+                    format!("must be at most {} characters", limits.max_title_length),
+                );
+            }
+        }
+
+        if let Some(due) = self.due
+            && due < Utc::now()
+        {
+            reject("due", format!("{} is in the past", due.format("%Y-%m-%d %H:%M")));
+        }
+
+        for tag in self.tags {
+            if tag.is_empty() || tag.chars().count() > limits.max_tag_length {
+                reject(
+                    "tags",
+                    format!("'{}' must be 1-{} characters", tag, limits.max_tag_length),
+                );
+            } else if !tag
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+            {
+                reject(
+                    "tags",
+                    format!("'{}' may only contain letters, digits, '-' and '_'", tag),
+                );
+            }
+        }
+
+        if fields.is_empty() {
+            Ok(())
+        } else {
+            Err(InvalidInput { fields })
+        }
+    }
+}
@@ -0,0 +1,80 @@
+//! Runs the client against recorded server responses from `fixtures/`.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use reqwest::StatusCode;
+use todo_api_client::api::{ApiClient, RequestOptions, TodoApi};
+use todo_api_client::cassette::{Cassette, REDACTED};
+use todo_api_client::error::{ApiError, ResponseTooLarge};
+
+fn replay(cassette: &str) -> ApiClient {
+    replay_with_limit(cassette, None)
+}
+
+fn replay_with_limit(cassette: &str, max_response_bytes: Option<usize>) -> ApiClient {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(cassette);
+    ApiClient::builder("http://recorded.invalid")
+        .middleware(Arc::new(Cassette::replay(path).expect("cassette loads")))
+        .max_response_bytes(max_response_bytes)
+        .build()
+        .expect("client builds")
+}
+
+#[tokio::test]
+async fn login_replays_scrubbed_token() {
+    let client = replay("todos.yaml");
+    let auth = client
+        .login("ada@example.com", "hunter2", &RequestOptions::default())
+        .await
+        .unwrap();
+
+    assert_eq!(auth.token.expose(), REDACTED);
+    assert_eq!(auth.user_id, Some(1));
+}
+
+#[tokio::test]
+async fn list_follows_recorded_pages() {
+    let client = replay("todos.yaml");
+    let todos = client.list_todos(None, &RequestOptions::default()).await.unwrap();
+
+    let titles: Vec<_> = todos.iter().map(|todo| todo.title.as_str()).collect();
+    assert_eq!(titles, ["Write quarterly report", "Book flights"]);
+}
+
+#[tokio::test]
+async fn recorded_errors_keep_status_and_request_id() {
+    let client = replay("todos.yaml");
+    let err = client.get_todo(42, &RequestOptions::default()).await.unwrap_err();
+
+    assert_eq!(err.to_string(), "Todo #42 not found");
+    let ApiError::NotFound { id: Some(42), error: err } = err else {
+        panic!("expected NotFound for #42, got {:?}", err);
+    };
+    assert_eq!(err.status, StatusCode::NOT_FOUND);
+    assert_eq!(err.message, "todo not found");
+    assert_eq!(err.request_id.as_deref(), Some("0a9b8c7d-6e5f-4a3b-2c1d-0e9f8a7b6c5d"));
+}
+
+#[tokio::test]
+async fn unrecorded_request_fails() {
+    let client = replay("todos.yaml");
+    let result = client.get_todo(7, &RequestOptions::default()).await;
+
+    let err = format!("{:#}", anyhow::Error::from(result.unwrap_err()));
+    assert!(err.contains("No recorded response for GET /api/v1/todos/7"), "{}", err);
+}
+
+#[tokio::test]
+async fn oversized_response_is_refused() {
+    let client = replay_with_limit("todos.yaml", Some(64));
+    let err = client.list_todos(None, &RequestOptions::default()).await.unwrap_err();
+
+    let ApiError::InvalidResponse(err) = err else {
+        panic!("expected InvalidResponse, got {:?}", err);
+    };
+    let err = err.downcast_ref::<ResponseTooLarge>().expect("a ResponseTooLarge");
+    assert_eq!(err.limit, 64);
+}
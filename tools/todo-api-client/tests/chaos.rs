@@ -0,0 +1,79 @@
+//! Fault injection in front of recorded responses, so failures are the only
+//! thing that changes between runs.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use reqwest::StatusCode;
+use todo_api_client::api::{ApiClient, RequestOptions, TodoApi};
+use todo_api_client::cassette::Cassette;
+use todo_api_client::chaos::{Chaos, Faults};
+
+fn fixture() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/todos.yaml")
+}
+
+fn replay_with(spec: &str) -> ApiClient {
+    let faults: Faults = spec.parse().expect("valid chaos spec");
+    ApiClient::builder("http://recorded.invalid")
+        .middleware(Arc::new(Chaos::new(faults)))
+        .middleware(Arc::new(Cassette::replay(fixture()).expect("cassette loads")))
+        .build()
+        .expect("client builds")
+}
+
+#[test]
+fn parses_spec() {
+    let faults: Faults = "p=0.2, latency=500ms, status=429, seed=42".parse().unwrap();
+    assert_eq!(faults.probability, 0.2);
+    assert_eq!(faults.latency.as_millis(), 500);
+    assert_eq!(faults.status, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(faults.seed, Some(42));
+
+    assert!("p=2".parse::<Faults>().is_err());
+    assert!("status=200".parse::<Faults>().is_err());
+    assert!("jitter=5ms".parse::<Faults>().is_err());
+}
+
+#[tokio::test]
+async fn no_failures_pass_through() {
+    let client = replay_with("p=0,latency=5ms,seed=1");
+    let todos = client.list_todos(None, &RequestOptions::default()).await.unwrap();
+    assert_eq!(todos.len(), 2);
+}
+
+#[tokio::test]
+async fn injected_failures_survive_retries() {
+    let client = replay_with("p=1,status=503,seed=1");
+    let opts = RequestOptions::default().retries(2);
+    let err = client.list_todos(None, &opts).await.unwrap_err();
+
+    assert!(err.is_retryable());
+    let err = err.client_error().expect("a server response");
+    assert_eq!(err.status, StatusCode::SERVICE_UNAVAILABLE);
+    assert!(err.message.contains("Injected fault"), "{}", err.message);
+}
+
+#[tokio::test]
+async fn same_seed_fails_the_same_requests() {
+    async fn outcomes(seed: u64) -> Vec<bool> {
+        let faults: Faults = format!("p=0.5,seed={}", seed).parse().unwrap();
+        let chaos = Arc::new(Chaos::new(faults));
+        let mut outcomes = Vec::new();
+        for _ in 0..8 {
+            // A fresh cassette each time, since replay uses up interactions
+            let client = ApiClient::builder("http://recorded.invalid")
+                .middleware(chaos.clone())
+                .middleware(Arc::new(Cassette::replay(fixture()).expect("cassette loads")))
+                .build()
+                .expect("client builds");
+            let result = client
+                .login("ada@example.com", "hunter2", &RequestOptions::default())
+                .await;
+            outcomes.push(result.is_ok());
+        }
+        outcomes
+    }
+
+    assert_eq!(outcomes(7).await, outcomes(7).await);
+}
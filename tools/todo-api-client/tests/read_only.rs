@@ -0,0 +1,46 @@
+//! Read-only mode refuses writes before they leave the client, and a
+//! refusal isn't retried.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use http::Extensions;
+use reqwest::{Request, Response};
+use reqwest_middleware::{Middleware, Next};
+use todo_api_client::api::{ApiClient, RequestOptions, TodoApi};
+use todo_api_client::error::ApiError;
+use todo_api_client::read_only::ReadOnly;
+
+/// Counts the attempts that reach it, in front of [`ReadOnly`].
+#[derive(Default)]
+struct Attempts(AtomicUsize);
+
+#[async_trait::async_trait]
+impl Middleware for Attempts {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> reqwest_middleware::Result<Response> {
+        self.0.fetch_add(1, Ordering::SeqCst);
+        next.run(req, extensions).await
+    }
+}
+
+#[tokio::test]
+async fn refused_writes_are_not_retried() {
+    let attempts = Arc::new(Attempts::default());
+    let client = ApiClient::builder("http://read-only.invalid")
+        .middleware(attempts.clone())
+        .middleware(Arc::new(ReadOnly))
+        .build()
+        .expect("client builds");
+
+    let opts = RequestOptions::new().retries(3);
+    let err = client.create_todo("Ship it", &opts).await.unwrap_err();
+
+    assert!(matches!(err, ApiError::ReadOnly(_)), "{:?}", err);
+    assert!(!err.is_retryable());
+    assert_eq!(attempts.0.load(Ordering::SeqCst), 1);
+}
@@ -0,0 +1,243 @@
+use std::fs::File;
+use std::io::{Cursor, Write};
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+use serde::Serialize;
+use serde_json::json;
+use zip::ZipWriter;
+use zip::write::SimpleFileOptions;
+
+use crate::aliases::Aliases;
+use crate::api::{RequestOptions, TodoApi, User};
+use crate::config::Config;
+use crate::style::Colorize;
+use crate::{history, output, prompt, session};
+
+/// Files larger than this are rejected before decoding.
+const MAX_AVATAR_BYTES: u64 = 10 * 1024 * 1024;
+/// Avatars are scaled down to fit this square before upload.
+const AVATAR_SIZE: u32 = 512;
+const MIN_AVATAR_SIZE: u32 = 32;
+
+/// Describes an export archive and what it does and doesn't contain.
+#[derive(Debug, Serialize)]
+struct Manifest<'a> {
+    exported_at: String,
+    source: &'a str,
+    /// Email used to log in from this machine, if remembered
+    email: Option<&'a str>,
+    client_version: &'a str,
+    todos: usize,
+    /// Data the API has no endpoint for, so the archive can't include it
+    not_available: &'a [&'a str],
+}
+
+/// Assembles everything the API exposes for the current account into a zip
+/// archive at `path`. There is no server-side export endpoint, so the
+/// archive is built client-side.
+pub async fn export<A: TodoApi>(client: &A, config: &Config, path: &Path) -> Result<()> {
+    let todos = client
+        .list_todos(None, &RequestOptions::new().retries(2))
+        .await
+        .context("Failed to fetch todos")?;
+
+    let manifest = Manifest {
+        exported_at: chrono::Utc::now().to_rfc3339(),
+        source: client.base_url(),
+        email: config.last_email.as_deref(),
+        client_version: crate::api::CLIENT_VERSION,
+        todos: todos.len(),
+        not_available: &["profile", "comments", "attachments"],
+    };
+
+    let file = File::create(path)
+        .with_context(|| format!("Failed to create {}", path.display()))?;
+    let mut archive = ZipWriter::new(file);
+    let options = SimpleFileOptions::default();
+
+    archive.start_file("manifest.json", options)?;
+    archive.write_all(&serde_json::to_vec_pretty(&manifest)?)?;
+    archive.start_file("todos.json", options)?;
+    archive.write_all(&serde_json::to_vec_pretty(&todos)?)?;
+    archive.finish().context("Failed to finish archive")?;
+
+    output::success(&format!(
+        "Exported {} todos to {}",
+        todos.len(),
+        path.display()
+    ));
+    output::print_result("exported", &[], json!({ "path": path, "todos": todos.len() }))
+}
+
+/// Deletes the account after the user retypes its email, then removes
+/// everything this machine kept for it: the stored token, the remembered
+/// email, history and aliases. `confirm` takes the email non-interactively.
+pub async fn delete<A: TodoApi>(client: &A, config: &Config, confirm: Option<&str>) -> Result<()> {
+    let Some(email) = config.last_email.as_deref() else {
+        bail!("Log in first so the CLI knows which account to delete");
+    };
+
+    let typed = match confirm {
+        Some(typed) => typed.to_string(),
+        None if prompt::interactive() => {
+            eprintln!(
+                "{}",
+                output::with_icon("⚠️ ", "This permanently deletes your account and all todos.")
+                    .red()
+            );
+            prompt::input(&format!("Type {} to confirm", email), None)?
+        }
+        None => {
+            return Err(prompt::required(
+                "Refusing to delete an account without confirmation; pass --confirm <email>",
+            ));
+        }
+    };
+    if !typed.eq_ignore_ascii_case(email) {
+        bail!("Email did not match; account not deleted");
+    }
+
+    client.delete_account(&RequestOptions::default()).await?;
+
+    // Builds without a keyring have no stored token to clear
+    let _ = Config::clear_token(client.base_url());
+    session::clear(client.base_url())?;
+    Config::update(|config| {
+        config.last_email = None;
+        Ok(())
+    })?;
+    history::clear()?;
+    Aliases::load().clear();
+
+    output::success("Account deleted.");
+    output::print_result("account_deleted", &[], json!({ "email": email }))
+}
+
+pub async fn show<A: TodoApi>(client: &A, format: &str) -> Result<()> {
+    let user = client.profile(&RequestOptions::default()).await?;
+    print_profile(&user, format)
+}
+
+/// Updates the profile, asking first when the login email would change.
+pub async fn update<A: TodoApi>(
+    client: &A,
+    name: Option<&str>,
+    email: Option<&str>,
+    yes: bool,
+    format: &str,
+) -> Result<()> {
+    if name.is_none() && email.is_none() {
+        bail!("Nothing to update; pass --name and/or --email");
+    }
+    if let Some(email) = email
+        && !yes
+    {
+        if !prompt::interactive() {
+            return Err(prompt::required(
+                "Not changing the login email without confirmation; pass --yes",
+            ));
+        }
+        let question = format!("Change your login email to {}?", email);
+        if !prompt::confirm(&question, false)? {
+            output::say!("Cancelled.");
+            return output::print_result("cancelled", &[], ());
+        }
+    }
+
+    let user = client
+        .update_profile(name, email, &RequestOptions::default())
+        .await?;
+    if output::json() {
+        output::print_result("updated", &[user.id], &user)?;
+    } else {
+        print_profile(&user, format)?;
+    }
+
+    match (email, user.pending_email.as_deref()) {
+        (Some(_), Some(pending)) => {
+            output::say!(
+                "{}",
+                output::with_icon(
+                    "📧",
+                    &format!(
+                        "Confirm the change from the message sent to {}; until then, \
+                         keep logging in as {}.",
+                        pending, user.email
+                    )
+                )
+            );
+        }
+        (Some(_), None) => {
+            // The server applied it directly, so re-login prompts should offer it
+            Config::remember_email(&user.email)?;
+            output::success("Profile updated.");
+        }
+        (None, _) => output::success("Profile updated."),
+    }
+    Ok(())
+}
+
+fn print_profile(user: &User, format: &str) -> Result<()> {
+    if format == "json" {
+        return output::print_json("account", user);
+    }
+
+    println!("  {}: {}", "ID".dimmed(), user.id);
+    println!("  {}: {}", "Name".dimmed(), user.name.as_deref().unwrap_or("(not set)"));
+    println!("  {}: {}", "Email".dimmed(), user.email);
+    if let Some(ref pending) = user.pending_email {
+        println!("  {}: {}", "Pending email".dimmed(), pending);
+    }
+    println!("  {}: {}", "Member since".dimmed(), user.created_at);
+    Ok(())
+}
+
+/// Checks and shrinks `path` locally, then uploads it as a PNG. Validating
+/// here gives a clear error for a bad file instead of a rejected upload.
+pub async fn set_avatar<A: TodoApi>(client: &A, path: &Path) -> Result<()> {
+    let size = std::fs::metadata(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?
+        .len();
+    if size > MAX_AVATAR_BYTES {
+        bail!("{} is larger than 10 MB", path.display());
+    }
+
+    let bytes = std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let image = image::load_from_memory(&bytes)
+        .with_context(|| format!("{} is not a PNG, JPEG or WebP image", path.display()))?;
+    if image.width() < MIN_AVATAR_SIZE || image.height() < MIN_AVATAR_SIZE {
+        bail!(
+            "Image is {}x{}; avatars must be at least {}x{}",
+            image.width(),
+            image.height(),
+            MIN_AVATAR_SIZE,
+            MIN_AVATAR_SIZE
+        );
+    }
+    let image = if image.width() > AVATAR_SIZE || image.height() > AVATAR_SIZE {
+        image.thumbnail(AVATAR_SIZE, AVATAR_SIZE)
+    } else {
+        image
+    };
+
+    let mut png = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)
+        .context("Failed to encode avatar")?;
+
+    client.set_avatar(png, &RequestOptions::default()).await?;
+    output::success(&format!(
+        "Avatar updated ({}x{}).",
+        image.width(),
+        image.height()
+    ));
+    let size = json!({ "width": image.width(), "height": image.height() });
+    output::print_result("avatar_updated", &[], size)
+}
+
+pub async fn remove_avatar<A: TodoApi>(client: &A) -> Result<()> {
+    client.remove_avatar(&RequestOptions::default()).await?;
+    output::success("Avatar removed.");
+    output::print_result("avatar_removed", &[], ())
+}
@@ -0,0 +1,185 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use anyhow::{Context, Result, bail};
+
+use crate::lock;
+
+/// A todo ID as typed on the command line: either the server ID or a local
+/// alias such as `a3`.
+#[derive(Debug, Clone)]
+pub enum IdArg {
+    Id(i64),
+    Alias(String),
+}
+
+impl FromStr for IdArg {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(id) = s.parse() {
+            return Ok(IdArg::Id(id));
+        }
+        if index_of(s).is_some() {
+            return Ok(IdArg::Alias(s.to_string()));
+        }
+        Err(format!("'{}' is neither a todo ID nor an alias like a3", s))
+    }
+}
+
+impl fmt::Display for IdArg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IdArg::Id(id) => write!(f, "#{}", id),
+            IdArg::Alias(alias) => f.write_str(alias),
+        }
+    }
+}
+
+/// Short, stable names for todo IDs, assigned when todos are listed and
+/// recycled once they are deleted. Kept per machine in the state directory.
+#[derive(Debug, Default)]
+pub struct Aliases {
+    path: Option<PathBuf>,
+    ids: BTreeMap<String, i64>,
+}
+
+impl Aliases {
+    /// Reads the alias file. Aliases are a convenience, so a missing or
+    /// unreadable file gives an empty table rather than an error.
+    pub fn load() -> Self {
+        let path = match crate::config::state_dir() {
+            Ok(dir) => dir.join("aliases.json"),
+            Err(err) => {
+                tracing::warn!("Aliases unavailable: {:#}", err);
+                return Self::default();
+            }
+        };
+        let ids = lock::shared(&path, || Ok(fs::read_to_string(&path)?))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+
+        Self {
+            path: Some(path),
+            ids,
+        }
+    }
+
+    /// Writes the table back; failures are logged, not returned.
+    pub fn save(&self) {
+        if let Err(err) = self.write() {
+            tracing::warn!("Failed to save aliases: {:#}", err);
+        }
+    }
+
+    fn write(&self) -> Result<()> {
+        let Some(ref path) = self.path else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).context("Failed to create state directory")?;
+        }
+        let content = serde_json::to_string_pretty(&self.ids)?;
+        lock::exclusive(path, || fs::write(path, content).context("Failed to write aliases"))
+    }
+
+    pub fn resolve(&self, arg: &IdArg) -> Result<i64> {
+        match arg {
+            IdArg::Id(id) => Ok(*id),
+            IdArg::Alias(alias) => match self.ids.get(alias) {
+                Some(id) => Ok(*id),
+                None => bail!("Unknown alias '{}'; run `todo list` to refresh aliases", alias),
+            },
+        }
+    }
+
+    /// Gives every ID in `ids` an alias, reusing the lowest free ones.
+    pub fn assign(&mut self, ids: impl IntoIterator<Item = i64>) {
+        let known: HashSet<i64> = self.ids.values().copied().collect();
+        let mut next = 0;
+        for id in ids {
+            if known.contains(&id) {
+                continue;
+            }
+            while self.ids.contains_key(&alias(next)) {
+                next += 1;
+            }
+            self.ids.insert(alias(next), id);
+        }
+    }
+
+    /// Frees the alias of a deleted todo for reuse.
+    pub fn forget(&mut self, id: i64) {
+        self.ids.retain(|_, v| *v != id);
+    }
+
+    /// Forgets every alias and removes them from disk.
+    pub fn clear(&mut self) {
+        self.ids.clear();
+        self.save();
+    }
+
+    /// Drops aliases for todos that no longer exist on the server.
+    pub fn retain(&mut self, live: &HashSet<i64>) {
+        self.ids.retain(|_, id| live.contains(id));
+    }
+
+    /// The alias table as stored, for `config export`.
+    pub fn table(&self) -> &BTreeMap<String, i64> {
+        &self.ids
+    }
+
+    /// Adds aliases from another machine. Aliases and IDs already known here
+    /// keep their current mapping.
+    pub fn import(&mut self, ids: BTreeMap<String, i64>) {
+        let known: HashSet<i64> = self.ids.values().copied().collect();
+        for (alias, id) in ids {
+            if index_of(&alias).is_some() && !known.contains(&id) {
+                self.ids.entry(alias).or_insert(id);
+            }
+        }
+        self.save();
+    }
+
+    /// Aliases keyed by todo ID, for display.
+    pub fn by_id(&self) -> HashMap<i64, String> {
+        self.ids.iter().map(|(alias, id)| (*id, alias.clone())).collect()
+    }
+}
+
+/// The `n`th alias: `a0`..`a9`, `b0`..`z9`, then `aa0` and so on.
+fn alias(n: usize) -> String {
+    let mut prefix = Vec::new();
+    let mut p = n / 10;
+    loop {
+        prefix.push(b'a' + (p % 26) as u8);
+        p /= 26;
+        if p == 0 {
+            break;
+        }
+        p -= 1;
+    }
+    prefix.reverse();
+    format!("{}{}", String::from_utf8(prefix).unwrap_or_default(), n % 10)
+}
+
+/// Inverse of `alias`, or `None` if `s` isn't shaped like one.
+fn index_of(s: &str) -> Option<usize> {
+    if !s.is_ascii() {
+        return None;
+    }
+    let (letters, digit) = s.split_at(s.len().checked_sub(1)?);
+    let digit = digit.parse::<usize>().ok()?;
+    if letters.is_empty() || !letters.bytes().all(|b| b.is_ascii_lowercase()) {
+        return None;
+    }
+    let mut p = 0usize;
+    for b in letters.bytes() {
+        p = p.checked_mul(26)?.checked_add((b - b'a') as usize + 1)?;
+    }
+    (p - 1).checked_mul(10)?.checked_add(digit)
+}
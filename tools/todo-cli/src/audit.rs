@@ -0,0 +1,179 @@
+//! `todo audit`: every request the CLI sent that could change something,
+//! appended to a log in the state directory that is never trimmed, so what
+//! a misbehaving script did to an account can be pieced together later.
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local, NaiveDate, Utc};
+use http::Extensions;
+use reqwest::{Method, Request, Response};
+use reqwest_middleware::{Middleware, Next};
+use serde::{Deserialize, Serialize};
+
+use crate::style::Colorize;
+use crate::{due, history, locale, lock, output};
+
+/// One line of the audit log.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Entry {
+    pub at: DateTime<Utc>,
+    /// The command line that sent the request, with secrets redacted
+    pub command: Vec<String>,
+    pub method: String,
+    /// Path and query; bodies are left out so passwords never reach the log
+    pub path: String,
+    /// Response status, or `None` when no response came back
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Logs every request other than reads, retries included, along with what
+/// came back.
+pub struct Audit {
+    command: Vec<String>,
+}
+
+impl Audit {
+    pub fn new(args: impl IntoIterator<Item = String>) -> Self {
+        Self {
+            command: history::redact(args),
+        }
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl Middleware for Audit {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> reqwest_middleware::Result<Response> {
+        if matches!(*req.method(), Method::GET | Method::HEAD | Method::OPTIONS) {
+            return next.run(req, extensions).await;
+        }
+
+        let mut entry = Entry {
+            at: Utc::now(),
+            command: self.command.clone(),
+            method: req.method().to_string(),
+            path: match req.url().query() {
+                Some(query) => format!("{}?{}", req.url().path(), query),
+                None => req.url().path().to_string(),
+            },
+            status: None,
+            request_id: None,
+            error: None,
+        };
+        let result = next.run(req, extensions).await;
+        match result {
+            Ok(ref response) => {
+                entry.status = Some(response.status().as_u16());
+                entry.request_id = response
+                    .headers()
+                    .get("X-Request-ID")
+                    .and_then(|v| v.to_str().ok())
+                    .map(str::to_string);
+            }
+            Err(ref err) => entry.error = Some(err.to_string()),
+        }
+        // Best effort, like history: a full disk shouldn't fail the command
+        if let Err(err) = append(&entry) {
+            tracing::warn!("Failed to write audit log: {:#}", err);
+        }
+        result
+    }
+}
+
+fn append(entry: &Entry) -> Result<()> {
+    let path = state_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).context("Failed to create state directory")?;
+    }
+    let line = serde_json::to_string(entry)?;
+    lock::exclusive(&path, || {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+        writeln!(file, "{}", line)?;
+        Ok(())
+    })
+}
+
+/// Reads every entry, oldest first. Lines that don't parse are skipped.
+pub fn load() -> Result<Vec<Entry>> {
+    let path = state_path()?;
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err).context("Failed to read audit log"),
+    };
+
+    Ok(content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+/// Parses `--since`: a span back from now like `2h` or `7d`, or a date.
+pub fn parse_since(s: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        return Ok(due::start_of(date));
+    }
+    due::parse_span(s)
+        .map(|span| Utc::now() - span)
+        .map_err(|_| format!("'{}' is neither a span like 7d nor a date (YYYY-MM-DD)", s))
+}
+
+/// Prints the entries at or after `since`, oldest first, keeping the newest
+/// `limit`.
+pub fn show(since: Option<DateTime<Utc>>, limit: usize, format: &str) -> Result<()> {
+    let mut entries = load()?;
+    if let Some(since) = since {
+        entries.retain(|entry| entry.at >= since);
+    }
+    let entries = &entries[entries.len().saturating_sub(limit)..];
+
+    if format == "json" {
+        return output::print_json("audit", entries);
+    }
+    if entries.is_empty() {
+        println!("{}", "Nothing in the audit log.".dimmed());
+        return Ok(());
+    }
+
+    for entry in entries {
+        let at = locale::datetime(&entry.at.with_timezone(&Local));
+        let status = match entry.status {
+            Some(status) if status < 400 => status.to_string().green(),
+            Some(status) => status.to_string().red(),
+            None => "---".red(),
+        };
+        println!(
+            "  {}  {}  {:<6} {}  {}",
+            at.dimmed(),
+            status,
+            entry.method,
+            entry.path,
+            format!("todo {}", entry.command.join(" ")).dimmed()
+        );
+        if let Some(ref error) = entry.error {
+            println!("  {}", error.red());
+        }
+    }
+    Ok(())
+}
+
+fn state_path() -> Result<PathBuf> {
+    Ok(crate::config::state_dir()?.join("audit.jsonl"))
+}
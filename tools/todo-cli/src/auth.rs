@@ -1,48 +1,267 @@
-use anyhow::Result;
-use colored::Colorize;
+use std::io::Read;
+use std::path::Path;
 
-use crate::api::ApiClient;
-use crate::config::Config;
+use anyhow::{Context, Result, bail};
+use base64::Engine;
+use base64::prelude::BASE64_URL_SAFE_NO_PAD;
+use chrono::{DateTime, Utc};
+use serde_json::json;
 
-pub async fn login(client: &ApiClient, email: &str, password: &str) -> Result<()> {
-    println!("🔑 Logging in as {}...", email);
+use crate::api::{AuthResponse, RequestOptions, TodoApi};
+use crate::config::{AuthScheme, Config};
+use crate::error::ApiError;
+use crate::oidc;
+use crate::output;
+use crate::prompt;
+use crate::secret::SecretString;
+use crate::secrets;
+use crate::session;
+use crate::style::Colorize;
 
-    let response = client.login(email, password).await?;
+pub async fn login<A: TodoApi>(
+    client: &A,
+    email: &str,
+    password: &str,
+    scheme: AuthScheme,
+) -> Result<()> {
+    output::say!("{}", output::with_icon("🔑", &format!("Logging in as {}...", email)));
 
-    Config::set_token(&response.token)?;
+    let response = client.login(email, password, &RequestOptions::default()).await?;
 
-    println!("{}", "✅ Login successful!".green());
-    println!("Token has been securely stored.");
+    store_credentials(client.base_url(), &response, scheme)?;
+    Config::remember_email(email)?;
 
-    Ok(())
+    output::success("Login successful!");
+    match scheme {
+        AuthScheme::Session => output::say!("Session has been securely stored."),
+        _ => output::say!("Token has been securely stored."),
+    }
+
+    output::print_result("logged_in", &user_ids(&response), json!({ "email": email }))
 }
 
-pub async fn register(client: &ApiClient, email: &str, password: &str) -> Result<()> {
-    println!("📝 Registering {}...", email);
+/// The password for `login`/`register`: read from `file` (`-` for stdin),
+/// else `password` (which clap fills from `TODO_PASSWORD`), else prompted.
+pub fn password(password: Option<String>, file: Option<&Path>) -> Result<String> {
+    let Some(file) = file else {
+        return match password {
+            Some(password) => Ok(password),
+            None if prompt::interactive() => prompt::password("Password: "),
+            None => Err(prompt::required(
+                "No password given; use --password, --password-file or TODO_PASSWORD",
+            )),
+        };
+    };
 
-    let response = client.register(email, password).await?;
+    let mut contents = String::new();
+    if file == Path::new("-") {
+        std::io::stdin().read_to_string(&mut contents)?;
+    } else {
+        contents = std::fs::read_to_string(file)
+            .with_context(|| format!("Failed to read {}", file.display()))?;
+    }
+    // Only the line ending; spaces may be part of the password
+    let password = contents.trim_end_matches(['\r', '\n']);
+    if password.is_empty() {
+        bail!("Password file {} is empty", file.display());
+    }
+    Ok(password.to_string())
+}
 
-    Config::set_token(&response.token)?;
+/// Signs in through the configured OIDC provider and stores the access
+/// token it issues as the API token.
+pub async fn login_browser<A: TodoApi>(client: &A, config: &Config) -> Result<()> {
+    let token = oidc::login(&config.oidc).await?;
+    Config::set_token(client.base_url(), token.expose())?;
 
-    println!("{}", "✅ Registration successful!".green());
-    println!("You are now logged in.");
+    output::success("Login successful!");
+    output::say!("Token has been securely stored.");
+    output::print_result("logged_in", &[], ())
+}
 
-    Ok(())
+pub async fn register<A: TodoApi>(
+    client: &A,
+    email: &str,
+    password: &str,
+    scheme: AuthScheme,
+) -> Result<()> {
+    output::say!("{}", output::with_icon("📝", &format!("Registering {}...", email)));
+
+    let response = client.register(email, password, &RequestOptions::default()).await?;
+
+    // Some servers answer with the new user alone, leaving login to the user
+    let logged_in = has_credentials(&response, scheme);
+    if logged_in {
+        store_credentials(client.base_url(), &response, scheme)?;
+    }
+
+    output::success("Registration successful!");
+    if logged_in {
+        output::say!("You are now logged in.");
+    } else {
+        output::say!("Run 'todo auth login' to sign in.");
+    }
+
+    let state = json!({ "email": email, "logged_in": logged_in });
+    output::print_result("registered", &user_ids(&response), state)
 }
 
-pub fn logout() -> Result<()> {
-    Config::clear_token()?;
-    println!("{}", "✅ Logged out successfully!".green());
-    Ok(())
+/// The account an auth response is for, when the server says.
+fn user_ids(response: &AuthResponse) -> Vec<i64> {
+    response.user_id.into_iter().collect()
+}
+
+/// Whether `response` carries the credential `scheme` needs.
+fn has_credentials(response: &AuthResponse, scheme: AuthScheme) -> bool {
+    match scheme {
+        AuthScheme::Session => response.session.is_some(),
+        AuthScheme::Bearer | AuthScheme::Hmac | AuthScheme::Basic => !response.token.is_empty(),
+    }
+}
+
+/// Keeps the credential that `scheme` authenticates later requests with.
+fn store_credentials(api_url: &str, response: &AuthResponse, scheme: AuthScheme) -> Result<()> {
+    match scheme {
+        AuthScheme::Session => {
+            let Some(ref cookie) = response.session else {
+                bail!("The server set no session cookie; check auth_scheme in the config");
+            };
+            session::save(api_url, cookie)
+        }
+        AuthScheme::Bearer | AuthScheme::Hmac | AuthScheme::Basic => {
+            if response.token.is_empty() {
+                bail!("The server returned no token; check auth_scheme in the config");
+            }
+            Config::set_token(api_url, response.token.expose())
+        }
+    }
+}
+
+pub fn logout(api_url: &str, scheme: AuthScheme) -> Result<()> {
+    match scheme {
+        AuthScheme::Session => session::clear(api_url)?,
+        AuthScheme::Bearer | AuthScheme::Hmac | AuthScheme::Basic => Config::clear_token(api_url)?,
+    }
+    output::success("Logged out successfully!");
+    output::print_result("logged_out", &[], ())
 }
 
-pub fn status(config: &Config) -> Result<()> {
-    if config.has_token() {
-        println!("{}", "✅ Authenticated".green());
+pub fn status(config: &Config, api_url: &str) -> Result<()> {
+    let logged_in = match config.auth_scheme {
+        AuthScheme::Session => session::load(api_url).is_some(),
+        AuthScheme::Bearer | AuthScheme::Hmac | AuthScheme::Basic => config.has_token(),
+    };
+    let (store, fallback) = secrets::active();
+    if output::json() {
+        let status = json!({
+            "authenticated": logged_in,
+            "credential_store": store.as_str(),
+            "fallback_reason": fallback,
+        });
+        return output::print_json("auth_status", &status);
+    }
+    if logged_in {
+        println!("{}", output::with_icon("✅", "Authenticated").green());
         println!("You are logged in and can access the API.");
     } else {
-        println!("{}", "❌ Not authenticated".red());
+        println!("{}", output::with_icon("❌", "Not authenticated").red());
         println!("Run 'todo auth login' to authenticate.");
     }
+    match fallback {
+        Some(reason) => println!(
+            "{}",
+            format!("Credential store: {} (no keyring: {})", store.as_str(), reason).dimmed()
+        ),
+        None => println!("{}", format!("Credential store: {}", store.as_str()).dimmed()),
+    }
     Ok(())
 }
+
+/// Whether `err` means the token was rejected and the user is around to log
+/// in again. Scripts and pipes get the original error instead.
+pub fn is_expired_session(err: &anyhow::Error) -> bool {
+    prompt::interactive() && is_unauthorized(err)
+}
+
+/// Whether the server rejected the credentials `err` was sent with.
+pub fn is_unauthorized(err: &anyhow::Error) -> bool {
+    matches!(err.downcast_ref::<ApiError>(), Some(ApiError::Unauthorized(_)))
+}
+
+/// Whether `--login-if-needed` should log in before running the command:
+/// there is no token, or it's a JWT that has expired.
+pub fn needs_login(config: &Config) -> bool {
+    match config.get_token() {
+        None => true,
+        Some(token) => jwt_expires_at(token.expose()).is_some_and(|exp| exp <= Utc::now()),
+    }
+}
+
+/// The `exp` claim of a JWT, read without verifying the signature; the
+/// server does that. `None` for opaque tokens.
+fn jwt_expires_at(token: &str) -> Option<DateTime<Utc>> {
+    let payload = token.split('.').nth(1)?;
+    let claims: serde_json::Value =
+        serde_json::from_slice(&BASE64_URL_SAFE_NO_PAD.decode(payload).ok()?).ok()?;
+    DateTime::from_timestamp(claims.get("exp")?.as_i64()?, 0)
+}
+
+/// Logs in without prompting for `--login-if-needed`, with `TODO_EMAIL`
+/// (or the last email used) and `TODO_PASSWORD`, and stores the new
+/// credentials like `auth login` does.
+pub async fn login_from_env<A: TodoApi>(client: &A, config: &Config) -> Result<SecretString> {
+    let email = std::env::var("TODO_EMAIL")
+        .ok()
+        .filter(|email| !email.is_empty())
+        .or_else(|| config.last_email.clone())
+        .ok_or_else(|| prompt::required("--login-if-needed needs TODO_EMAIL"))?;
+    let password = std::env::var("TODO_PASSWORD")
+        .ok()
+        .filter(|password| !password.is_empty())
+        .ok_or_else(|| prompt::required("--login-if-needed needs TODO_PASSWORD"))?;
+
+    eprintln!("{}", format!("Logging in as {}...", email).dimmed());
+    let response = client
+        .login(&email, &password, &RequestOptions::default())
+        .await
+        .context("Automatic login failed")?;
+    store_credentials(client.base_url(), &response, config.auth_scheme)?;
+    Config::remember_email(&email)?;
+    Ok(response.token)
+}
+
+/// Offers to log in again after the stored token was rejected, returning the
+/// new token if the user accepted and the login succeeded.
+pub async fn relogin<A: TodoApi>(client: &A, config: &Config) -> Result<Option<SecretString>> {
+    eprintln!("{}", output::with_icon("⚠️ ", "Your session has expired.").yellow());
+    if !prompt::confirm("Log in again?", true)? {
+        return Ok(None);
+    }
+
+    let email = prompt::input("Email", config.last_email.as_deref())?;
+    let password = prompt::password("Password: ")?;
+    let response = client
+        .login(&email, &password, &RequestOptions::default())
+        .await?;
+
+    Config::set_token(client.base_url(), response.token.expose())?;
+    Config::remember_email(&email)?;
+    eprintln!("{}", output::with_icon("✅", "Login successful!").green());
+
+    Ok(Some(response.token))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn an_empty_token_is_not_stored() {
+        let response = AuthResponse::default();
+        for scheme in [AuthScheme::Bearer, AuthScheme::Hmac, AuthScheme::Basic] {
+            assert!(!has_credentials(&response, scheme));
+            let err = store_credentials("http://fake.test", &response, scheme).unwrap_err();
+            assert!(err.to_string().contains("no token"), "{}", err);
+        }
+    }
+}
@@ -0,0 +1,514 @@
+//! Per-item outcomes for commands that act on many todos at once. One
+//! failure doesn't stop the rest; the summary at the end says what happened
+//! to each item, and the command fails if any did.
+//!
+//! Ctrl+C doesn't abandon such a command halfway through a write: it
+//! finishes the item in flight, skips the rest and still prints the summary.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::api::{RequestOptions, TodoApi};
+use crate::error::ApiError;
+use crate::history::{self, Action};
+use crate::journal::{Journal, Operation};
+use crate::markdown;
+use crate::org;
+use crate::output;
+use crate::style::Colorize;
+use crate::validation::{InvalidInput, TodoInput};
+
+static RUNNING: AtomicBool = AtomicBool::new(false);
+static CANCELLED: AtomicBool = AtomicBool::new(false);
+
+/// Held while a bulk command works through its items, so Ctrl+C stops it
+/// between items instead of dropping it.
+pub struct Running(());
+
+impl Drop for Running {
+    fn drop(&mut self) {
+        RUNNING.store(false, Ordering::Relaxed);
+    }
+}
+
+pub fn running() -> Running {
+    RUNNING.store(true, Ordering::Relaxed);
+    Running(())
+}
+
+/// Asks the running bulk command to stop after the current item. `false`
+/// when none is running or it was already asked, and the command should
+/// be dropped instead.
+pub fn cancel() -> bool {
+    RUNNING.load(Ordering::Relaxed) && !CANCELLED.swap(true, Ordering::Relaxed)
+}
+
+/// Whether Ctrl+C asked the bulk command to stop; its loop checks before
+/// each item.
+pub fn cancelled() -> bool {
+    CANCELLED.load(Ordering::Relaxed)
+}
+
+/// Forgets a Ctrl+C once its command has stopped, for `todo shell`, which
+/// carries on with the next command.
+pub fn reset() {
+    CANCELLED.store(false, Ordering::Relaxed);
+}
+
+/// The reason items left after Ctrl+C are skipped with.
+pub const CANCELLED_REASON: &str = "cancelled";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    Succeeded,
+    Failed,
+    Skipped,
+}
+
+impl Status {
+    fn label(self) -> &'static str {
+        match self {
+            Status::Succeeded => "succeeded",
+            Status::Failed => "failed",
+            Status::Skipped => "skipped",
+        }
+    }
+}
+
+/// What happened to one item: a todo ID, or a title when it was never
+/// created.
+#[derive(Debug, Serialize)]
+pub struct Item {
+    pub item: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<i64>,
+    pub status: Status,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// The `--report` file.
+#[derive(Serialize)]
+struct Report<'a> {
+    action: &'a str,
+    succeeded: usize,
+    failed: usize,
+    skipped: usize,
+    items: &'a [Item],
+}
+
+pub struct Summary {
+    action: &'static str,
+    items: Vec<Item>,
+    _running: Running,
+}
+
+impl Summary {
+    /// `action` is the past tense the result is reported under, e.g. `deleted`.
+    pub fn new(action: &'static str) -> Self {
+        Self {
+            action,
+            items: Vec::new(),
+            _running: running(),
+        }
+    }
+
+    pub fn succeeded(&mut self, item: impl Into<String>, id: i64) {
+        self.push(item.into(), Some(id), Status::Succeeded, None);
+    }
+
+    pub fn failed(
+        &mut self,
+        item: impl Into<String>,
+        id: Option<i64>,
+        err: impl Into<anyhow::Error>,
+    ) {
+        self.push(item.into(), id, Status::Failed, Some(reason(&err.into())));
+    }
+
+    pub fn skipped(&mut self, item: impl Into<String>, id: Option<i64>, reason: &str) {
+        self.push(item.into(), id, Status::Skipped, Some(reason.to_string()));
+    }
+
+    fn push(&mut self, item: String, id: Option<i64>, status: Status, reason: Option<String>) {
+        self.items.push(Item {
+            item,
+            id,
+            status,
+            reason,
+        });
+    }
+
+    pub fn has_failures(&self) -> bool {
+        self.count(Status::Failed) > 0
+    }
+
+    fn count(&self, status: Status) -> usize {
+        self.items.iter().filter(|item| item.status == status).count()
+    }
+
+    /// Prints the summary table (or a result in JSON mode), writes the
+    /// report if one was asked for, and fails if any item did.
+    pub fn finish(self, report: Option<&Path>) -> Result<()> {
+        let (succeeded, failed, skipped) = (
+            self.count(Status::Succeeded),
+            self.count(Status::Failed),
+            self.count(Status::Skipped),
+        );
+
+        if let Some(path) = report {
+            let report = Report {
+                action: self.action,
+                succeeded,
+                failed,
+                skipped,
+                items: &self.items,
+            };
+            fs::write(path, serde_json::to_string_pretty(&report)? + "\n")
+                .with_context(|| format!("Failed to write report {}", path.display()))?;
+        }
+
+        self.print();
+        output::say!(
+            "{} succeeded, {} failed, {} skipped",
+            succeeded.to_string().green(),
+            failed.to_string().red(),
+            skipped.to_string().yellow()
+        );
+        let ids: Vec<_> = self
+            .items
+            .iter()
+            .filter(|item| item.status == Status::Succeeded)
+            .filter_map(|item| item.id)
+            .collect();
+        output::print_result(self.action, &ids, &self.items)?;
+
+        if failed > 0 {
+            bail!("{} of {} items failed", failed, self.items.len());
+        }
+        Ok(())
+    }
+
+    fn print(&self) {
+        let width = self
+            .items
+            .iter()
+            .map(|item| item.item.chars().count())
+            .max()
+            .unwrap_or(0);
+        for item in &self.items {
+            let label = format!("{:<9}", item.status.label());
+            let label = match item.status {
+                Status::Succeeded => label.green(),
+                Status::Failed => label.red(),
+                Status::Skipped => label.yellow(),
+            };
+            match item.reason {
+                Some(ref reason) => {
+                    output::say!("  {} {:<width$}  {}", label, item.item, reason.dimmed())
+                }
+                None => output::say!("  {} {}", label, item.item),
+            }
+        }
+    }
+}
+
+/// What `todo import` reads.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportFormat {
+    /// A file written by `export --as json`
+    #[default]
+    Json,
+    /// A checklist of `- [ ]` items; nesting makes subtasks, headings tags
+    Markdown,
+    /// Emacs org-mode TODO/DONE headings; nesting makes subtasks
+    Org,
+}
+
+impl ImportFormat {
+    /// Markdown for `.md` files, org for `.org` files, else JSON.
+    pub fn detect(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("md" | "markdown") => ImportFormat::Markdown,
+            Some("org") => ImportFormat::Org,
+            _ => ImportFormat::Json,
+        }
+    }
+}
+
+/// One todo to import. IDs and timestamps are the server's to assign.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Imported {
+    pub title: String,
+    #[serde(default)]
+    pub completed: bool,
+    /// From the headings above a markdown or org item
+    #[serde(skip_deserializing, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// From an org DEADLINE
+    #[serde(skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub due: Option<DateTime<Utc>>,
+    /// Index of the item this one is a subtask of, for markdown and org
+    /// nesting.
+    /// The parent is made to depend on its subtasks.
+    #[serde(skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<usize>,
+}
+
+/// Creates the todos in `path`, skipping any whose title an existing todo
+/// already has so a rerun after a partial failure only creates what is
+/// missing. Progress is journaled for `todo resume`.
+pub async fn import<A: TodoApi>(
+    client: &A,
+    path: &Path,
+    from: ImportFormat,
+    report: Option<&Path>,
+) -> Result<()> {
+    let (content, todos) = read_import(path, from)?;
+    let file = path
+        .canonicalize()
+        .with_context(|| format!("Failed to resolve {}", path.display()))?;
+    let journal = Journal::start(Operation::Import {
+        file,
+        sha256: hex::encode(Sha256::digest(&content)),
+        from,
+    })?;
+    import_todos(client, todos, journal, report).await
+}
+
+/// Continues an interrupted import of `path`, which must not have changed.
+pub async fn resume_import<A: TodoApi>(
+    client: &A,
+    path: &Path,
+    sha256: &str,
+    from: ImportFormat,
+    journal: Journal,
+    report: Option<&Path>,
+) -> Result<()> {
+    let (content, todos) = read_import(path, from)?;
+    if hex::encode(Sha256::digest(&content)) != sha256 {
+        bail!("{} changed since the import started; import it again instead", path.display());
+    }
+    import_todos(client, todos, journal, report).await
+}
+
+/// Shows what importing `path` would create, subtasks indented under
+/// their parents, without sending anything.
+pub fn preview(path: &Path, from: ImportFormat, format: &str) -> Result<()> {
+    let (_, todos) = read_import(path, from)?;
+    if format == "json" {
+        return output::print_json("import_preview", &todos);
+    }
+    if todos.is_empty() {
+        output::say!("{}", format!("Nothing to import from {}.", path.display()).dimmed());
+        return Ok(());
+    }
+
+    output::say!("Would import {} todos from {}:", todos.len(), path.display());
+    let mut depths: Vec<usize> = Vec::with_capacity(todos.len());
+    for todo in &todos {
+        let depth = todo.parent.map_or(0, |parent| depths[parent] + 1);
+        depths.push(depth);
+        let check = if todo.completed { "[x]" } else { "[ ]" };
+        let mut notes: Vec<_> = todo.tags.iter().map(|tag| format!("#{}", tag)).collect();
+        if let Some(due) = todo.due {
+            notes.push(format!("due {}", due.with_timezone(&Local).format("%Y-%m-%d %H:%M")));
+        }
+        output::say!(
+            "  {}{} {}  {}",
+            "  ".repeat(depth),
+            check,
+            todo.title,
+            notes.join(" ").dimmed()
+        );
+    }
+    Ok(())
+}
+
+fn read_import(path: &Path, from: ImportFormat) -> Result<(String, Vec<Imported>)> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let todos = match from {
+        ImportFormat::Json => serde_json::from_str(&content)
+            .with_context(|| format!("{} is not a JSON export of todos", path.display()))?,
+        ImportFormat::Markdown => markdown::parse(&content),
+        ImportFormat::Org => org::parse(&content),
+    };
+    Ok((content, todos))
+}
+
+/// Journal keys are the todo's index in the file, plus `:due`, `:tags`
+/// and `:completed` once those were applied and `:subtasks` once a parent
+/// was made to depend on its subtasks.
+async fn import_todos<A: TodoApi>(
+    client: &A,
+    todos: Vec<Imported>,
+    mut journal: Journal,
+    report: Option<&Path>,
+) -> Result<()> {
+
+    let existing = client.list_todos(None, &RequestOptions::new().retries(2)).await?;
+    let mut titles: HashMap<_, _> =
+        existing.into_iter().map(|todo| (todo.title, todo.id)).collect();
+    let limits = client
+        .limits(&RequestOptions::new().timeout(Duration::from_secs(2)))
+        .await
+        .unwrap_or_default();
+
+    let opts = RequestOptions::default();
+    let mut summary = Summary::new("imported");
+    // The todo each item ended up as, for linking subtasks
+    let mut ids: Vec<Option<i64>> = Vec::with_capacity(todos.len());
+    for (i, todo) in todos.iter().enumerate() {
+        if cancelled() {
+            ids.push(None);
+            summary.skipped(todo.title.clone(), None, CANCELLED_REASON);
+            continue;
+        }
+        let key = i.to_string();
+        let (id, created) = if let Some(id) = journal.get(&key) {
+            (id.context("Journal is missing a created todo's ID")?, false)
+        } else if let Some(&id) = titles.get(&todo.title) {
+            ids.push(Some(id));
+            summary.skipped(todo.title.clone(), Some(id), "a todo with this title exists");
+            continue;
+        } else {
+            let input = TodoInput {
+                title: Some(&todo.title),
+                ..Default::default()
+            };
+            if let Err(err) = input.validate(&limits) {
+                ids.push(None);
+                summary.failed(todo.title.clone(), None, err);
+                continue;
+            }
+            let created = match client.create_todo(&todo.title, &opts).await {
+                Ok(created) => created,
+                Err(err) => {
+                    ids.push(None);
+                    summary.failed(todo.title.clone(), None, err);
+                    continue;
+                }
+            };
+            journal.record(key, Some(created.id))?;
+            history::record_todo(&created, Action::Created);
+            titles.insert(todo.title.clone(), created.id);
+            (created.id, true)
+        };
+        ids.push(Some(id));
+
+        let item = format!("#{} {}", id, todo.title);
+        match finish_item(client, id, todo, i, &opts, &mut journal).await {
+            Ok(changed) if created || changed => summary.succeeded(item, id),
+            Ok(_) => summary.skipped(item, Some(id), "imported earlier"),
+            // Created but not finished; `todo resume` does the rest
+            Err(err) => summary.failed(item, Some(id), err),
+        }
+    }
+
+    let mut subtasks: BTreeMap<usize, Vec<i64>> = BTreeMap::new();
+    for (todo, id) in todos.iter().zip(&ids) {
+        if let (Some(parent), Some(id)) = (todo.parent, id) {
+            subtasks.entry(parent).or_default().push(*id);
+        }
+    }
+    for (parent, depends_on) in subtasks {
+        if cancelled() {
+            break;
+        }
+        // Only parents this import created; an existing todo keeps its own
+        let Some(Some(id)) = journal.get(&parent.to_string()) else {
+            continue;
+        };
+        let key = format!("{}:subtasks", parent);
+        if journal.is_done(&key) {
+            continue;
+        }
+        match client.set_dependencies(id, &depends_on, &opts).await {
+            Ok(_) => journal.record(key, Some(id))?,
+            Err(err) => {
+                let item = format!("#{} {}", id, todos[parent].title);
+                let err = anyhow::Error::from(err).context("Subtasks not linked");
+                summary.failed(item, Some(id), err);
+            }
+        }
+    }
+
+    // After Ctrl+C the journal stays, so `todo resume` does the rest
+    journal.close(!summary.has_failures() && !cancelled())?;
+    summary.finish(report)
+}
+
+/// Applies what's left of `todo` after it was created: its due date and
+/// tags, then completion. Returns whether anything was sent.
+async fn finish_item<A: TodoApi>(
+    client: &A,
+    id: i64,
+    todo: &Imported,
+    index: usize,
+    opts: &RequestOptions,
+    journal: &mut Journal,
+) -> Result<bool> {
+    let mut changed = false;
+    let due_key = format!("{}:due", index);
+    if let Some(due) = todo.due
+        && !journal.is_done(&due_key)
+    {
+        let scheduled = client.set_due(id, due, opts).await.context("Due date not set")?;
+        journal.record(due_key, Some(id))?;
+        history::record_todo(&scheduled, Action::Updated);
+        changed = true;
+    }
+    let tags_key = format!("{}:tags", index);
+    if !todo.tags.is_empty() && !journal.is_done(&tags_key) {
+        let tagged = client.set_tags(id, &todo.tags, opts).await.context("Not tagged")?;
+        journal.record(tags_key, Some(id))?;
+        history::record_todo(&tagged, Action::Updated);
+        changed = true;
+    }
+    let completed_key = format!("{}:completed", index);
+    if todo.completed && !journal.is_done(&completed_key) {
+        complete(client, id, opts, journal, &completed_key)
+            .await
+            .context("Not marked done")?;
+        changed = true;
+    }
+    Ok(changed)
+}
+
+async fn complete<A: TodoApi>(
+    client: &A,
+    id: i64,
+    opts: &RequestOptions,
+    journal: &mut Journal,
+    key: &str,
+) -> Result<()> {
+    let todo = client.update_todo(id, None, Some(true), opts).await?;
+    journal.record(key, Some(id))?;
+    history::record_todo(&todo, Action::Completed);
+    Ok(())
+}
+
+/// The error plus any field errors behind it, on one line.
+fn reason(err: &anyhow::Error) -> String {
+    let fields = match (err.downcast_ref::<InvalidInput>(), err.downcast_ref::<ApiError>()) {
+        (Some(invalid), _) => &invalid.fields[..],
+        (_, Some(api_err)) => api_err.fields(),
+        _ => &[],
+    };
+    let mut reason = format!("{:#}", err);
+    for field in fields {
+        reason.push_str(&format!("; {}: {}", field.field, field.message));
+    }
+    reason
+}
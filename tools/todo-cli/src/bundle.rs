@@ -0,0 +1,185 @@
+//! `config export`/`import`: a TOML bundle with the settings and aliases a
+//! new machine needs. Credentials are only included on request, encrypted
+//! with a passphrase, since bundles tend to end up in dotfile repos.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result, anyhow, bail};
+use argon2::Argon2;
+use base64::Engine;
+use base64::prelude::BASE64_STANDARD;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::aliases::Aliases;
+use crate::config::Config;
+use crate::secrets::{self, Kind, Secret};
+use crate::{output, prompt};
+
+/// Bumped if the bundle layout changes incompatibly. The config inside
+/// carries its own version and is migrated on import.
+const BUNDLE_VERSION: u32 = 1;
+
+/// Credentials worth carrying over. The session key stays behind with the
+/// session file it unlocks.
+const EXPORTED: [Kind; 3] = [Kind::Token, Kind::HmacSecret, Kind::BasicAuth];
+
+const NONCE_LEN: usize = 12;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Bundle {
+    version: u32,
+    /// Server the credentials belong to
+    api_url: String,
+    #[serde(default)]
+    aliases: BTreeMap<String, i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    secrets: Option<Sealed>,
+    config: toml::Table,
+}
+
+/// Credentials encrypted with ChaCha20-Poly1305 under an Argon2id key
+/// derived from the passphrase. All fields are base64.
+#[derive(Debug, Serialize, Deserialize)]
+struct Sealed {
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+/// Writes the bundle to `path`, or stdout when it's `None`.
+pub fn export(
+    config: &Config,
+    api_url: &str,
+    path: Option<&Path>,
+    with_secrets: bool,
+) -> Result<()> {
+    let secrets = if with_secrets {
+        let secrets = collect_secrets(api_url)?;
+        Some(seal(&secrets, &new_passphrase()?)?)
+    } else {
+        None
+    };
+    let bundle = Bundle {
+        version: BUNDLE_VERSION,
+        api_url: api_url.to_string(),
+        aliases: Aliases::load().table().clone(),
+        secrets,
+        config: config.to_table()?,
+    };
+    let content = toml::to_string_pretty(&bundle)?;
+
+    match path {
+        Some(path) => {
+            fs::write(path, content)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            output::success(&format!("Config exported to {}", path.display()));
+            output::print_result("exported", &[], json!({ "path": path }))?;
+        }
+        None => print!("{}", content),
+    }
+    Ok(())
+}
+
+/// Replaces the config with the bundle's and merges its aliases. Secrets
+/// are decrypted first, so a wrong passphrase leaves everything untouched.
+pub fn import(path: &Path, skip_secrets: bool) -> Result<()> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let bundle: Bundle = toml::from_str(&content)
+        .with_context(|| format!("{} is not a config bundle", path.display()))?;
+    if bundle.version > BUNDLE_VERSION {
+        bail!("This bundle was written by a newer todo-cli; upgrade to import it");
+    }
+
+    let secrets = match bundle.secrets {
+        Some(ref sealed) if !skip_secrets => {
+            Some(unseal(sealed, &prompt::password("Passphrase: ")?)?)
+        }
+        _ => None,
+    };
+
+    let config = Config::import(bundle.config)?;
+    Aliases::load().import(bundle.aliases);
+    let with_secrets = secrets.is_some();
+    if let Some(secrets) = secrets {
+        // Into the backend the imported config asks for
+        config.secret_backend.install();
+        for (kind, value) in &secrets {
+            secrets::set(Secret::new(*kind, &bundle.api_url), value)?;
+        }
+        output::success(&format!("Config and credentials for {} imported.", bundle.api_url));
+    } else {
+        output::success("Config imported.");
+    }
+    let state = json!({ "api_url": bundle.api_url, "secrets": with_secrets });
+    output::print_result("imported", &[], state)
+}
+
+fn collect_secrets(api_url: &str) -> Result<BTreeMap<Kind, String>> {
+    let mut secrets = BTreeMap::new();
+    for kind in EXPORTED {
+        if let Some(value) = secrets::get(Secret::new(kind, api_url))? {
+            secrets.insert(kind, value);
+        }
+    }
+    if secrets.is_empty() {
+        bail!("No credentials stored for {}", api_url);
+    }
+    Ok(secrets)
+}
+
+fn new_passphrase() -> Result<String> {
+    let passphrase = prompt::password("Passphrase for the exported credentials: ")?;
+    if passphrase.is_empty() {
+        bail!("The passphrase can't be empty");
+    }
+    if prompt::password("Repeat passphrase: ")? != passphrase {
+        bail!("Passphrases did not match");
+    }
+    Ok(passphrase)
+}
+
+fn seal(secrets: &BTreeMap<Kind, String>, passphrase: &str) -> Result<Sealed> {
+    let mut salt = [0u8; 16];
+    getrandom::getrandom(&mut salt).context("Failed to generate random bytes")?;
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = cipher(passphrase, &salt)?
+        .encrypt(&nonce, serde_json::to_vec(secrets)?.as_slice())
+        .map_err(|_| anyhow!("Failed to encrypt credentials"))?;
+
+    Ok(Sealed {
+        salt: BASE64_STANDARD.encode(salt),
+        nonce: BASE64_STANDARD.encode(nonce),
+        ciphertext: BASE64_STANDARD.encode(ciphertext),
+    })
+}
+
+fn unseal(sealed: &Sealed, passphrase: &str) -> Result<BTreeMap<Kind, String>> {
+    let decode = |field: &str| {
+        BASE64_STANDARD
+            .decode(field)
+            .context("Corrupt credentials in bundle")
+    };
+    let nonce = decode(&sealed.nonce)?;
+    if nonce.len() != NONCE_LEN {
+        bail!("Corrupt credentials in bundle");
+    }
+    let plaintext = cipher(passphrase, &decode(&sealed.salt)?)?
+        .decrypt(Nonce::from_slice(&nonce), decode(&sealed.ciphertext)?.as_slice())
+        .map_err(|_| anyhow!("Wrong passphrase, or the bundle was modified"))?;
+
+    Ok(serde_json::from_slice(&plaintext)?)
+}
+
+fn cipher(passphrase: &str, salt: &[u8]) -> Result<ChaCha20Poly1305> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|err| anyhow!("Failed to derive key: {}", err))?;
+    Ok(ChaCha20Poly1305::new(Key::from_slice(&key)))
+}
@@ -0,0 +1,71 @@
+//! `todo completions <shell>`: a script that has the shell ask `todo`
+//! itself what can come next, so subcommands and flags always match the
+//! binary, and todo IDs and aliases complete from the todos this machine
+//! has listed, titles alongside.
+
+use std::ffi::OsStr;
+use std::io;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use clap_complete::Shell;
+use clap_complete::engine::CompletionCandidate;
+use clap_complete::env::Shells;
+
+use crate::aliases::Aliases;
+use crate::search;
+
+/// Set by the registered script when it calls back for candidates; `main`
+/// answers and exits before parsing arguments.
+pub const VAR: &str = "COMPLETE";
+
+/// Prints the script that registers completion for `shell`.
+pub fn print(shell: Shell) -> Result<()> {
+    let name = shell.to_string();
+    let shells = Shells::builtins();
+    let completer = shells
+        .completer(&name)
+        .with_context(|| format!("No completion support for {}", name))?;
+    // The shell calls back into this very binary, under the name it was
+    // run as, so `todo-cli` and a `todo` symlink both work
+    let bin = std::env::current_exe().context("Failed to locate the todo binary")?;
+    let bin = bin.to_string_lossy();
+    let command = std::env::args_os()
+        .next()
+        .as_deref()
+        .and_then(|arg0| Path::new(arg0).file_name())
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "todo".to_string());
+    completer
+        .write_registration(VAR, &command, &bin, &bin, &mut io::stdout())
+        .context("Failed to write the completion script")
+}
+
+/// Candidates for a todo ID or alias argument, from the local search index
+/// and alias table. Completion must never fail, so an empty or unreadable
+/// cache just offers nothing.
+pub fn todo_ids(current: &OsStr) -> Vec<CompletionCandidate> {
+    let Some(current) = current.to_str() else {
+        return Vec::new();
+    };
+    let titles = search::Index::open()
+        .and_then(|index| index.titles())
+        .unwrap_or_default();
+    let title = |id: i64| {
+        titles
+            .iter()
+            .find(|(other, _)| *other == id)
+            .map(|(_, title)| title.clone())
+    };
+
+    let table = Aliases::load();
+    let ids = titles.iter().map(|&(id, _)| (id.to_string(), id));
+    let aliases = table
+        .table()
+        .iter()
+        .map(|(alias, &id)| (alias.clone(), id));
+    ids.chain(aliases)
+        .filter(|(value, _)| value.starts_with(current))
+        .map(|(value, id)| CompletionCandidate::new(value).help(title(id).map(Into::into)))
+        .collect()
+}
@@ -1,103 +1,1015 @@
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
+#[cfg(feature = "directories")]
 use directories::ProjectDirs;
+use serde::de::{DeserializeOwned, IntoDeserializer};
 use serde::{Deserialize, Serialize};
-use std::fs;
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 
-const APP_NAME: &str = "todo-cli";
+use crate::locale::Locale;
+use crate::lock;
+use crate::secret::SecretString;
+use crate::secrets::{self, Kind, Secret};
+use crate::wip;
+
+pub const APP_NAME: &str = "todo-cli";
+#[cfg(feature = "directories")]
 const ORG_NAME: &str = "go-api-starter";
 
-#[derive(Debug, Default, Serialize, Deserialize)]
+/// Current on-disk config schema. Bump it and append to `MIGRATIONS` when a
+/// key is renamed or restructured.
+pub const CONFIG_VERSION: u32 = 1;
+
+/// `MIGRATIONS[n]` upgrades a version `n` table to version `n + 1`.
+const MIGRATIONS: &[fn(&mut toml::Table)] = &[
+    // v0 -> v1: files written before the `version` key existed; the layout
+    // itself is unchanged
+    |_| {},
+];
+
+static CONFIG_PATH: OnceLock<PathBuf> = OnceLock::new();
+
+/// Points every later `Config::load` at `path` instead of the default
+/// location (`--config`/`TODO_CONFIG`).
+pub fn use_path(path: PathBuf) {
+    let _ = CONFIG_PATH.set(path);
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct Config {
+    #[serde(default)]
+    pub version: u32,
+
     #[serde(default)]
     pub api_url: Option<String>,
 
+    /// How requests are authenticated beyond the bearer token
+    #[serde(default)]
+    pub auth_scheme: AuthScheme,
+
+    /// Where tokens and other secrets are stored: `keyring` or `pass`
+    #[serde(default)]
+    pub secret_backend: secrets::Backend,
+
+    /// Output format used when `--format` isn't given
+    #[serde(default)]
+    pub format: Option<String>,
+
+    /// Request timeout in seconds (30 when unset)
+    #[serde(default)]
+    pub timeout: Option<u64>,
+
+    /// Largest response body accepted, in MiB (16 when unset)
+    #[serde(default)]
+    pub max_response_mb: Option<u64>,
+
+    /// Whether deleting or changing todos asks before going ahead
+    #[serde(default)]
+    pub confirm: ConfirmPolicy,
+
+    /// Append JSON-formatted logs here in addition to stderr
+    #[serde(default)]
+    pub log_file: Option<PathBuf>,
+
+    /// Appended to the User-Agent header to identify wrapping tools
+    #[serde(default)]
+    pub user_agent_suffix: Option<String>,
+
+    /// OTLP collector for trace export (builds with the `otel` feature)
+    #[serde(default)]
+    pub otlp_endpoint: Option<String>,
+
+    /// Email of the last successful login, offered when re-authenticating
+    #[serde(default)]
+    pub last_email: Option<String>,
+
+    /// Workspace selected with `workspace switch`; unset means personal todos
+    #[serde(default)]
+    pub workspace: Option<i64>,
+
+    /// Link template for sharing a todo, with `{id}` replaced, e.g.
+    /// `https://todo.example.com/todos/{id}`
+    #[serde(default)]
+    pub web_url: Option<String>,
+
+    /// List overdue todos first in the default `list` output
+    #[serde(default)]
+    pub overdue_first: bool,
+
+    /// Locale for dates and numbers, e.g. `de_DE`; unset follows the system
+    #[serde(default)]
+    pub locale: Option<String>,
+
+    /// Saved filter that a plain `list` applies
+    #[serde(default)]
+    pub default_filter: Option<String>,
+
+    /// Refuse every request that could change something on the server
+    #[serde(default)]
+    pub read_only: bool,
+
+    /// Work from the local cache, queuing changes for `todo sync`
+    #[serde(default)]
+    pub offline: bool,
+
+    /// Most todos in progress at once; unset means no limit
+    #[serde(default)]
+    pub wip_limit: Option<usize>,
+
+    /// What `start-work` does once `wip_limit` is reached: warn or block
+    #[serde(default)]
+    pub wip_policy: wip::Policy,
+
+    /// Todos to complete each week, tracked by `todo goals`
+    #[serde(default)]
+    pub goal_per_week: Option<usize>,
+
+    /// Log in from TODO_EMAIL and TODO_PASSWORD when the token is missing,
+    /// expired or rejected, instead of failing
+    #[serde(default)]
+    pub login_if_needed: bool,
+
+    #[serde(default)]
+    pub theme: ThemeConfig,
+
+    /// The `[headers]` section: extra headers sent with every request
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+
+    /// The `[filters]` section: `list` flags saved under a name, run with
+    /// `todo list @name`
+    #[serde(default)]
+    pub filters: BTreeMap<String, String>,
+
+    /// The `[places]` section: `lat,lon` of place tags like `home`, for
+    /// locations in ICS export
+    #[serde(default)]
+    pub places: BTreeMap<String, String>,
+
+    #[serde(default)]
+    pub oidc: OidcConfig,
+
     #[serde(skip)]
-    token: Option<String>,
+    token: Option<SecretString>,
 
     #[serde(skip)]
     config_path: Option<PathBuf>,
 }
 
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuthScheme {
+    /// Bearer token from `auth login`
+    #[default]
+    Bearer,
+    /// Bearer token plus an HMAC signature over each request, keyed by a
+    /// shared secret stored with `auth set-secret`
+    Hmac,
+    /// The session cookie set by `auth login`, for servers without JWT
+    Session,
+    /// HTTP Basic credentials stored with `auth set-basic`, for APIs behind
+    /// a basic-auth proxy; replaces the bearer token
+    Basic,
+}
+
+impl AuthScheme {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AuthScheme::Bearer => "bearer",
+            AuthScheme::Hmac => "hmac",
+            AuthScheme::Session => "session",
+            AuthScheme::Basic => "basic",
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfirmPolicy {
+    #[default]
+    Always,
+    /// Act as if `--force`/`--yes` were always passed
+    Never,
+}
+
+impl ConfirmPolicy {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ConfirmPolicy::Always => "always",
+            ConfirmPolicy::Never => "never",
+        }
+    }
+}
+
+/// The `[oidc]` section, for `auth login --browser` against an SSO provider.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OidcConfig {
+    /// Issuer URL; endpoints come from its `/.well-known/openid-configuration`
+    #[serde(default)]
+    pub issuer: Option<String>,
+    /// Public client registered with the provider for this CLI
+    #[serde(default)]
+    pub client_id: Option<String>,
+    #[serde(default = "default_scopes")]
+    pub scopes: Vec<String>,
+}
+
+impl Default for OidcConfig {
+    fn default() -> Self {
+        Self {
+            issuer: None,
+            client_id: None,
+            scopes: default_scopes(),
+        }
+    }
+}
+
+fn default_scopes() -> Vec<String> {
+    vec!["openid".to_string(), "email".to_string()]
+}
+
+/// The `[theme]` section: a built-in preset plus per-role overrides written
+/// as style specs such as `"bold blue"` or `"#859900"`.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct ThemeConfig {
+    /// `default`, `solarized` or `mono`
+    #[serde(default)]
+    pub preset: Option<String>,
+    #[serde(default)]
+    pub completed: Option<String>,
+    #[serde(default)]
+    pub pending: Option<String>,
+    #[serde(default)]
+    pub id: Option<String>,
+    #[serde(default)]
+    pub header: Option<String>,
+    #[serde(default)]
+    pub overdue: Option<String>,
+    #[serde(default)]
+    pub in_progress: Option<String>,
+    #[serde(default)]
+    pub blocked: Option<String>,
+}
+
 impl Config {
     pub fn load() -> Result<Self> {
         let config_path = Self::config_path()?;
-
-        let mut config = if config_path.exists() {
-            let content = fs::read_to_string(&config_path)
-                .context("Failed to read config file")?;
-            toml::from_str(&content).unwrap_or_default()
+        let content = if config_path.exists() {
+            lock::shared(&config_path, || read_config(&config_path))?
         } else {
-            Config::default()
+            None
         };
+        let (config, migrated) = Self::from_file(&config_path, content.as_deref())?;
 
-        config.config_path = Some(config_path);
+        if let (true, Some(content)) = (migrated, content) {
+            let backup = config_path.with_extension("toml.bak");
+            // A read-only config is still usable; it just migrates again next time
+            let saved = fs::write(&backup, content)
+                .context("Failed to back up config file")
+                .and_then(|_| config.save());
+            if let Err(err) = saved {
+                eprintln!("Warning: failed to save migrated config: {:#}", err);
+            }
+        }
+        Ok(config)
+    }
 
-        // Try to load token from keyring
-        config.token = Self::load_token_from_keyring().ok();
+    /// Loads the config, lets `change` modify it and saves it, holding the
+    /// file's lock throughout so that concurrent updates, e.g. two `todo
+    /// config set` at once, don't overwrite each other.
+    pub fn update<T>(change: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        let config_path = Self::config_path()?;
+        lock::exclusive(&config_path, || {
+            let content = read_config(&config_path)?;
+            let (mut config, migrated) = Self::from_file(&config_path, content.as_deref())?;
+            if let (true, Some(content)) = (migrated, content) {
+                fs::write(config_path.with_extension("toml.bak"), content)
+                    .context("Failed to back up config file")?;
+            }
+            let value = change(&mut config)?;
+            config.write()?;
+            Ok(value)
+        })
+    }
+
+    /// The config in `content`, read from `path`, or the defaults without a
+    /// file. Also returns whether any migration ran.
+    fn from_file(path: &Path, content: Option<&str>) -> Result<(Self, bool)> {
+        let (mut config, migrated) = match content {
+            Some(content) => Self::parse(content)
+                .with_context(|| format!("Invalid config file {}", path.display()))?,
+            None => (
+                Config {
+                    version: CONFIG_VERSION,
+                    ..Default::default()
+                },
+                false,
+            ),
+        };
+        config.config_path = Some(path.to_path_buf());
+        Ok((config, migrated))
+    }
+
+    /// Parses a config file written by any known version, upgrading it to
+    /// `CONFIG_VERSION`. Also returns whether any migration ran.
+    fn parse(content: &str) -> Result<(Self, bool)> {
+        Self::from_table(toml::from_str(content)?)
+    }
+
+    fn from_table(mut table: toml::Table) -> Result<(Self, bool)> {
+        let version = match table.get("version") {
+            None => 0,
+            Some(value) => value
+                .as_integer()
+                .and_then(|v| u32::try_from(v).ok())
+                .context("`version` must be a non-negative integer")?,
+        };
+        if version > CONFIG_VERSION {
+            anyhow::bail!(
+                "Config version {} is newer than this CLI supports ({}); upgrade todo-cli",
+                version,
+                CONFIG_VERSION
+            );
+        }
+
+        for migrate in &MIGRATIONS[version as usize..] {
+            migrate(&mut table);
+        }
+        table.insert(
+            "version".to_string(),
+            toml::Value::Integer(i64::from(CONFIG_VERSION)),
+        );
+
+        let config = toml::Value::Table(table).try_into()?;
+        Ok((config, version < CONFIG_VERSION))
+    }
 
+    /// The config as a TOML table, for `config export`.
+    pub fn to_table(&self) -> Result<toml::Table> {
+        Ok(toml::Table::try_from(self)?)
+    }
+
+    /// Replaces the config file with `table` from `config export`, which may
+    /// come from an older CLI. The current file is kept as a backup.
+    pub fn import(table: toml::Table) -> Result<Self> {
+        let (mut config, _) = Self::from_table(table).context("Invalid config in bundle")?;
+        let path = Self::config_path()?;
+        config.config_path = Some(path.clone());
+        lock::exclusive(&path, || {
+            if path.exists() {
+                fs::copy(&path, path.with_extension("toml.bak"))
+                    .context("Failed to back up config file")?;
+            }
+            config.write()
+        })?;
         Ok(config)
     }
 
-    pub fn save(&self) -> Result<()> {
+    fn save(&self) -> Result<()> {
+        match self.config_path {
+            Some(ref path) => lock::exclusive(path, || self.write()),
+            None => Ok(()),
+        }
+    }
+
+    /// Writes the file; the caller holds its lock.
+    fn write(&self) -> Result<()> {
         if let Some(ref path) = self.config_path {
             if let Some(parent) = path.parent() {
                 fs::create_dir_all(parent).context("Failed to create config directory")?;
             }
 
             let content = toml::to_string_pretty(self)?;
-            fs::write(path, content).context("Failed to write config file")?;
+            write_atomically(path, content.as_bytes()).context("Failed to write config file")?;
         }
         Ok(())
     }
 
     fn config_path() -> Result<PathBuf> {
+        match CONFIG_PATH.get() {
+            Some(path) => Ok(path.clone()),
+            None => Self::default_path(),
+        }
+    }
+
+    #[cfg(feature = "directories")]
+    fn default_path() -> Result<PathBuf> {
         let proj_dirs = ProjectDirs::from("", ORG_NAME, APP_NAME)
             .context("Failed to determine config directory")?;
 
         Ok(proj_dirs.config_dir().join("config.toml"))
     }
 
-    pub fn get_token(&self) -> Option<String> {
+    /// XDG-style lookup for builds without the `directories` crate.
+    #[cfg(not(feature = "directories"))]
+    fn default_path() -> Result<PathBuf> {
+        let base = std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+            .context("Failed to determine config directory")?;
+
+        Ok(base.join(APP_NAME).join("config.toml"))
+    }
+
+    pub fn get_token(&self) -> Option<SecretString> {
         self.token.clone()
     }
 
-    pub fn set_token(token: &str) -> Result<()> {
-        let entry = keyring::Entry::new(APP_NAME, "api_token")
-            .context("Failed to create keyring entry")?;
-        entry.set_password(token)
-            .context("Failed to save token to keyring")?;
-        Ok(())
+    pub fn set_token(api_url: &str, token: &str) -> Result<()> {
+        secrets::set(Secret::new(Kind::Token, api_url), token)
     }
 
-    pub fn clear_token() -> Result<()> {
-        let entry = keyring::Entry::new(APP_NAME, "api_token")
-            .context("Failed to create keyring entry")?;
-        // Ignore error if token doesn't exist
-        let _ = entry.delete_credential();
-        Ok(())
+    pub fn clear_token(api_url: &str) -> Result<()> {
+        secrets::delete(Secret::new(Kind::Token, api_url))
+    }
+
+    /// Stores the shared secret used to sign requests to `api_url`.
+    pub fn set_hmac_secret(api_url: &str, secret: &str) -> Result<()> {
+        secrets::set(Secret::new(Kind::HmacSecret, api_url), secret)
+    }
+
+    pub fn hmac_secret(api_url: &str) -> Result<String> {
+        secrets::get(Secret::new(Kind::HmacSecret, api_url))?
+            .context("No signing secret stored; run `todo auth set-secret`")
+    }
+
+    pub fn set_basic_auth(api_url: &str, username: &str, password: &str) -> Result<()> {
+        let credentials = format!("{}:{}", username, password);
+        secrets::set(Secret::new(Kind::BasicAuth, api_url), &credentials)
     }
 
-    fn load_token_from_keyring() -> Result<String> {
-        let entry = keyring::Entry::new(APP_NAME, "api_token")
-            .context("Failed to create keyring entry")?;
-        entry.get_password()
-            .context("Failed to get token from keyring")
+    /// The stored Basic credentials for `api_url` as (username, password).
+    pub fn basic_auth(api_url: &str) -> Result<(String, String)> {
+        let credentials = secrets::get(Secret::new(Kind::BasicAuth, api_url))?
+            .context("No basic auth credentials stored; run `todo auth set-basic`")?;
+        split_credentials(&credentials)
     }
 
-    pub fn set_url(&mut self, url: &str) -> Result<()> {
-        self.api_url = Some(url.to_string());
-        self.save()
+    /// Loads the stored token for `api_url`, if there is one.
+    /// `TODO_TOKEN` wins over the stored token, so pipelines can authenticate
+    /// without logging in.
+    pub fn load_token(&mut self, api_url: &str) {
+        self.token = std::env::var(Kind::Token.env())
+            .ok()
+            .filter(|token| !token.is_empty())
+            .or_else(|| secrets::get(Secret::new(Kind::Token, api_url)).ok().flatten())
+            // An empty token would be sent as `Bearer ` and always refused
+            .filter(|token| !token.is_empty())
+            .map(SecretString::from);
+    }
+
+    pub fn remember_email(email: &str) -> Result<()> {
+        Self::update(|config| {
+            config.last_email = Some(email.to_string());
+            Ok(())
+        })
+    }
+
+    /// The current value of a registry key, or `None` when it's unset.
+    pub fn get(&self, name: &str) -> Result<Option<String>> {
+        Ok((key(name)?.get)(self))
+    }
+
+    /// Validates `value` for a registry key and sets it, for
+    /// [`Config::update`] to save.
+    pub fn set(&mut self, name: &str, value: &str) -> Result<()> {
+        let key = key(name)?;
+        (key.set)(self, value).with_context(|| format!("Invalid value for {}", key.name))
+    }
+
+    /// Resets a registry key to its default.
+    pub fn unset(&mut self, name: &str) -> Result<()> {
+        (key(name)?.unset)(self);
+        Ok(())
     }
 
     pub fn print(&self) {
         println!("Configuration:");
-        println!("  Config file: {:?}", self.config_path);
+        println!("  Config file: {:?} (version {})", self.config_path, self.version);
         println!("  API URL: {}", self.api_url.as_deref().unwrap_or("(default)"));
+        println!("  Auth scheme: {}", self.auth_scheme.as_str());
+        println!("  Secret backend: {}", self.secret_backend.as_str());
+        println!("  Format: {}", self.format.as_deref().unwrap_or("text"));
+        match self.timeout {
+            Some(seconds) => println!("  Timeout: {}s", seconds),
+            None => println!("  Timeout: (default)"),
+        }
+        match self.max_response_mb {
+            Some(mb) => println!("  Max response size: {} MiB", mb),
+            None => println!("  Max response size: (default)"),
+        }
+        println!("  Confirm: {}", self.confirm.as_str());
+        println!("  Log file: {:?}", self.log_file);
+        println!(
+            "  User-Agent suffix: {}",
+            self.user_agent_suffix.as_deref().unwrap_or("(none)")
+        );
+        println!(
+            "  OTLP endpoint: {}",
+            self.otlp_endpoint.as_deref().unwrap_or("(none)")
+        );
+        match self.workspace {
+            Some(id) => println!("  Workspace: {}", id),
+            None => println!("  Workspace: (personal)"),
+        }
+        for name in self.headers.keys() {
+            println!("  Header: {}", name);
+        }
+        println!("  Locale: {}", self.locale.as_deref().unwrap_or("(system)"));
+        if self.read_only {
+            println!("  Read-only: yes");
+        }
+        if self.offline {
+            println!("  Offline: yes");
+        }
+        if let Some(limit) = self.wip_limit {
+            println!("  WIP limit: {} ({})", limit, self.wip_policy.as_str());
+        }
+        if let Some(goal) = self.goal_per_week {
+            println!("  Weekly goal: {} todos", goal);
+        }
+        if self.login_if_needed {
+            println!("  Login if needed: yes");
+        }
+        for (name, args) in &self.filters {
+            let default = match self.default_filter {
+                Some(ref default) if default == name => " (default)",
+                _ => "",
+            };
+            println!("  Filter @{}: {}{}", name, args, default);
+        }
+        for (name, position) in &self.places {
+            println!("  Place @{}: {}", name, position);
+        }
+        println!(
+            "  Theme: {}",
+            self.theme.preset.as_deref().unwrap_or("default")
+        );
         println!("  Token: {}", if self.token.is_some() { "✓ stored" } else { "✗ not set" });
     }
 
+    /// Where a todo can be opened: the configured `web_url`, or the API
+    /// resource itself when no web frontend is configured.
+    pub fn todo_link(&self, api_url: &str, id: i64) -> String {
+        match self.web_url {
+            Some(ref template) => template.replace("{id}", &id.to_string()),
+            None => format!("{}/api/v1/todos/{}", api_url.trim_end_matches('/'), id),
+        }
+    }
+
     pub fn has_token(&self) -> bool {
         self.token.is_some()
     }
 }
+
+/// Directory for data the CLI keeps between runs (history, aliases). Only
+/// Linux has a dedicated state directory; elsewhere local data is used.
+#[cfg(feature = "directories")]
+pub fn state_dir() -> Result<PathBuf> {
+    let proj_dirs = ProjectDirs::from("", ORG_NAME, APP_NAME)
+        .context("Failed to determine state directory")?;
+
+    Ok(proj_dirs
+        .state_dir()
+        .unwrap_or_else(|| proj_dirs.data_local_dir())
+        .to_path_buf())
+}
+
+/// XDG-style lookup for builds without the `directories` crate.
+#[cfg(not(feature = "directories"))]
+pub fn state_dir() -> Result<PathBuf> {
+    let base = std::env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| {
+            std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/state"))
+        })
+        .context("Failed to determine state directory")?;
+
+    Ok(base.join(APP_NAME))
+}
+
+/// The config file's content, or `None` when there is none yet.
+fn read_config(path: &Path) -> Result<Option<String>> {
+    match fs::read_to_string(path) {
+        Ok(content) => Ok(Some(content)),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err).context("Failed to read config file"),
+    }
+}
+
+/// Replaces `path` with `content` by writing a temporary file next to it
+/// and renaming it over, so a crash mid-write leaves the old file rather
+/// than a truncated one. The file is readable by its owner only, since it
+/// can hold a token when there is no keyring.
+fn write_atomically(path: &Path, content: &[u8]) -> Result<()> {
+    let mut name = path.file_name().context("Config path has no file name")?.to_os_string();
+    name.push(".tmp");
+    let tmp = path.with_file_name(name);
+
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options
+        .open(&tmp)
+        .with_context(|| format!("Failed to create {}", tmp.display()))?;
+    // `mode` only applies to new files; a leftover one keeps its own
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    }
+    file.write_all(content)?;
+    file.sync_all()?;
+    drop(file);
+
+    fs::rename(&tmp, path).with_context(|| format!("Failed to replace {}", path.display()))?;
+    // Make the rename itself durable
+    #[cfg(unix)]
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::File::open(dir)?.sync_all()?;
+    }
+    Ok(())
+}
+
+/// Splits stored `username:password` credentials; Basic auth usernames
+/// can't contain a colon, so the first one separates them.
+fn split_credentials(credentials: &str) -> Result<(String, String)> {
+    let (username, password) = credentials
+        .split_once(':')
+        .context("Basic auth credentials must look like username:password")?;
+    Ok((username.to_string(), password.to_string()))
+}
+
+/// A setting `config get/set/unset` can address. Keys inside a section are
+/// dotted, e.g. `theme.preset`.
+pub struct Key {
+    pub name: &'static str,
+    pub help: &'static str,
+    get: fn(&Config) -> Option<String>,
+    /// Parses and validates the value before storing it
+    set: fn(&mut Config, &str) -> Result<()>,
+    unset: fn(&mut Config),
+}
+
+/// Every key `config set` accepts. Secrets are not here: they go through
+/// `auth` commands into the secret store.
+pub const KEYS: &[Key] = &[
+    Key {
+        name: "api_url",
+        help: "API server URL",
+        get: |c| c.api_url.clone(),
+        set: |c, v| {
+            c.api_url = Some(parse_url(v)?);
+            Ok(())
+        },
+        unset: |c| c.api_url = None,
+    },
+    Key {
+        name: "auth_scheme",
+        help: "bearer, hmac, session or basic",
+        get: |c| Some(c.auth_scheme.as_str().to_string()),
+        set: |c, v| {
+            c.auth_scheme = parse_enum(v)?;
+            Ok(())
+        },
+        unset: |c| c.auth_scheme = AuthScheme::default(),
+    },
+    Key {
+        name: "secret_backend",
+        help: "keyring (falls back when unavailable), pass, encrypted or env",
+        get: |c| Some(c.secret_backend.as_str().to_string()),
+        set: |c, v| {
+            c.secret_backend = parse_enum(v)?;
+            Ok(())
+        },
+        unset: |c| c.secret_backend = secrets::Backend::default(),
+    },
+    Key {
+        name: "format",
+        help: "Default output format: text, json, csv, tsv or markdown",
+        get: |c| c.format.clone(),
+        set: |c, v| {
+            if !["text", "json", "csv", "tsv", "markdown"].contains(&v) {
+                bail!("expected text, json, csv, tsv or markdown");
+            }
+            c.format = Some(v.to_string());
+            Ok(())
+        },
+        unset: |c| c.format = None,
+    },
+    Key {
+        name: "timeout",
+        help: "Request timeout in seconds",
+        get: |c| c.timeout.map(|seconds| seconds.to_string()),
+        set: |c, v| match v.parse::<u64>() {
+            Ok(0) | Err(_) => bail!("expected a positive number of seconds"),
+            Ok(seconds) => {
+                c.timeout = Some(seconds);
+                Ok(())
+            }
+        },
+        unset: |c| c.timeout = None,
+    },
+    Key {
+        name: "max_response_mb",
+        help: "Largest response body accepted, in MiB",
+        get: |c| c.max_response_mb.map(|mb| mb.to_string()),
+        set: |c, v| match v.parse::<u64>() {
+            Ok(0) | Err(_) => bail!("expected a positive number of MiB"),
+            Ok(mb) => {
+                c.max_response_mb = Some(mb);
+                Ok(())
+            }
+        },
+        unset: |c| c.max_response_mb = None,
+    },
+    Key {
+        name: "confirm",
+        help: "Ask before deleting or changing todos: always or never",
+        get: |c| Some(c.confirm.as_str().to_string()),
+        set: |c, v| {
+            c.confirm = parse_enum(v)?;
+            Ok(())
+        },
+        unset: |c| c.confirm = ConfirmPolicy::default(),
+    },
+    Key {
+        name: "workspace",
+        help: "Workspace ID for todo commands (see `workspace switch`)",
+        get: |c| c.workspace.map(|id| id.to_string()),
+        set: |c, v| {
+            c.workspace = Some(v.parse().context("expected a workspace ID")?);
+            Ok(())
+        },
+        unset: |c| c.workspace = None,
+    },
+    Key {
+        name: "web_url",
+        help: "Link template for sharing a todo, with {id} replaced",
+        get: |c| c.web_url.clone(),
+        set: |c, v| {
+            if !v.contains("{id}") {
+                bail!("the template needs an {{id}} placeholder");
+            }
+            c.web_url = Some(parse_url(v)?);
+            Ok(())
+        },
+        unset: |c| c.web_url = None,
+    },
+    Key {
+        name: "overdue_first",
+        help: "List overdue todos first: true or false",
+        get: |c| Some(c.overdue_first.to_string()),
+        set: |c, v| {
+            c.overdue_first = v.parse().context("expected true or false")?;
+            Ok(())
+        },
+        unset: |c| c.overdue_first = false,
+    },
+    Key {
+        name: "locale",
+        help: "Locale for dates and numbers, e.g. en_US or de_DE",
+        get: |c| c.locale.clone(),
+        set: |c, v| {
+            Locale::parse(v)?;
+            c.locale = Some(v.to_string());
+            Ok(())
+        },
+        unset: |c| c.locale = None,
+    },
+    Key {
+        name: "default_filter",
+        help: "Saved filter applied by a plain `list`",
+        get: |c| c.default_filter.clone(),
+        set: |c, v| {
+            let name = v.trim_start_matches('@');
+            if !c.filters.contains_key(name) {
+                bail!("no saved filter '{}'; see `todo filter list`", name);
+            }
+            c.default_filter = Some(name.to_string());
+            Ok(())
+        },
+        unset: |c| c.default_filter = None,
+    },
+    Key {
+        name: "read_only",
+        help: "Refuse requests that change anything: true or false",
+        get: |c| Some(c.read_only.to_string()),
+        set: |c, v| {
+            c.read_only = v.parse().context("expected true or false")?;
+            Ok(())
+        },
+        unset: |c| c.read_only = false,
+    },
+    Key {
+        name: "offline",
+        help: "Work from the local cache until `todo sync`: true or false",
+        get: |c| Some(c.offline.to_string()),
+        set: |c, v| {
+            c.offline = v.parse().context("expected true or false")?;
+            Ok(())
+        },
+        unset: |c| c.offline = false,
+    },
+    Key {
+        name: "wip_limit",
+        help: "Most todos in progress at once",
+        get: |c| c.wip_limit.map(|limit| limit.to_string()),
+        set: |c, v| match v.parse::<usize>() {
+            Ok(0) | Err(_) => bail!("expected a positive number of todos"),
+            Ok(limit) => {
+                c.wip_limit = Some(limit);
+                Ok(())
+            }
+        },
+        unset: |c| c.wip_limit = None,
+    },
+    Key {
+        name: "wip_policy",
+        help: "What start-work does at the WIP limit: warn or block",
+        get: |c| Some(c.wip_policy.as_str().to_string()),
+        set: |c, v| {
+            c.wip_policy = parse_enum(v)?;
+            Ok(())
+        },
+        unset: |c| c.wip_policy = wip::Policy::default(),
+    },
+    Key {
+        name: "goal_per_week",
+        help: "Todos to complete each week",
+        get: |c| c.goal_per_week.map(|goal| goal.to_string()),
+        set: |c, v| match v.parse::<usize>() {
+            Ok(0) | Err(_) => bail!("expected a positive number of todos"),
+            Ok(goal) => {
+                c.goal_per_week = Some(goal);
+                Ok(())
+            }
+        },
+        unset: |c| c.goal_per_week = None,
+    },
+    Key {
+        name: "login_if_needed",
+        help: "Log in from TODO_EMAIL/TODO_PASSWORD when needed: true or false",
+        get: |c| Some(c.login_if_needed.to_string()),
+        set: |c, v| {
+            c.login_if_needed = v.parse().context("expected true or false")?;
+            Ok(())
+        },
+        unset: |c| c.login_if_needed = false,
+    },
+    Key {
+        name: "log_file",
+        help: "Append JSON logs to this file",
+        get: |c| c.log_file.as_ref().map(|path| path.display().to_string()),
+        set: |c, v| {
+            c.log_file = Some(PathBuf::from(v));
+            Ok(())
+        },
+        unset: |c| c.log_file = None,
+    },
+    Key {
+        name: "user_agent_suffix",
+        help: "Product token appended to the User-Agent",
+        get: |c| c.user_agent_suffix.clone(),
+        set: |c, v| {
+            reqwest::header::HeaderValue::from_str(v).context("not a valid header value")?;
+            c.user_agent_suffix = Some(v.to_string());
+            Ok(())
+        },
+        unset: |c| c.user_agent_suffix = None,
+    },
+    Key {
+        name: "otlp_endpoint",
+        help: "OTLP collector for trace export",
+        get: |c| c.otlp_endpoint.clone(),
+        set: |c, v| {
+            c.otlp_endpoint = Some(parse_url(v)?);
+            Ok(())
+        },
+        unset: |c| c.otlp_endpoint = None,
+    },
+    Key {
+        name: "theme.preset",
+        help: "default, solarized or mono",
+        get: |c| c.theme.preset.clone(),
+        set: |c, v| set_theme(c, |theme| theme.preset = Some(v.to_string())),
+        unset: |c| c.theme.preset = None,
+    },
+    Key {
+        name: "theme.completed",
+        help: "Style for completed todos, e.g. \"bold green\"",
+        get: |c| c.theme.completed.clone(),
+        set: |c, v| set_theme(c, |theme| theme.completed = Some(v.to_string())),
+        unset: |c| c.theme.completed = None,
+    },
+    Key {
+        name: "theme.pending",
+        help: "Style for pending todos",
+        get: |c| c.theme.pending.clone(),
+        set: |c, v| set_theme(c, |theme| theme.pending = Some(v.to_string())),
+        unset: |c| c.theme.pending = None,
+    },
+    Key {
+        name: "theme.id",
+        help: "Style for todo IDs",
+        get: |c| c.theme.id.clone(),
+        set: |c, v| set_theme(c, |theme| theme.id = Some(v.to_string())),
+        unset: |c| c.theme.id = None,
+    },
+    Key {
+        name: "theme.header",
+        help: "Style for headings",
+        get: |c| c.theme.header.clone(),
+        set: |c, v| set_theme(c, |theme| theme.header = Some(v.to_string())),
+        unset: |c| c.theme.header = None,
+    },
+    Key {
+        name: "theme.overdue",
+        help: "Style for overdue todos",
+        get: |c| c.theme.overdue.clone(),
+        set: |c, v| set_theme(c, |theme| theme.overdue = Some(v.to_string())),
+        unset: |c| c.theme.overdue = None,
+    },
+    Key {
+        name: "theme.in_progress",
+        help: "Style for todos in progress",
+        get: |c| c.theme.in_progress.clone(),
+        set: |c, v| set_theme(c, |theme| theme.in_progress = Some(v.to_string())),
+        unset: |c| c.theme.in_progress = None,
+    },
+    Key {
+        name: "theme.blocked",
+        help: "Style for blocked todos",
+        get: |c| c.theme.blocked.clone(),
+        set: |c, v| set_theme(c, |theme| theme.blocked = Some(v.to_string())),
+        unset: |c| c.theme.blocked = None,
+    },
+    Key {
+        name: "oidc.issuer",
+        help: "OpenID Connect issuer for `auth login --browser`",
+        get: |c| c.oidc.issuer.clone(),
+        set: |c, v| {
+            c.oidc.issuer = Some(parse_url(v)?);
+            Ok(())
+        },
+        unset: |c| c.oidc.issuer = None,
+    },
+    Key {
+        name: "oidc.client_id",
+        help: "Client ID registered with the identity provider",
+        get: |c| c.oidc.client_id.clone(),
+        set: |c, v| {
+            c.oidc.client_id = Some(v.to_string());
+            Ok(())
+        },
+        unset: |c| c.oidc.client_id = None,
+    },
+    Key {
+        name: "oidc.scopes",
+        help: "Space-separated scopes to request",
+        get: |c| Some(c.oidc.scopes.join(" ")),
+        set: |c, v| {
+            c.oidc.scopes = v.split_whitespace().map(str::to_string).collect();
+            Ok(())
+        },
+        unset: |c| c.oidc.scopes = default_scopes(),
+    },
+];
+
+fn key(name: &str) -> Result<&'static Key> {
+    KEYS.iter().find(|key| key.name == name).with_context(|| {
+        let names: Vec<_> = KEYS.iter().map(|key| key.name).collect();
+        format!("Unknown config key '{}'; expected one of {}", name, names.join(", "))
+    })
+}
+
+fn parse_url(value: &str) -> Result<String> {
+    let url = reqwest::Url::parse(value).context("expected an absolute URL")?;
+    if !matches!(url.scheme(), "http" | "https") {
+        bail!("expected an http or https URL");
+    }
+    Ok(value.to_string())
+}
+
+/// Parses a value the way the config file would, so `set` accepts exactly
+/// the spellings the file does.
+fn parse_enum<T: DeserializeOwned>(value: &str) -> Result<T> {
+    T::deserialize(value.into_deserializer())
+        .map_err(|err: serde::de::value::Error| anyhow::anyhow!(err))
+}
+
+/// Applies a theme change only if the resulting theme still loads.
+fn set_theme(config: &mut Config, change: impl FnOnce(&mut ThemeConfig)) -> Result<()> {
+    let mut theme = config.theme.clone();
+    change(&mut theme);
+    crate::output::Theme::from_config(&theme)?;
+    config.theme = theme;
+    Ok(())
+}
@@ -0,0 +1,103 @@
+//! GTD contexts: `todo context set @office` narrows `list`, `today` and
+//! `search` to todos tagged `office` until `todo context clear`. The
+//! active context is kept per machine in the state directory.
+
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{Context as _, Result, bail};
+use serde_json::json;
+
+use crate::api::Todo;
+use crate::style::Colorize;
+use crate::{config, lock, output};
+
+/// The context in effect: the tag a todo needs to be shown.
+#[derive(Debug, Clone)]
+pub struct Active {
+    pub tag: String,
+}
+
+impl Active {
+    /// Reads the active context. Like aliases it is a convenience, so an
+    /// unreadable file means no context rather than an error.
+    pub fn load() -> Option<Self> {
+        let path = path().ok()?;
+        let tag = lock::shared(&path, || Ok(fs::read_to_string(&path)?)).ok()?;
+        let tag = tag.trim();
+        (!tag.is_empty()).then(|| Self {
+            tag: tag.to_string(),
+        })
+    }
+
+    pub fn matches(&self, todo: &Todo) -> bool {
+        todo.tags.contains(&self.tag)
+    }
+
+    /// Drops the todos outside this context.
+    pub fn retain(&self, todos: &mut Vec<Todo>) {
+        todos.retain(|todo| self.matches(todo));
+    }
+
+    /// The dimmed line above output narrowed by the context.
+    pub fn print_header(&self) {
+        output::say!(
+            "{}",
+            format!("Context @{} (`todo context clear` to see everything)", self.tag).dimmed()
+        );
+    }
+}
+
+/// `@office` or `office` as the tag `office`.
+fn parse(name: &str) -> Result<String> {
+    let tag = name.trim().trim_start_matches('@');
+    if tag.is_empty() || !tag.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_') {
+        bail!("Contexts are tags, e.g. @office: letters, digits, '-' and '_'");
+    }
+    Ok(tag.to_string())
+}
+
+pub fn set(name: &str) -> Result<()> {
+    let tag = parse(name)?;
+    let path = path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).context("Failed to create state directory")?;
+    }
+    lock::exclusive(&path, || {
+        fs::write(&path, format!("{}\n", tag)).context("Failed to save the context")
+    })?;
+    output::success(&format!(
+        "Now in @{}; `list`, `today` and `search` show todos tagged {}.",
+        tag, tag
+    ));
+    output::print_result("context_set", &[], json!({ "context": tag }))
+}
+
+pub fn clear() -> Result<()> {
+    let path = path()?;
+    lock::exclusive(&path, || match fs::remove_file(&path) {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+            Err(err).context("Failed to clear the context")
+        }
+        _ => Ok(()),
+    })?;
+    output::success("Context cleared.");
+    output::print_result("context_cleared", &[], ())
+}
+
+pub fn show() -> Result<()> {
+    let active = Active::load();
+    if output::json() {
+        let tag = active.map(|active| active.tag);
+        return output::print_json("context", &json!({ "context": tag }));
+    }
+    match active {
+        Some(active) => println!("@{}", active.tag),
+        None => println!("{}", "No context; set one with `todo context set @name`.".dimmed()),
+    }
+    Ok(())
+}
+
+fn path() -> Result<PathBuf> {
+    Ok(config::state_dir()?.join("context"))
+}
@@ -0,0 +1,144 @@
+//! `todo depends`: todos that wait on others. A dependency that would
+//! close a loop is refused before anything is sent, and `done` won't
+//! complete a todo while the ones it waits on are still open.
+
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use anyhow::{Result, bail};
+
+use crate::api::{RequestOptions, Todo, TodoApi};
+use crate::error::ApiError;
+
+/// Makes `id` depend on each of `on` as well as what it already waits on.
+pub async fn add<A: TodoApi>(client: &A, id: i64, on: &[i64]) -> Result<Todo> {
+    let todos = client.list_todos(None, &RequestOptions::new().retries(2)).await?;
+    let mut graph: HashMap<i64, Vec<i64>> = todos
+        .iter()
+        .map(|todo| (todo.id, todo.depends_on.clone()))
+        .collect();
+
+    let Some(current) = graph.get(&id).cloned() else {
+        bail!("No todo #{}", id);
+    };
+    let mut depends_on = current.clone();
+    for &other in on {
+        if other == id {
+            bail!("#{} can't depend on itself", id);
+        }
+        if !graph.contains_key(&other) {
+            bail!("No todo #{}", other);
+        }
+        if let Some(path) = path(&graph, other, id) {
+            let cycle: Vec<_> = std::iter::once(id)
+                .chain(path)
+                .map(|id| format!("#{}", id))
+                .collect();
+            bail!(
+                "#{} can't depend on #{}: that would make a cycle ({})",
+                id,
+                other,
+                cycle.join(" → ")
+            );
+        }
+        if !depends_on.contains(&other) {
+            depends_on.push(other);
+            // Later IDs in `on` must not loop back through this one either
+            graph.insert(id, depends_on.clone());
+        }
+    }
+    if depends_on == current {
+        bail!("#{} already depends on {}", id, list(on));
+    }
+    Ok(client.set_dependencies(id, &depends_on, &RequestOptions::default()).await?)
+}
+
+/// Stops `id` waiting on each of `on`.
+pub async fn remove<A: TodoApi>(client: &A, id: i64, on: &[i64]) -> Result<Todo> {
+    let todo = client.get_todo(id, &RequestOptions::new().retries(2)).await?;
+    let depends_on: Vec<_> = todo
+        .depends_on
+        .iter()
+        .copied()
+        .filter(|dep| !on.contains(dep))
+        .collect();
+    if depends_on.len() == todo.depends_on.len() {
+        bail!("#{} doesn't depend on {}", id, list(on));
+    }
+    Ok(client.set_dependencies(id, &depends_on, &RequestOptions::default()).await?)
+}
+
+/// Fails when a dependency of `id` is still open. Dependencies that have
+/// since been deleted don't count.
+pub async fn check_done<A: TodoApi>(client: &A, id: i64, opts: &RequestOptions) -> Result<()> {
+    let todo = client.get_todo(id, opts).await?;
+    let mut open = Vec::new();
+    for &dep in &todo.depends_on {
+        match client.get_todo(dep, opts).await {
+            Ok(dep) if !dep.completed => open.push(dep.id),
+            Ok(_) => {}
+            Err(ApiError::NotFound { .. }) => {}
+            Err(err) => return Err(err.into()),
+        }
+    }
+    if !open.is_empty() {
+        bail!("#{} is blocked by {} (complete them first or use --force)", id, list(&open));
+    }
+    Ok(())
+}
+
+/// The open dependencies of each open todo in `todos`, for the list view.
+/// Dependencies missing from `todos` are taken as done or deleted.
+pub fn open_blockers(todos: &[Todo]) -> HashMap<i64, Vec<i64>> {
+    let open: HashSet<i64> = todos
+        .iter()
+        .filter(|todo| !todo.completed)
+        .map(|todo| todo.id)
+        .collect();
+    todos
+        .iter()
+        .filter(|todo| !todo.completed)
+        .filter_map(|todo| {
+            let blockers: Vec<_> = todo
+                .depends_on
+                .iter()
+                .copied()
+                .filter(|dep| open.contains(dep))
+                .collect();
+            (!blockers.is_empty()).then_some((todo.id, blockers))
+        })
+        .collect()
+}
+
+/// The dependency chain from `from` to `to`, both included, if there is one.
+fn path(graph: &HashMap<i64, Vec<i64>>, from: i64, to: i64) -> Option<Vec<i64>> {
+    let mut previous = HashMap::from([(from, from)]);
+    let mut queue = VecDeque::from([from]);
+    while let Some(id) = queue.pop_front() {
+        if id == to {
+            let mut path = vec![to];
+            let mut at = to;
+            while at != from {
+                at = previous[&at];
+                path.push(at);
+            }
+            path.reverse();
+            return Some(path);
+        }
+        for &next in graph.get(&id).into_iter().flatten() {
+            if let Entry::Vacant(entry) = previous.entry(next) {
+                entry.insert(id);
+                queue.push_back(next);
+            }
+        }
+    }
+    None
+}
+
+/// `#1`, or `#1, #2` for several.
+pub fn list(ids: &[i64]) -> String {
+    ids.iter()
+        .map(|id| format!("#{}", id))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
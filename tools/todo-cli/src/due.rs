@@ -0,0 +1,129 @@
+use chrono::{DateTime, Duration, Local, NaiveDate, Utc, Weekday};
+use serde::Serialize;
+
+use crate::api::Todo;
+
+/// Parses a span like `3d`, `12h`, `2w` or `45m` for `--due-within`.
+pub fn parse_span(s: &str) -> Result<Duration, String> {
+    let (amount, unit) = s.split_at(s.len().saturating_sub(1));
+    let amount: i64 = amount
+        .parse()
+        .map_err(|_| format!("'{}' is not a span like 3d, 12h or 2w", s))?;
+
+    match unit {
+        "m" => Ok(Duration::minutes(amount)),
+        "h" => Ok(Duration::hours(amount)),
+        "d" => Ok(Duration::days(amount)),
+        "w" => Ok(Duration::weeks(amount)),
+        _ => Err(format!("Unknown unit in '{}' (use m, h, d or w)", s)),
+    }
+}
+
+/// Parses an effort like `2h`, `90m` or `1d` for `--estimate`, in minutes.
+pub fn parse_estimate(s: &str) -> Result<i64, String> {
+    let minutes = parse_span(s)?.num_minutes();
+    if minutes <= 0 {
+        return Err(format!("An estimate must be at least a minute, not '{}'", s));
+    }
+    Ok(minutes)
+}
+
+/// `2h 30m`, `45m` or `3h`: an effort in minutes, as hours and minutes.
+pub fn format_effort(minutes: i64) -> String {
+    match (minutes / 60, minutes % 60) {
+        (0, minutes) => format!("{}m", minutes),
+        (hours, 0) => format!("{}h", hours),
+        (hours, minutes) => format!("{}h {}m", hours, minutes),
+    }
+}
+
+/// Total estimated minutes of the open todos among `todos`.
+pub fn open_estimate<'a>(todos: impl IntoIterator<Item = &'a Todo>) -> i64 {
+    todos
+        .into_iter()
+        .filter(|todo| !todo.completed)
+        .filter_map(|todo| todo.estimate_minutes)
+        .sum()
+}
+
+/// Client-side due-date filters for `list`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DueFilter {
+    pub overdue: bool,
+    pub within: Option<Duration>,
+}
+
+impl DueFilter {
+    /// With both filters set, a todo matching either is kept.
+    pub fn matches(&self, todo: &Todo, now: DateTime<Utc>) -> bool {
+        if !self.overdue && self.within.is_none() {
+            return true;
+        }
+        let overdue = self.overdue && todo.is_overdue(now);
+        let due_soon = self.within.is_some_and(|within| {
+            !todo.completed && todo.due().is_some_and(|due| due >= now && due <= now + within)
+        });
+        overdue || due_soon
+    }
+}
+
+/// An agenda from `date` through `until` (the same day unless it covers a
+/// week): what is due in that span plus open items carried over from
+/// earlier days, each sorted by due time.
+#[derive(Debug, Serialize)]
+pub struct Agenda {
+    pub date: NaiveDate,
+    pub until: NaiveDate,
+    pub overdue: Vec<Todo>,
+    pub due: Vec<Todo>,
+    /// Estimated minutes of the open todos in it
+    pub estimated_minutes: i64,
+}
+
+impl Agenda {
+    pub fn for_day(todos: Vec<Todo>, date: NaiveDate) -> Self {
+        Self::for_span(todos, date, date)
+    }
+
+    /// The week containing `date`, starting on `first_weekday`.
+    pub fn for_week(todos: Vec<Todo>, date: NaiveDate, first_weekday: Weekday) -> Self {
+        let start = date.week(first_weekday).first_day();
+        Self::for_span(todos, start, start + Duration::days(6))
+    }
+
+    fn for_span(todos: Vec<Todo>, date: NaiveDate, until: NaiveDate) -> Self {
+        let start = start_of(date);
+        let end = start_of(until + Duration::days(1));
+
+        let (mut overdue, mut due) = (Vec::new(), Vec::new());
+        for todo in todos {
+            match todo.due() {
+                Some(at) if at < start && !todo.completed => overdue.push(todo),
+                Some(at) if at >= start && at < end => due.push(todo),
+                _ => {}
+            }
+        }
+        overdue.sort_by_key(Todo::due);
+        due.sort_by_key(Todo::due);
+        let estimated_minutes = open_estimate(overdue.iter().chain(&due));
+
+        Self {
+            date,
+            until,
+            overdue,
+            due,
+            estimated_minutes,
+        }
+    }
+}
+
+/// Local midnight at the start of `date`, in UTC.
+pub fn start_of(date: NaiveDate) -> DateTime<Utc> {
+    let midnight = date.and_time(chrono::NaiveTime::MIN);
+    midnight
+        .and_local_timezone(Local)
+        .earliest()
+        .map(|at| at.with_timezone(&Utc))
+        // Midnight skipped by a DST change; UTC midnight is close enough
+        .unwrap_or_else(|| midnight.and_utc())
+}
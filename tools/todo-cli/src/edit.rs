@@ -0,0 +1,172 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+use futures::{StreamExt, stream};
+use serde::{Deserialize, Serialize};
+
+use crate::api::{RequestOptions, Todo, TodoApi};
+use crate::error;
+
+/// Updates sent at once when applying an edited document.
+const CONCURRENCY: usize = 4;
+
+const HEADER: &str = "\
+# Edit titles or completion state, then save and quit to apply.
+# Rows that are removed or left unchanged are not touched; IDs must not change.
+";
+
+/// The editable part of a todo, as it appears in the document.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Row {
+    pub id: i64,
+    pub title: String,
+    pub completed: bool,
+}
+
+impl From<&Todo> for Row {
+    fn from(todo: &Todo) -> Self {
+        Self {
+            id: todo.id,
+            title: todo.title.clone(),
+            completed: todo.completed,
+        }
+    }
+}
+
+/// Fields of one row that differ from the server's copy.
+#[derive(Debug)]
+pub struct Change {
+    pub id: i64,
+    pub title: Option<String>,
+    pub completed: Option<bool>,
+}
+
+/// Parses `--filter key=value` pairs into the list's completion filter.
+pub fn parse_filters(filters: &[String]) -> Result<Option<bool>> {
+    let mut completed = None;
+    for filter in filters {
+        let (key, value) = filter
+            .split_once('=')
+            .with_context(|| format!("Invalid filter '{}', expected key=value", filter))?;
+        match key {
+            "completed" => {
+                completed = Some(value.parse().with_context(|| {
+                    format!("Invalid value for completed: '{}'", value)
+                })?);
+            }
+            _ => bail!("Unknown filter '{}' (supported: completed)", key),
+        }
+    }
+    Ok(completed)
+}
+
+/// The todos `--filter` pairs let through.
+pub async fn matching<A: TodoApi>(
+    client: &A,
+    filters: &[String],
+    opts: &RequestOptions,
+) -> Result<Vec<Todo>> {
+    let completed = parse_filters(filters)?;
+    let mut todos = client.list_todos(completed, opts).await?;
+    // The server may ignore `completed`, so filter here too
+    todos.retain(|todo| completed.is_none_or(|c| todo.completed == c));
+    Ok(todos)
+}
+
+pub fn to_document(todos: &[Todo]) -> Result<String> {
+    let rows: Vec<Row> = todos.iter().map(Row::from).collect();
+    Ok(format!("{}{}", HEADER, serde_yaml::to_string(&rows)?))
+}
+
+/// Opens `todos` in the user's editor and returns the rows they changed.
+pub async fn edit_in_editor(todos: &[Todo]) -> Result<Vec<Change>> {
+    let path = std::env::temp_dir().join(format!("todo-edit-{}.yaml", std::process::id()));
+    fs::write(&path, to_document(todos)?).context("Failed to write edit buffer")?;
+
+    run_editor(&path).await?;
+
+    let edited = fs::read_to_string(&path).context("Failed to read edit buffer")?;
+    let rows: Vec<Row> = serde_yaml::from_str(&edited)
+        .with_context(|| format!("Invalid document; your edits are kept in {}", path.display()))?;
+    let changes = diff(todos, &rows)
+        .with_context(|| format!("Your edits are kept in {}", path.display()))?;
+
+    let _ = fs::remove_file(&path);
+    Ok(changes)
+}
+
+/// Compares edited rows against the fetched todos.
+fn diff(todos: &[Todo], rows: &[Row]) -> Result<Vec<Change>> {
+    let originals: HashMap<i64, &Todo> = todos.iter().map(|todo| (todo.id, todo)).collect();
+
+    let mut changes = Vec::new();
+    for row in rows {
+        let Some(original) = originals.get(&row.id) else {
+            bail!("Todo #{} was not part of the edited set", row.id);
+        };
+        let change = Change {
+            id: row.id,
+            title: Some(row.title.clone()).filter(|title| *title != original.title),
+            completed: Some(row.completed).filter(|done| *done != original.completed),
+        };
+        if change.title.is_some() || change.completed.is_some() {
+            changes.push(change);
+        }
+    }
+    Ok(changes)
+}
+
+/// Runs `$VISUAL` or `$EDITOR` (falling back to `vi`) on `path`. The
+/// variable may carry arguments, e.g. `code --wait`.
+async fn run_editor(path: &Path) -> Result<()> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().context("$EDITOR is empty")?;
+
+    let status = tokio::process::Command::new(program)
+        .args(parts)
+        .arg(path)
+        .status()
+        .await
+        .with_context(|| format!("Failed to start editor '{}'", editor))?;
+    if !status.success() {
+        bail!("Editor exited with {}; nothing was changed", status);
+    }
+    Ok(())
+}
+
+/// Sends `changes` a few at a time, returning each outcome in input order.
+pub async fn apply<A: TodoApi>(
+    client: &A,
+    changes: &[Change],
+    opts: &RequestOptions,
+) -> Vec<error::Result<Todo>> {
+    stream::iter(changes)
+        .map(|change| {
+            client.update_todo(change.id, change.title.as_deref(), change.completed, opts)
+        })
+        .buffered(CONCURRENCY)
+        .collect()
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fake::FakeApi;
+
+    #[tokio::test]
+    async fn the_completed_filter_applies_when_the_server_ignores_it() {
+        let client = FakeApi::with_todos(&["Open", "Done"]);
+        client.edit(2, |todo| todo.completed = true);
+
+        let filters = vec!["completed=false".to_string()];
+        let todos = matching(&client, &filters, &RequestOptions::new()).await.unwrap();
+        let ids: Vec<_> = todos.iter().map(|todo| todo.id).collect();
+        assert_eq!(ids, [1]);
+    }
+}
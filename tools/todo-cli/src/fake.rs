@@ -0,0 +1,323 @@
+//! An in-memory [`TodoApi`] for unit tests. Todos live in a map; calls
+//! beyond todos fail as unsupported.
+
+use std::cell::{Cell, RefCell};
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
+use chrono::{DateTime, Utc};
+use reqwest::{Method, StatusCode};
+use tempfile::TempDir;
+
+use crate::api::{
+    AuthResponse, Invitation, Link, Member, Page, Quota, RequestOptions, Role, Todo, TodoApi,
+    TodoStatus, User, Workspace,
+};
+use crate::error::{ApiError, ClientError, Result};
+use crate::offline::OfflineRequest;
+use crate::validation::Limits;
+
+/// Points the config and state directories at one temporary directory for
+/// the whole test run, so tests never touch the real ones.
+pub fn isolate() {
+    static HOME: OnceLock<TempDir> = OnceLock::new();
+    HOME.get_or_init(|| {
+        let home = TempDir::new().expect("temporary home");
+        // SAFETY: only tests that call this first read these variables,
+        // and the lock lets one of them set them
+        unsafe {
+            std::env::set_var("HOME", home.path());
+            std::env::set_var("XDG_CONFIG_HOME", home.path().join("config"));
+            std::env::set_var("XDG_STATE_HOME", home.path().join("state"));
+            std::env::set_var("XDG_DATA_HOME", home.path().join("data"));
+        }
+        home
+    });
+}
+
+pub struct FakeApi {
+    todos: RefCell<BTreeMap<i64, Todo>>,
+    next_id: Cell<i64>,
+    /// Fails every call as if the server were out of reach
+    pub unreachable: Cell<bool>,
+}
+
+impl FakeApi {
+    /// A server holding one open todo per title, numbered from 1.
+    pub fn with_todos(titles: &[&str]) -> Self {
+        let api = Self {
+            todos: RefCell::default(),
+            next_id: Cell::new(1),
+            unreachable: Cell::new(false),
+        };
+        for title in titles {
+            api.insert(title);
+        }
+        api
+    }
+
+    pub fn todo(&self, id: i64) -> Option<Todo> {
+        self.todos.borrow().get(&id).cloned()
+    }
+
+    pub fn todos(&self) -> Vec<Todo> {
+        self.todos.borrow().values().cloned().collect()
+    }
+
+    /// Changes a todo behind the client's back, as another client would.
+    pub fn edit(&self, id: i64, change: impl FnOnce(&mut Todo)) {
+        let mut todos = self.todos.borrow_mut();
+        let todo = todos.get_mut(&id).expect("todo exists");
+        change(todo);
+        todo.updated_at = now();
+    }
+
+    /// Deletes a todo behind the client's back.
+    pub fn remove(&self, id: i64) {
+        self.todos.borrow_mut().remove(&id);
+    }
+
+    fn insert(&self, title: &str) -> Todo {
+        let id = self.next_id.replace(self.next_id.get() + 1);
+        let todo = Todo {
+            id,
+            user_id: 1,
+            title: title.to_string(),
+            completed: false,
+            created_at: now(),
+            updated_at: now(),
+            due_at: None,
+            tags: Vec::new(),
+            status: None,
+            blocked_reason: None,
+            depends_on: Vec::new(),
+            estimate_minutes: None,
+            links: Vec::new(),
+        };
+        self.todos.borrow_mut().insert(id, todo.clone());
+        todo
+    }
+
+    fn reach(&self, method: Method, path: &str) -> Result<()> {
+        if !self.unreachable.get() {
+            return Ok(());
+        }
+        let refused = OfflineRequest {
+            method,
+            path: path.to_string(),
+        };
+        Err(ApiError::Network(reqwest_middleware::Error::Middleware(refused.into())))
+    }
+
+    fn change(&self, id: i64, change: impl FnOnce(&mut Todo)) -> Result<Todo> {
+        self.reach(Method::PUT, &format!("/api/v1/todos/{}", id))?;
+        let mut todos = self.todos.borrow_mut();
+        let todo = todos.get_mut(&id).ok_or_else(|| not_found(id))?;
+        change(todo);
+        todo.updated_at = now();
+        Ok(todo.clone())
+    }
+}
+
+fn now() -> String {
+    Utc::now().to_rfc3339()
+}
+
+fn not_found(id: i64) -> ApiError {
+    ApiError::NotFound {
+        id: Some(id),
+        error: ClientError {
+            status: StatusCode::NOT_FOUND,
+            code: None,
+            message: "todo not found".to_string(),
+            fields: Vec::new(),
+            request_id: None,
+        },
+    }
+}
+
+impl TodoApi for FakeApi {
+    fn base_url(&self) -> &str {
+        "http://fake.test"
+    }
+
+    fn workspace(&self) -> Option<i64> {
+        None
+    }
+
+    async fn login(&self, _: &str, _: &str, _: &RequestOptions) -> Result<AuthResponse> {
+        Err(ApiError::Unsupported("accounts"))
+    }
+
+    async fn register(&self, _: &str, _: &str, _: &RequestOptions) -> Result<AuthResponse> {
+        Err(ApiError::Unsupported("accounts"))
+    }
+
+    /// Every todo, whatever `completed` asks for: the real server doesn't
+    /// filter either, so callers have to.
+    async fn list_todos_page(
+        &self,
+        _: Option<bool>,
+        _: Option<&str>,
+        _: &RequestOptions,
+    ) -> Result<Page<Todo>> {
+        self.reach(Method::GET, "/api/v1/todos")?;
+        let todos = self.todos();
+        Ok(Page {
+            total: todos.len() as i64,
+            items: todos,
+            next_cursor: None,
+        })
+    }
+
+    async fn get_todo(&self, id: i64, _: &RequestOptions) -> Result<Todo> {
+        self.reach(Method::GET, &format!("/api/v1/todos/{}", id))?;
+        self.todo(id).ok_or_else(|| not_found(id))
+    }
+
+    async fn create_todo(&self, title: &str, _: &RequestOptions) -> Result<Todo> {
+        self.reach(Method::POST, "/api/v1/todos")?;
+        Ok(self.insert(title))
+    }
+
+    async fn update_todo(
+        &self,
+        id: i64,
+        title: Option<&str>,
+        completed: Option<bool>,
+        _: &RequestOptions,
+    ) -> Result<Todo> {
+        self.change(id, |todo| {
+            if let Some(title) = title {
+                todo.title = title.to_string();
+            }
+            if let Some(completed) = completed {
+                todo.completed = completed;
+            }
+        })
+    }
+
+    async fn set_due(&self, id: i64, due: DateTime<Utc>, _: &RequestOptions) -> Result<Todo> {
+        self.change(id, |todo| todo.due_at = Some(due.to_rfc3339()))
+    }
+
+    async fn set_tags(&self, id: i64, tags: &[String], _: &RequestOptions) -> Result<Todo> {
+        self.change(id, |todo| todo.tags = tags.to_vec())
+    }
+
+    async fn set_estimate(&self, id: i64, minutes: i64, _: &RequestOptions) -> Result<Todo> {
+        self.change(id, |todo| todo.estimate_minutes = Some(minutes))
+    }
+
+    async fn set_dependencies(
+        &self,
+        id: i64,
+        depends_on: &[i64],
+        _: &RequestOptions,
+    ) -> Result<Todo> {
+        self.change(id, |todo| todo.depends_on = depends_on.to_vec())
+    }
+
+    async fn set_links(&self, id: i64, links: &[Link], _: &RequestOptions) -> Result<Todo> {
+        self.change(id, |todo| todo.links = links.to_vec())
+    }
+
+    async fn set_status(
+        &self,
+        id: i64,
+        status: TodoStatus,
+        reason: Option<&str>,
+        _: &RequestOptions,
+    ) -> Result<Todo> {
+        self.change(id, |todo| {
+            todo.completed = status == TodoStatus::Done;
+            todo.status = Some(status);
+            todo.blocked_reason = reason.map(str::to_string);
+        })
+    }
+
+    async fn delete_todo(&self, id: i64, _: &RequestOptions) -> Result<()> {
+        self.reach(Method::DELETE, &format!("/api/v1/todos/{}", id))?;
+        self.todos.borrow_mut().remove(&id).ok_or_else(|| not_found(id))?;
+        Ok(())
+    }
+
+    async fn limits(&self, _: &RequestOptions) -> Result<Limits> {
+        self.reach(Method::GET, "/config")?;
+        Ok(Limits::default())
+    }
+
+    async fn quota(&self, _: &RequestOptions) -> Result<Quota> {
+        Err(ApiError::Unsupported("quotas"))
+    }
+
+    async fn delete_account(&self, _: &RequestOptions) -> Result<()> {
+        Err(ApiError::Unsupported("accounts"))
+    }
+
+    async fn profile(&self, _: &RequestOptions) -> Result<User> {
+        Err(ApiError::Unsupported("accounts"))
+    }
+
+    async fn set_avatar(&self, _: Vec<u8>, _: &RequestOptions) -> Result<()> {
+        Err(ApiError::Unsupported("avatars"))
+    }
+
+    async fn remove_avatar(&self, _: &RequestOptions) -> Result<()> {
+        Err(ApiError::Unsupported("avatars"))
+    }
+
+    async fn update_profile(
+        &self,
+        _: Option<&str>,
+        _: Option<&str>,
+        _: &RequestOptions,
+    ) -> Result<User> {
+        Err(ApiError::Unsupported("accounts"))
+    }
+
+    async fn list_workspaces(&self, _: &RequestOptions) -> Result<Vec<Workspace>> {
+        Err(ApiError::Unsupported("workspaces"))
+    }
+
+    async fn create_workspace(&self, _: &str, _: &RequestOptions) -> Result<Workspace> {
+        Err(ApiError::Unsupported("workspaces"))
+    }
+
+    async fn workspace_members(&self, _: i64, _: &RequestOptions) -> Result<Vec<Member>> {
+        Err(ApiError::Unsupported("workspaces"))
+    }
+
+    async fn invite_member(
+        &self,
+        _: i64,
+        _: &str,
+        _: Role,
+        _: &RequestOptions,
+    ) -> Result<Invitation> {
+        Err(ApiError::Unsupported("workspaces"))
+    }
+
+    async fn set_member_role(
+        &self,
+        _: i64,
+        _: i64,
+        _: Role,
+        _: &RequestOptions,
+    ) -> Result<Member> {
+        Err(ApiError::Unsupported("workspaces"))
+    }
+
+    async fn list_invitations(&self, _: &RequestOptions) -> Result<Vec<Invitation>> {
+        Err(ApiError::Unsupported("workspaces"))
+    }
+
+    async fn respond_to_invitation(
+        &self,
+        _: i64,
+        _: bool,
+        _: &RequestOptions,
+    ) -> Result<Invitation> {
+        Err(ApiError::Unsupported("workspaces"))
+    }
+}
@@ -0,0 +1,187 @@
+//! Saved filters: `list` flags kept under a name in the `[filters]` config
+//! section, run with `todo list @name`. One can be the default list view.
+
+use anyhow::{Result, anyhow, bail};
+use chrono::{DateTime, Utc};
+use clap::{Args, Parser};
+use serde_json::json;
+
+use crate::api::{RequestOptions, Todo, TodoApi, TodoStatus};
+use crate::config::Config;
+use crate::style::Colorize;
+use crate::{due, output, places};
+
+/// The `list` flags that narrow which todos are shown, and that a saved
+/// filter may contain.
+#[derive(Args, Debug, Default, Clone)]
+pub struct ListFilter {
+    /// Filter by completion status
+    #[arg(short, long)]
+    pub completed: Option<bool>,
+    /// Only show open todos past their due date
+    #[arg(long)]
+    pub overdue: bool,
+    /// Only show open todos due within a span, e.g. 3d, 12h, 2w
+    #[arg(long, value_parser = due::parse_span)]
+    pub due_within: Option<chrono::Duration>,
+    /// Only show todos with this status: todo, in-progress, blocked or done
+    #[arg(long)]
+    pub status: Option<TodoStatus>,
+    /// Only show todos tagged with this place, e.g. @home
+    #[arg(long, value_name = "PLACE", value_parser = places::parse_name)]
+    pub at: Option<String>,
+}
+
+impl ListFilter {
+    pub fn is_empty(&self) -> bool {
+        self.completed.is_none()
+            && !self.overdue
+            && self.due_within.is_none()
+            && self.status.is_none()
+            && self.at.is_none()
+    }
+
+    /// Adds `saved` under these flags; flags given here win.
+    pub fn or(self, saved: ListFilter) -> ListFilter {
+        ListFilter {
+            completed: self.completed.or(saved.completed),
+            overdue: self.overdue || saved.overdue,
+            due_within: self.due_within.or(saved.due_within),
+            status: self.status.or(saved.status),
+            at: self.at.or(saved.at),
+        }
+    }
+
+    /// Drops todos the flags exclude. `completed` also goes to the server,
+    /// which may ignore it; the API has no query for the others.
+    pub fn retain(&self, todos: &mut Vec<Todo>, now: DateTime<Utc>) {
+        let due = due::DueFilter {
+            overdue: self.overdue,
+            within: self.due_within,
+        };
+        todos.retain(|todo| {
+            due.matches(todo, now)
+                && self.completed.is_none_or(|completed| todo.completed == completed)
+                && self.status.is_none_or(|status| todo.status() == status)
+                && self.at.as_deref().is_none_or(|place| places::is_at(todo, place))
+        });
+    }
+}
+
+#[derive(Parser)]
+#[command(no_binary_name = true, disable_help_flag = true)]
+struct Saved {
+    #[command(flatten)]
+    filter: ListFilter,
+}
+
+/// Parses saved flags, e.g. `--overdue --completed false`.
+fn parse(args: &str) -> Result<ListFilter> {
+    Saved::try_parse_from(args.split_whitespace())
+        .map(|saved| saved.filter)
+        .map_err(|err| {
+            let message = err.to_string();
+            let first = message.lines().next().unwrap_or_default();
+            anyhow!("{}", first.trim_start_matches("error: "))
+        })
+}
+
+/// The `@name` argument of `list`.
+pub fn parse_name(s: &str) -> Result<String, String> {
+    s.strip_prefix('@')
+        .map(str::to_string)
+        .ok_or_else(|| format!("saved filters are written @name, e.g. @{}", s))
+}
+
+/// Looks up and parses the filter saved as `name`.
+pub fn resolve(config: &Config, name: &str) -> Result<ListFilter> {
+    let Some(args) = config.filters.get(name) else {
+        bail!("No saved filter @{}; see `todo filter list`", name);
+    };
+    parse(args).map_err(|err| anyhow!("Saved filter @{} is invalid: {}", name, err))
+}
+
+/// The todos the filter saved as `name` lets through.
+pub async fn matching<A: TodoApi>(client: &A, config: &Config, name: &str) -> Result<Vec<Todo>> {
+    let filter = resolve(config, name)?;
+    let mut todos = client
+        .list_todos(filter.completed, &RequestOptions::new().retries(2))
+        .await?;
+    filter.retain(&mut todos, Utc::now());
+    Ok(todos)
+}
+
+pub fn add(name: &str, args: &[String]) -> Result<()> {
+    let name = name.trim_start_matches('@');
+    if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_') {
+        bail!("Filter names may only contain letters, digits, '-' and '_'");
+    }
+    let args = args.join(" ");
+    parse(&args)?;
+
+    let replaced = Config::update(|config| {
+        Ok(config.filters.insert(name.to_string(), args.clone()).is_some())
+    })?;
+    let verb = if replaced { "updated" } else { "saved" };
+    output::success(&format!("Filter @{} {}; run it with `todo list @{}`.", name, verb, name));
+    output::print_result("filter_saved", &[], json!({ "name": name, "args": args }))
+}
+
+pub fn list(config: &Config) -> Result<()> {
+    if output::json() {
+        let filters = json!({ "filters": config.filters, "default": config.default_filter });
+        return output::print_json("filter_list", &filters);
+    }
+    if config.filters.is_empty() {
+        println!("{}", "No saved filters; add one with `todo filter add`.".dimmed());
+        return Ok(());
+    }
+
+    let width = config.filters.keys().map(|name| name.chars().count()).max().unwrap_or(0);
+    for (name, args) in &config.filters {
+        let default = if config.default_filter.as_deref() == Some(name.as_str()) {
+            "  (default)".dimmed().to_string()
+        } else {
+            String::new()
+        };
+        println!("  @{:<width$}  {}{}", name, args, default);
+    }
+    Ok(())
+}
+
+/// Removes a filter, and stops using it as the default.
+pub fn remove(name: &str) -> Result<()> {
+    let name = name.trim_start_matches('@');
+    Config::update(|config| {
+        if config.filters.remove(name).is_none() {
+            bail!("No saved filter @{}; see `todo filter list`", name);
+        }
+        if config.default_filter.as_deref() == Some(name) {
+            config.default_filter = None;
+        }
+        Ok(())
+    })?;
+    output::success(&format!("Filter @{} removed.", name));
+    output::print_result("filter_removed", &[], json!({ "name": name }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fake::FakeApi;
+
+    #[tokio::test]
+    async fn completed_applies_when_the_server_ignores_it() {
+        let client = FakeApi::with_todos(&["Open", "Done"]);
+        client.edit(2, |todo| todo.completed = true);
+        let filter = ListFilter {
+            completed: Some(true),
+            ..Default::default()
+        };
+
+        let mut todos = client.list_todos(filter.completed, &RequestOptions::new()).await.unwrap();
+        filter.retain(&mut todos, Utc::now());
+        let ids: Vec<_> = todos.iter().map(|todo| todo.id).collect();
+        assert_eq!(ids, [2]);
+    }
+}
@@ -0,0 +1,140 @@
+//! `todo focus`: a pomodoro countdown on one todo. Each interval goes to
+//! the local time log, the terminal is notified when time is up, and a key
+//! then marks the todo done or starts another round.
+
+use std::io::{self, IsTerminal, Write};
+use std::time::Duration as StdDuration;
+
+use anyhow::{Result, bail};
+use chrono::{DateTime, Duration, Utc};
+use indicatif::{ProgressBar, ProgressStyle};
+
+use crate::api::{RequestOptions, TodoApi};
+use crate::history::{self, Action};
+use crate::style::Colorize;
+use crate::timelog::{self, Interval};
+use crate::{depends, due, output, prompt};
+
+const KEYS: &str = "[d]one  [c]ontinue  [q]uit";
+
+pub async fn run<A: TodoApi>(client: &A, id: i64, length: Duration, format: &str) -> Result<()> {
+    if !prompt::interactive() {
+        return Err(prompt::required("`todo focus` counts down in a terminal"));
+    }
+
+    let todo = client.get_todo(id, &RequestOptions::new().retries(2)).await?;
+    if todo.completed {
+        bail!("#{} is already done", id);
+    }
+    history::record_todo(&todo, Action::Viewed);
+
+    let mut tracked = timelog::tracked_minutes(&timelog::load()?, id);
+    output::say!("{} #{} {}", "Focusing on".bold(), todo.id, todo.title);
+    match todo.estimate_minutes {
+        Some(estimate) => output::say!(
+            "{}",
+            format!(
+                "{} tracked of {} estimated",
+                due::format_effort(tracked),
+                due::format_effort(estimate)
+            )
+            .dimmed()
+        ),
+        None if tracked > 0 => {
+            output::say!("{}", format!("{} tracked", due::format_effort(tracked)).dimmed())
+        }
+        None => {}
+    }
+
+    loop {
+        let interval = countdown(id, length).await;
+        tracked += interval.minutes();
+        notify(&format!("Time's up on #{} {}", todo.id, todo.title));
+        output::say!(
+            "Logged {} ({} in total)",
+            due::format_effort(interval.minutes()),
+            due::format_effort(tracked)
+        );
+
+        loop {
+            match prompt::key(KEYS)? {
+                'd' => {
+                    depends::check_done(client, id, &RequestOptions::default()).await?;
+                    let todo = client
+                        .update_todo(id, None, Some(true), &RequestOptions::default())
+                        .await?;
+                    history::record_todo(&todo, Action::Completed);
+                    output::print_changed("completed", &todo, format)?;
+                    output::success("Todo marked as completed!");
+                    return Ok(());
+                }
+                'c' => break,
+                'q' => return Ok(()),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Logs the interval when dropped, so one cut short by Ctrl+C (which drops
+/// the running command) still counts.
+struct Tracking {
+    todo_id: i64,
+    started_at: DateTime<Utc>,
+}
+
+impl Tracking {
+    fn interval(&self) -> Interval {
+        Interval {
+            todo_id: self.todo_id,
+            started_at: self.started_at,
+            ended_at: Utc::now(),
+        }
+    }
+}
+
+impl Drop for Tracking {
+    fn drop(&mut self) {
+        if let Err(err) = timelog::record(&self.interval()) {
+            tracing::warn!("Failed to log time: {:#}", err);
+        }
+    }
+}
+
+/// Counts `length` down on a progress bar and returns the logged interval.
+async fn countdown(id: i64, length: Duration) -> Interval {
+    let tracking = Tracking {
+        todo_id: id,
+        started_at: Utc::now(),
+    };
+    let seconds = length.num_seconds().max(1) as u64;
+    // Screen readers get the time left without the bar
+    let template = if output::accessible() { "{msg}" } else { "{bar:30} {msg}" };
+    let progress = ProgressBar::new(seconds)
+        .with_style(ProgressStyle::with_template(template).expect("progress template is valid"));
+
+    let mut ticks = tokio::time::interval(StdDuration::from_secs(1));
+    for elapsed in 0..=seconds {
+        ticks.tick().await;
+        progress.set_position(elapsed);
+        progress.set_message(format!("{} left", clock(seconds - elapsed)));
+    }
+    progress.finish_and_clear();
+    tracking.interval()
+}
+
+/// `24:59`: minutes and seconds.
+fn clock(seconds: u64) -> String {
+    format!("{:02}:{:02}", seconds / 60, seconds % 60)
+}
+
+/// Says `message`, ringing the bell and raising a desktop notification on
+/// terminals that support OSC 9 (iTerm2, Windows Terminal, kitty).
+fn notify(message: &str) {
+    let mut stdout = io::stdout();
+    if stdout.is_terminal() {
+        let _ = write!(stdout, "\x1b]9;{}\x07\x07", message);
+        let _ = stdout.flush();
+    }
+    output::say!("{}", output::with_icon("⏰", message).bold());
+}
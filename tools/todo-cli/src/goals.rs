@@ -0,0 +1,195 @@
+//! Weekly goals: `goal_per_week` is how many todos to complete each week,
+//! from the locale's first day of the week. `todo goals` and `todo stats`
+//! show progress, and `todo goals prompt` prints a short segment for a
+//! shell prompt. The API has no place for goals, so each week's goal and
+//! count are kept in the state directory, where they outlive the todos.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use chrono::{Days, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::api::{RequestOptions, Todo, TodoApi};
+use crate::config::Config;
+use crate::style::Colorize;
+use crate::{config, locale, lock, output, search, stats};
+
+/// Completions in one week against that week's goal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Week {
+    pub goal: usize,
+    pub done: usize,
+}
+
+impl Week {
+    pub fn is_met(&self) -> bool {
+        self.done >= self.goal
+    }
+
+    /// `7/15`, green once the goal is met.
+    fn styled(&self) -> String {
+        let text = format!(
+            "{}/{}",
+            locale::integer(self.done),
+            locale::integer(self.goal)
+        );
+        if self.is_met() {
+            text.as_str().green().to_string()
+        } else {
+            text
+        }
+    }
+}
+
+/// The first day of the week containing `date`.
+pub fn week_of(date: NaiveDate) -> NaiveDate {
+    date.week(locale::first_weekday()).first_day()
+}
+
+/// This week's progress from `todos`, which should include completed ones.
+/// `None` without a configured goal.
+pub fn this_week(todos: &[Todo], config: &Config) -> Option<(NaiveDate, Week)> {
+    let goal = config.goal_per_week?;
+    let start = week_of(Local::now().date_naive());
+    let end = start + Days::new(7);
+    let done = todos
+        .iter()
+        .filter(|todo| todo.completed)
+        .filter_map(stats::completed_on)
+        .filter(|day| (start..end).contains(day))
+        .count();
+    Some((start, Week { goal, done }))
+}
+
+pub fn set(per_week: usize) -> Result<()> {
+    Config::update(|config| {
+        config.goal_per_week = Some(per_week);
+        Ok(())
+    })?;
+    output::success(&format!("Goal set: {} completed todos a week.", per_week));
+    output::print_result("goal_set", &[], json!({ "per_week": per_week }))
+}
+
+/// Drops the goal; past weeks stay in the history.
+pub fn clear() -> Result<()> {
+    Config::update(|config| {
+        config.goal_per_week = None;
+        Ok(())
+    })?;
+    output::success("Weekly goal cleared.");
+    output::print_result("goal_cleared", &[], json!({}))
+}
+
+/// This week's progress and the `weeks` before it.
+pub async fn show<A: TodoApi>(client: &A, config: &Config, weeks: usize) -> Result<()> {
+    let todos = client
+        .list_todos(None, &RequestOptions::new().retries(2))
+        .await?;
+    let current = this_week(&todos, config);
+    if let Some((start, week)) = current {
+        record(start, week);
+    }
+    let history = load()?;
+    let past: Vec<_> = history
+        .iter()
+        .rev()
+        .filter(|(start, _)| current.is_none_or(|(current, _)| **start < current))
+        .take(weeks)
+        .collect();
+
+    if output::json() {
+        let past: Vec<_> = past
+            .iter()
+            .map(|(start, week)| json!({ "week": start, "goal": week.goal, "done": week.done }))
+            .collect();
+        let this_week = current
+            .map(|(start, week)| json!({ "week": start, "goal": week.goal, "done": week.done }));
+        return output::print_json(
+            "goals",
+            &json!({
+                "per_week": config.goal_per_week,
+                "this_week": this_week,
+                "past_weeks": past,
+            }),
+        );
+    }
+
+    match current {
+        Some((_, week)) => print_progress(&week),
+        None => {
+            let hint = "No weekly goal; set one with `todo goals set --per-week N`.";
+            println!("{}", hint.dimmed());
+        }
+    }
+    if !past.is_empty() {
+        println!();
+        println!("{}", "Past weeks".bold());
+        for (start, week) in past {
+            let mark = if week.is_met() {
+                "✓".green().to_string()
+            } else {
+                " ".to_string()
+            };
+            println!("  {}  {:>9}  {}", locale::date(*start), week.styled(), mark);
+        }
+    }
+    Ok(())
+}
+
+/// `This week: 7/15 done, 8 to go`, for `todo goals` and `todo stats`.
+pub fn print_progress(week: &Week) {
+    let rest = match week.goal.saturating_sub(week.done) {
+        0 => "goal met".to_string(),
+        left => format!("{} to go", locale::integer(left)),
+    };
+    println!("{} {} done, {}", "This week:".bold(), week.styled(), rest);
+}
+
+/// The prompt segment: `7/15`, counted from the todos this machine has
+/// listed, so it needs no request. `None` without a goal.
+pub fn segment(config: &Config) -> Option<String> {
+    config.goal_per_week?;
+    let todos = search::Index::open()
+        .and_then(|index| index.todos())
+        .unwrap_or_default();
+    let (_, week) = this_week(&todos, config)?;
+    Some(format!("{}/{}", week.done, week.goal))
+}
+
+/// Saves the count for the week starting `start`. The history is a
+/// convenience, so failures are logged, not returned.
+pub fn record(start: NaiveDate, week: Week) {
+    let result = state_path().and_then(|path| {
+        lock::exclusive(&path, || {
+            let mut history = load()?;
+            if history.get(&start) == Some(&week) {
+                return Ok(());
+            }
+            history.insert(start, week);
+            fs::write(&path, serde_json::to_string_pretty(&history)?)
+                .with_context(|| format!("Failed to write {}", path.display()))
+        })
+    });
+    if let Err(err) = result {
+        tracing::warn!("Failed to update goal history: {:#}", err);
+    }
+}
+
+/// Every recorded week by its first day, oldest first.
+fn load() -> Result<BTreeMap<NaiveDate, Week>> {
+    let path = state_path()?;
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+        Err(err) => return Err(err).context("Failed to read goal history"),
+    };
+    serde_json::from_str(&content).context("Failed to parse goal history")
+}
+
+fn state_path() -> Result<PathBuf> {
+    Ok(config::state_dir()?.join("goals.json"))
+}
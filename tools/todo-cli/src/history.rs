@@ -0,0 +1,243 @@
+use std::collections::HashSet;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::api::Todo;
+use crate::locale;
+use crate::lock;
+use crate::output;
+use crate::style::Colorize;
+
+/// Entries kept in the state file; older ones are dropped when it grows past this.
+const MAX_ENTRIES: usize = 1000;
+
+/// One line of the history file.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Entry {
+    pub at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub event: Event,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event {
+    /// A CLI invocation, with secrets redacted
+    Command { args: Vec<String>, success: bool },
+    /// A todo the user acted on
+    Todo {
+        id: i64,
+        /// Unknown for deletes that skipped confirmation
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        title: Option<String>,
+        action: Action,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Created,
+    Viewed,
+    Updated,
+    Completed,
+    Reopened,
+    Deleted,
+}
+
+impl Action {
+    fn label(self) -> &'static str {
+        match self {
+            Action::Created => "created",
+            Action::Viewed => "viewed",
+            Action::Updated => "updated",
+            Action::Completed => "completed",
+            Action::Reopened => "reopened",
+            Action::Deleted => "deleted",
+        }
+    }
+}
+
+/// Records the command line of this invocation. History is best effort, so
+/// failures are logged rather than returned.
+pub fn record_command(args: impl IntoIterator<Item = String>, success: bool) {
+    let args = redact(args);
+    append(Event::Command { args, success });
+}
+
+/// Records that `todo` was created, viewed or changed.
+pub fn record_todo(todo: &Todo, action: Action) {
+    append(Event::Todo {
+        id: todo.id,
+        title: Some(todo.title.clone()),
+        action,
+    });
+}
+
+/// Records a delete; `title` is known only if the todo was fetched first.
+pub fn record_deleted(id: i64, title: Option<String>) {
+    append(Event::Todo {
+        id,
+        title,
+        action: Action::Deleted,
+    });
+}
+
+fn append(event: Event) {
+    let entry = Entry {
+        at: Utc::now(),
+        event,
+    };
+    if let Err(err) = write_entry(&entry) {
+        tracing::warn!("Failed to record history: {:#}", err);
+    }
+}
+
+fn write_entry(entry: &Entry) -> Result<()> {
+    let path = state_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).context("Failed to create state directory")?;
+    }
+
+    // One lock over the append and the trim, so another process can't
+    // append between reading the entries and writing them back
+    lock::exclusive(&path, || {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+        writeln!(file, "{}", serde_json::to_string(entry)?)?;
+        drop(file);
+
+        // Only rewrite the file once it has outgrown the cap
+        let entries = load()?;
+        if entries.len() > MAX_ENTRIES {
+            let keep = &entries[entries.len() - MAX_ENTRIES..];
+            let mut content = String::new();
+            for entry in keep {
+                content.push_str(&serde_json::to_string(entry)?);
+                content.push('\n');
+            }
+            fs::write(&path, content).context("Failed to write history file")?;
+        }
+        Ok(())
+    })
+}
+
+/// Reads every entry, oldest first. Lines that don't parse (e.g. from a
+/// newer CLI) are skipped.
+pub fn load() -> Result<Vec<Entry>> {
+    let path = state_path()?;
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err).context("Failed to read history file"),
+    };
+
+    Ok(content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+pub fn clear() -> Result<()> {
+    let path = state_path()?;
+    match fs::remove_file(&path) {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+            Err(err).context("Failed to clear history file")
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Most recently touched todos, newest first, one entry per ID.
+pub fn recent(entries: &[Entry], limit: usize) -> Vec<&Entry> {
+    let mut seen = HashSet::new();
+    entries
+        .iter()
+        .rev()
+        .filter(|entry| match entry.event {
+            Event::Todo { id, .. } => seen.insert(id),
+            Event::Command { .. } => false,
+        })
+        .take(limit)
+        .collect()
+}
+
+/// The newest `limit` entries of one kind, newest first.
+pub fn latest(entries: &[Entry], commands: bool, limit: usize) -> Vec<&Entry> {
+    entries
+        .iter()
+        .rev()
+        .filter(|entry| matches!(entry.event, Event::Command { .. }) == commands)
+        .take(limit)
+        .collect()
+}
+
+pub fn print(entries: &[&Entry], format: &str) -> Result<()> {
+    if format == "json" {
+        return output::print_json("history", entries);
+    }
+
+    if entries.is_empty() {
+        println!("{}", "No history yet.".dimmed());
+        return Ok(());
+    }
+
+    for entry in entries {
+        let at = locale::datetime(&entry.at.with_timezone(&Local));
+        match &entry.event {
+            Event::Command { args, success } => {
+                let status = match (output::accessible(), *success) {
+                    (true, true) => "OK:".green(),
+                    (true, false) => "FAILED:".red(),
+                    (false, true) => "✓".green(),
+                    (false, false) => "✗".red(),
+                };
+                println!("  {} {}  todo {}", status, at.dimmed(), args.join(" "));
+            }
+            Event::Todo { id, title, action } => {
+                println!(
+                    "  {}  {:<9}  {} {}",
+                    at.dimmed(),
+                    action.label(),
+                    format!("#{}", id).dimmed(),
+                    title.as_deref().unwrap_or_default()
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Replaces password values so they never reach a state file.
+pub fn redact(args: impl IntoIterator<Item = String>) -> Vec<String> {
+    let mut redact_next = false;
+    args.into_iter()
+        .map(|arg| {
+            if std::mem::take(&mut redact_next) {
+                return "***".to_string();
+            }
+            if arg == "-p" || arg == "--password" {
+                redact_next = true;
+                arg
+            } else if arg.starts_with("--password=") {
+                "--password=***".to_string()
+            } else if arg.starts_with("-p") && !arg.starts_with("--") {
+                "-p***".to_string()
+            } else {
+                arg
+            }
+        })
+        .collect()
+}
+
+fn state_path() -> Result<PathBuf> {
+    Ok(crate::config::state_dir()?.join("history.jsonl"))
+}
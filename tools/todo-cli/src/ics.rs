@@ -0,0 +1,102 @@
+//! iCalendar for `todo export --as ics`: one VTODO per todo, with its due
+//! date, status and tags. A todo tagged with a place that has coordinates
+//! gets its LOCATION and GEO, plus the structured location Apple's apps
+//! read as a geofence, so the reminder can fire on arrival.
+
+use chrono::{DateTime, Utc};
+
+use crate::api::{Todo, TodoStatus};
+use crate::config::Config;
+use crate::places;
+
+/// Arrival radius suggested to calendar apps, in metres.
+const GEOFENCE_RADIUS: u32 = 100;
+
+/// Longest content line before folding, in octets (RFC 5545 §3.1).
+const LINE_LIMIT: usize = 75;
+
+/// Renders `todos` as a VCALENDAR stamped with `now`.
+pub fn render(todos: &[Todo], config: &Config, now: DateTime<Utc>) -> String {
+    let mut out = String::new();
+    line(&mut out, "BEGIN:VCALENDAR");
+    line(&mut out, "VERSION:2.0");
+    line(&mut out, "PRODID:-//go-api-starter//todo-cli//EN");
+    for todo in todos {
+        write_todo(&mut out, todo, config, now);
+    }
+    line(&mut out, "END:VCALENDAR");
+    out
+}
+
+fn write_todo(out: &mut String, todo: &Todo, config: &Config, now: DateTime<Utc>) {
+    line(out, "BEGIN:VTODO");
+    line(out, &format!("UID:todo-{}@go-api-starter", todo.id));
+    line(out, &format!("DTSTAMP:{}", timestamp(now)));
+    if let Some(created) = parse(&todo.created_at) {
+        line(out, &format!("CREATED:{}", timestamp(created)));
+    }
+    if let Some(modified) = parse(&todo.updated_at) {
+        line(out, &format!("LAST-MODIFIED:{}", timestamp(modified)));
+    }
+    line(out, &format!("SUMMARY:{}", escape(&todo.title)));
+    let status = match todo.status() {
+        TodoStatus::Done => "COMPLETED",
+        TodoStatus::InProgress => "IN-PROCESS",
+        _ => "NEEDS-ACTION",
+    };
+    line(out, &format!("STATUS:{}", status));
+    if let Some(due) = todo.due() {
+        line(out, &format!("DUE:{}", timestamp(due)));
+    }
+    if !todo.tags.is_empty() {
+        let tags: Vec<_> = todo.tags.iter().map(|tag| escape(tag)).collect();
+        line(out, &format!("CATEGORIES:{}", tags.join(",")));
+    }
+    if let Some((name, geo)) = places::located(todo, config) {
+        line(out, &format!("LOCATION:{}", escape(name)));
+        line(out, &format!("GEO:{};{}", geo.lat, geo.lon));
+        line(
+            out,
+            &format!(
+                "X-APPLE-STRUCTURED-LOCATION;VALUE=URI;X-APPLE-RADIUS={};X-TITLE={}:geo:{},{}",
+                GEOFENCE_RADIUS, name, geo.lat, geo.lon
+            ),
+        );
+    }
+    line(out, "END:VTODO");
+}
+
+fn parse(s: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(s)
+        .ok()
+        .map(|at| at.with_timezone(&Utc))
+}
+
+fn timestamp(at: DateTime<Utc>) -> String {
+    at.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// TEXT values escape backslashes, separators and newlines.
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// Appends a content line, folded so no physical line is longer than
+/// [`LINE_LIMIT`] octets and no character is split.
+fn line(out: &mut String, content: &str) {
+    let mut width = 0;
+    for c in content.chars() {
+        if width + c.len_utf8() > LINE_LIMIT {
+            out.push_str("\r\n ");
+            // The leading space counts towards the folded line
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    out.push_str("\r\n");
+}
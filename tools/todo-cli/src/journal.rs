@@ -0,0 +1,247 @@
+//! Journals for long bulk operations (`import`, `purge`), so one that dies
+//! midway can be picked up with `todo resume <op-id>`. Each journal is a
+//! JSON Lines file in the state directory: a header describing the
+//! operation, then one line per finished step, appended as it happens so
+//! a crash loses at most the step in flight.
+
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Utc};
+use fd_lock::RwLock;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::bulk::ImportFormat;
+use crate::config;
+use crate::locale;
+use crate::output;
+use crate::style::Colorize;
+
+/// What an operation does, with everything needed to run it again.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Operation {
+    /// `import`, with a hash so a resume notices the file changed
+    Import {
+        file: PathBuf,
+        sha256: String,
+        /// Absent from journals written before markdown import
+        #[serde(default)]
+        from: ImportFormat,
+    },
+    /// `purge --completed`, with the todos that were confirmed for deletion
+    Purge { ids: Vec<i64> },
+}
+
+impl Operation {
+    fn prefix(&self) -> &'static str {
+        match self {
+            Operation::Import { .. } => "import",
+            Operation::Purge { .. } => "purge",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Header {
+    id: String,
+    started_at: DateTime<Utc>,
+    operation: Operation,
+}
+
+/// A finished step: `key` names the item, `id` the todo it produced or
+/// removed.
+#[derive(Serialize, Deserialize)]
+struct Step {
+    key: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    id: Option<i64>,
+}
+
+pub struct Journal {
+    id: String,
+    /// Locked per step, in case two `todo resume` runs share the journal
+    file: RwLock<File>,
+    path: PathBuf,
+    done: HashMap<String, Option<i64>>,
+}
+
+impl Journal {
+    /// Opens a new journal for `operation` and says how to resume it.
+    pub fn start(operation: Operation) -> Result<Self> {
+        let mut bytes = [0u8; 4];
+        getrandom::getrandom(&mut bytes).context("Failed to generate an operation ID")?;
+        let id = format!("{}-{}", operation.prefix(), hex::encode(bytes));
+        let path = dir()?.join(format!("{}.jsonl", id));
+        fs::create_dir_all(dir()?).context("Failed to create journal directory")?;
+
+        let header = Header {
+            id: id.clone(),
+            started_at: Utc::now(),
+            operation,
+        };
+        let mut file = File::create(&path)
+            .with_context(|| format!("Failed to create journal {}", path.display()))?;
+        writeln!(file, "{}", serde_json::to_string(&header)?)?;
+
+        eprintln!(
+            "{}",
+            format!("Operation {}; if interrupted, run `todo resume {}`", id, id).dimmed()
+        );
+        Ok(Self {
+            id,
+            file: RwLock::new(file),
+            path,
+            done: HashMap::new(),
+        })
+    }
+
+    /// Reopens the journal of an unfinished operation.
+    pub fn open(id: &str) -> Result<(Self, Operation)> {
+        let valid = !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+        let path = dir()?.join(format!("{}.jsonl", id));
+        if !valid || !path.exists() {
+            bail!("No unfinished operation {}; `todo resume` lists them", id);
+        }
+        let (header, done) = read(&path)?;
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("Failed to open journal {}", path.display()))?;
+        // Start a fresh line after one a crash cut short
+        if !fs::read(&path)?.ends_with(b"\n") {
+            writeln!(file)?;
+        }
+
+        Ok((
+            Self {
+                id: header.id,
+                file: RwLock::new(file),
+                path,
+                done,
+            },
+            header.operation,
+        ))
+    }
+
+    /// Whether `key` finished in an earlier run, and the todo it recorded.
+    pub fn get(&self, key: &str) -> Option<Option<i64>> {
+        self.done.get(key).copied()
+    }
+
+    pub fn is_done(&self, key: &str) -> bool {
+        self.done.contains_key(key)
+    }
+
+    /// Records that `key` finished, before anything else happens.
+    pub fn record(&mut self, key: impl Into<String>, id: Option<i64>) -> Result<()> {
+        let step = Step {
+            key: key.into(),
+            id,
+        };
+        let line = serde_json::to_string(&step)?;
+        self.file
+            .write()
+            .and_then(|mut file| {
+                writeln!(file, "{}", line)?;
+                file.sync_data()
+            })
+            .with_context(|| format!("Failed to write journal {}", self.path.display()))?;
+        self.done.insert(step.key, step.id);
+        Ok(())
+    }
+
+    /// Removes the journal once the operation went through, or points at
+    /// `todo resume` when it didn't.
+    pub fn close(self, finished: bool) -> Result<()> {
+        if finished {
+            return fs::remove_file(&self.path)
+                .with_context(|| format!("Failed to remove journal {}", self.path.display()));
+        }
+        eprintln!(
+            "{}",
+            format!("Retry what's left with `todo resume {}`", self.id).dimmed()
+        );
+        Ok(())
+    }
+}
+
+/// Lists unfinished operations, oldest first.
+pub fn list() -> Result<()> {
+    let dir = dir()?;
+    let mut headers = Vec::new();
+    if dir.exists() {
+        for entry in fs::read_dir(&dir).context("Failed to read journal directory")? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "jsonl") {
+                match read(&path) {
+                    Ok((header, done)) => headers.push((header, done.len())),
+                    Err(err) => {
+                        tracing::warn!(error = %err, path = %path.display(), "Skipping journal")
+                    }
+                }
+            }
+        }
+    }
+    headers.sort_by_key(|(header, _)| header.started_at);
+    if output::json() {
+        let operations: Vec<_> = headers
+            .iter()
+            .map(|(header, steps)| json!({ "journal": header, "steps_done": steps }))
+            .collect();
+        return output::print_json("operation_list", &operations);
+    }
+    if headers.is_empty() {
+        println!("{}", "No unfinished operations.".dimmed());
+        return Ok(());
+    }
+
+    for (header, steps) in headers {
+        let what = match header.operation {
+            Operation::Import { ref file, .. } => format!("import {}", file.display()),
+            Operation::Purge { ref ids } => format!("purge of {} todos", ids.len()),
+        };
+        println!(
+            "  {}  {}  {}",
+            header.id,
+            what,
+            format!(
+                "started {}, {} steps done",
+                locale::datetime(&header.started_at.with_timezone(&chrono::Local)),
+                steps
+            )
+            .dimmed()
+        );
+    }
+    Ok(())
+}
+
+fn dir() -> Result<PathBuf> {
+    Ok(config::state_dir()?.join("operations"))
+}
+
+fn read(path: &Path) -> Result<(Header, HashMap<String, Option<i64>>)> {
+    let file =
+        File::open(path).with_context(|| format!("Failed to read journal {}", path.display()))?;
+    let mut lines = BufReader::new(file).lines();
+    let header: Header = match lines.next() {
+        Some(line) => serde_json::from_str(&line?)
+            .with_context(|| format!("Journal {} is corrupt", path.display()))?,
+        None => bail!("Journal {} is empty", path.display()),
+    };
+
+    let mut done = HashMap::new();
+    for line in lines {
+        let line = line?;
+        // A crash can leave a line half-written; that step reruns
+        let Ok(step) = serde_json::from_str::<Step>(&line) else {
+            continue;
+        };
+        done.insert(step.key, step.id);
+    }
+    Ok((header, done))
+}
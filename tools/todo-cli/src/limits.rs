@@ -0,0 +1,49 @@
+use anyhow::Result;
+use chrono::{Local, Utc};
+
+use crate::api::Quota;
+use crate::output;
+use crate::style::Colorize;
+
+pub fn print(quota: &Quota, format: &str) -> Result<()> {
+    if format == "json" {
+        return output::print_json("quota", quota);
+    }
+
+    let rate = &quota.rate_limit;
+    println!("{}", "Rate limit".bold());
+    if !rate.is_reported() {
+        println!("  {}", "Not reported by this server".dimmed());
+    }
+    match (rate.remaining, rate.limit) {
+        (Some(remaining), Some(limit)) => {
+            let text = format!("{} of {}", remaining, limit);
+            let text = if remaining == 0 { text.as_str().red() } else { text.as_str().green() };
+            println!("  Remaining: {}", text);
+        }
+        (Some(remaining), None) => println!("  Remaining: {}", remaining),
+        (None, Some(limit)) => println!("  Limit: {}", limit),
+        (None, None) => {}
+    }
+    if let Some(reset_at) = rate.reset_at {
+        let seconds = (reset_at - Utc::now()).num_seconds().max(0);
+        println!(
+            "  Resets: in {}s ({})",
+            seconds,
+            reset_at.with_timezone(&Local).format("%H:%M:%S")
+        );
+    }
+
+    let limits = &quota.limits;
+    println!();
+    println!("{}", "Configured limits".bold());
+    if let Some(ref plan) = limits.plan {
+        println!("  Plan: {}", plan);
+    }
+    if let Some(per_minute) = limits.requests_per_minute {
+        println!("  Requests per minute: {}", per_minute);
+    }
+    println!("  Max title length: {}", limits.max_title_length);
+    println!("  Max tag length: {}", limits.max_tag_length);
+    Ok(())
+}
@@ -0,0 +1,159 @@
+//! `todo link`: typed links from a todo to related work, to the todo it
+//! duplicates, or to a page elsewhere. The detail view lists them along
+//! with the todos linking back, and `todo open` follows external ones.
+
+use anyhow::{Result, anyhow, bail};
+
+use crate::aliases::{Aliases, IdArg};
+use crate::api::{Link, RequestOptions, Todo, TodoApi};
+use crate::search;
+
+/// How `todo link --to` relates two todos.
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum Kind {
+    /// Related work, in no particular order
+    RelatesTo,
+    /// The same work; the other todo is the one to keep
+    Duplicates,
+}
+
+/// Reads `--to`: an http(s) URL is an external link, anything else a todo
+/// ID or alias, related unless `kind` says otherwise.
+pub fn parse(to: &str, kind: Option<Kind>, aliases: &Aliases) -> Result<Link> {
+    if to.starts_with("http://") || to.starts_with("https://") {
+        if kind.is_some() {
+            bail!("Links to URLs are external; --kind is for links between todos");
+        }
+        let url = reqwest::Url::parse(to)
+            .map_err(|err| anyhow!("'{}' isn't a valid URL: {}", to, err))?;
+        return Ok(Link::External {
+            url: url.to_string(),
+        });
+    }
+
+    let id: IdArg = to
+        .parse()
+        .map_err(|_| anyhow!("'{}' is neither a todo ID, an alias like a3 nor a URL", to))?;
+    let todo_id = aliases.resolve(&id)?;
+    Ok(match kind.unwrap_or(Kind::RelatesTo) {
+        Kind::RelatesTo => Link::RelatesTo { todo_id },
+        Kind::Duplicates => Link::Duplicates { todo_id },
+    })
+}
+
+/// Adds `link` to `id`. Two todos are linked one way only, so a new kind
+/// replaces the old.
+pub async fn add<A: TodoApi>(client: &A, id: i64, link: Link) -> Result<Todo> {
+    let opts = RequestOptions::new().retries(2);
+    let todo = client.get_todo(id, &opts).await?;
+    if let Some(other) = link.todo_id() {
+        if other == id {
+            bail!("#{} can't link to itself", id);
+        }
+        // A typo'd ID fails here instead of leaving a dangling link
+        client.get_todo(other, &opts).await?;
+    }
+    if todo.links.contains(&link) {
+        bail!("#{} already {}", id, describe(&link));
+    }
+
+    let mut links: Vec<_> = todo
+        .links
+        .into_iter()
+        .filter(|existing| !same_target(existing, &link))
+        .collect();
+    links.push(link);
+    Ok(client.set_links(id, &links, &RequestOptions::default()).await?)
+}
+
+/// Removes the link from `id` to the target of `link`, whatever its kind.
+pub async fn remove<A: TodoApi>(client: &A, id: i64, link: &Link) -> Result<Todo> {
+    let todo = client.get_todo(id, &RequestOptions::new().retries(2)).await?;
+    let links: Vec<_> = todo
+        .links
+        .iter()
+        .filter(|existing| !same_target(existing, link))
+        .cloned()
+        .collect();
+    if links.len() == todo.links.len() {
+        bail!("#{} has no link to {}", id, target(link));
+    }
+    Ok(client.set_links(id, &links, &RequestOptions::default()).await?)
+}
+
+fn same_target(a: &Link, b: &Link) -> bool {
+    match (a, b) {
+        (Link::External { url: a }, Link::External { url: b }) => a == b,
+        _ => a.todo_id().is_some() && a.todo_id() == b.todo_id(),
+    }
+}
+
+fn target(link: &Link) -> String {
+    match link {
+        Link::RelatesTo { todo_id } | Link::Duplicates { todo_id } => format!("#{}", todo_id),
+        Link::External { url } => url.clone(),
+    }
+}
+
+/// `relates to #4`, `duplicates #7` or the URL, for the detail view.
+pub fn describe(link: &Link) -> String {
+    match link {
+        Link::RelatesTo { todo_id } => format!("relates to #{}", todo_id),
+        Link::Duplicates { todo_id } => format!("duplicates #{}", todo_id),
+        Link::External { url } => url.clone(),
+    }
+}
+
+/// `#9 (duplicate)` for each todo linking to `id`. They come from the
+/// local search index, so they're as current as the last listing; an
+/// unreadable index just means none are shown.
+pub fn backlinks(id: i64) -> Vec<String> {
+    let todos = search::Index::open()
+        .and_then(|index| index.todos())
+        .unwrap_or_default();
+    let mut backlinks = Vec::new();
+    for todo in todos {
+        for link in todo.links.iter().filter(|link| link.todo_id() == Some(id)) {
+            let how = match link {
+                Link::Duplicates { .. } => "duplicate",
+                _ => "related",
+            };
+            backlinks.push(format!("#{} ({})", todo.id, how));
+        }
+    }
+    backlinks
+}
+
+/// The external link `todo open` should follow: the `n`th counting from
+/// 1, or the only one. `None` when the todo has none.
+pub fn external(todo: &Todo, n: Option<usize>) -> Result<Option<String>> {
+    let urls: Vec<&str> = todo
+        .links
+        .iter()
+        .filter_map(|link| match link {
+            Link::External { url } => Some(url.as_str()),
+            _ => None,
+        })
+        .collect();
+    match (n, urls.as_slice()) {
+        (Some(n), _) => match n.checked_sub(1).and_then(|i| urls.get(i)) {
+            Some(url) => Ok(Some(url.to_string())),
+            None => bail!("#{} has {} external link(s), not {}", todo.id, urls.len(), n),
+        },
+        (None, []) => Ok(None),
+        (None, [url]) => Ok(Some(url.to_string())),
+        (None, _) => {
+            let choices: Vec<_> = urls
+                .iter()
+                .enumerate()
+                .map(|(i, url)| format!("  {}. {}", i + 1, url))
+                .collect();
+            bail!(
+                "#{} has {} external links; pick one with --link N:\n{}",
+                todo.id,
+                urls.len(),
+                choices.join("\n")
+            )
+        }
+    }
+}
@@ -0,0 +1,164 @@
+//! Locale-aware dates and numbers for human-readable output. The locale is
+//! the `locale` config key, else the system's (`LC_ALL`, `LC_TIME`, `LANG`
+//! or the OS setting); without one, output stays ISO dates and plain
+//! numbers. JSON, CSV and markdown output never go through here.
+
+use std::sync::OnceLock;
+
+use anyhow::{Result, bail};
+use chrono::{DateTime, NaiveDate, TimeZone, Weekday};
+
+static LOCALE: OnceLock<Locale> = OnceLock::new();
+
+/// Regions whose weeks start on Sunday or Saturday; the rest start on Monday.
+const SUNDAY_FIRST: &[&str] = &[
+    "US", "CA", "MX", "BR", "JP", "KR", "TW", "HK", "IL", "IN", "PH", "ZA",
+];
+const SATURDAY_FIRST: &[&str] = &[
+    "AE", "AF", "BH", "DZ", "EG", "IQ", "IR", "JO", "KW", "LY", "OM", "QA", "SA", "SD", "SY",
+];
+
+#[derive(Debug, Clone)]
+pub struct Locale {
+    time: Option<chrono::Locale>,
+    first_weekday: Weekday,
+    thousands: &'static str,
+    decimal: char,
+}
+
+impl Default for Locale {
+    /// ISO dates, weeks starting on Monday and unseparated numbers.
+    fn default() -> Self {
+        Self {
+            time: None,
+            first_weekday: Weekday::Mon,
+            thousands: "",
+            decimal: '.',
+        }
+    }
+}
+
+impl Locale {
+    /// Parses a name like `de_DE`, `en-US` or `fr_FR.UTF-8`; `C` and `POSIX`
+    /// give the default.
+    pub fn parse(name: &str) -> Result<Self> {
+        let name = name.split(['.', '@']).next().unwrap_or_default().replace('-', "_");
+        if name.is_empty() || name == "C" || name == "POSIX" {
+            return Ok(Self::default());
+        }
+        let Ok(time) = chrono::Locale::try_from(name.as_str()) else {
+            bail!("unknown locale '{}'; expected a name like en_US or de_DE", name);
+        };
+
+        let (language, region) = name.split_once('_').unwrap_or((&name, ""));
+        let first_weekday = if SUNDAY_FIRST.contains(&region) {
+            Weekday::Sun
+        } else if SATURDAY_FIRST.contains(&region) {
+            Weekday::Sat
+        } else {
+            Weekday::Mon
+        };
+        let (thousands, decimal) = match (language, region) {
+            ("de", "CH") | ("it", "CH") | ("fr", "CH") => ("'", '.'),
+            ("en" | "ja" | "zh" | "ko" | "he" | "th" | "hi", _) => (",", '.'),
+            ("de" | "nl" | "it" | "es" | "pt" | "da" | "id" | "tr" | "el", _) => (".", ','),
+            ("ro" | "hr" | "sl" | "sr", _) => (".", ','),
+            _ => ("\u{a0}", ','),
+        };
+        Ok(Self {
+            time: Some(time),
+            first_weekday,
+            thousands,
+            decimal,
+        })
+    }
+
+    /// The configured locale, else the system's. An unrecognized system
+    /// locale falls back to the default rather than failing every command.
+    pub fn from_config(configured: Option<&str>) -> Result<Self> {
+        if let Some(name) = configured {
+            return Self::parse(name);
+        }
+        let system = ["LC_ALL", "LC_TIME", "LANG"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|value| !value.is_empty())
+            .or_else(sys_locale::get_locale);
+        Ok(system
+            .and_then(|name| Self::parse(&name).ok())
+            .unwrap_or_default())
+    }
+
+    pub fn install(self) {
+        let _ = LOCALE.set(self);
+    }
+}
+
+fn current() -> &'static Locale {
+    LOCALE.get_or_init(Locale::default)
+}
+
+/// A date such as `2026-10-16`, or `16.10.2026` in `de_DE`.
+pub fn date(date: NaiveDate) -> String {
+    match current().time {
+        Some(locale) => date.format_localized("%x", locale).to_string(),
+        None => date.format("%Y-%m-%d").to_string(),
+    }
+}
+
+/// A date with its weekday, for agenda headings.
+pub fn weekday_date(date: NaiveDate) -> String {
+    match current().time {
+        Some(locale) => date.format_localized("%A %x", locale).to_string(),
+        None => date.format("%A %Y-%m-%d").to_string(),
+    }
+}
+
+/// A date and time to the minute, in the timezone `at` carries.
+pub fn datetime<Tz: TimeZone>(at: &DateTime<Tz>) -> String
+where
+    Tz::Offset: std::fmt::Display,
+{
+    match current().time {
+        Some(locale) => at.format_localized("%x %H:%M", locale).to_string(),
+        None => at.format("%Y-%m-%d %H:%M").to_string(),
+    }
+}
+
+pub fn first_weekday() -> Weekday {
+    current().first_weekday
+}
+
+/// An integer with thousands separators, e.g. `12,345` or `12.345`.
+pub fn integer(n: usize) -> String {
+    group(&n.to_string())
+}
+
+/// `n` rounded to `places` decimals, with the locale's separators.
+pub fn decimal(n: f64, places: usize) -> String {
+    let formatted = format!("{:.*}", places, n);
+    let (sign, digits) = match formatted.strip_prefix('-') {
+        Some(digits) => ("-", digits),
+        None => ("", formatted.as_str()),
+    };
+    let (whole, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+    let mut out = sign.to_string();
+    out.push_str(&group(whole));
+    if !fraction.is_empty() {
+        out.push(current().decimal);
+        out.push_str(fraction);
+    }
+    out
+}
+
+fn group(digits: &str) -> String {
+    let thousands = current().thousands;
+    let mut out = String::with_capacity(digits.len() + digits.len() / 3 * thousands.len());
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push_str(thousands);
+        }
+        out.push(digit);
+    }
+    out
+}
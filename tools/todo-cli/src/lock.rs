@@ -0,0 +1,53 @@
+//! Advisory file locks, so simultaneous `todo` processes (a script in a
+//! loop, several terminals) don't interleave writes to the same file.
+//!
+//! Each file is guarded by a `<name>.lock` file next to it rather than by
+//! the file itself, which may be replaced or removed while locked.
+
+use std::ffi::OsString;
+use std::fs::{self, File, OpenOptions};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use fd_lock::RwLock;
+
+/// Runs `f` while holding the write lock on `path`, waiting for other
+/// processes to release it first.
+pub fn exclusive<T>(path: &Path, f: impl FnOnce() -> Result<T>) -> Result<T> {
+    let mut lock = open(path)?;
+    let _guard = lock
+        .write()
+        .with_context(|| format!("Failed to lock {}", path.display()))?;
+    f()
+}
+
+/// Runs `f` while holding a read lock on `path`, so it never sees a write
+/// in progress.
+pub fn shared<T>(path: &Path, f: impl FnOnce() -> Result<T>) -> Result<T> {
+    let lock = open(path)?;
+    let _guard = lock
+        .read()
+        .with_context(|| format!("Failed to lock {}", path.display()))?;
+    f()
+}
+
+fn open(path: &Path) -> Result<RwLock<File>> {
+    let lock_path = lock_path(path);
+    if let Some(parent) = lock_path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&lock_path)
+        .with_context(|| format!("Failed to open {}", lock_path.display()))?;
+    Ok(RwLock::new(file))
+}
+
+fn lock_path(path: &Path) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(".lock");
+    PathBuf::from(name)
+}
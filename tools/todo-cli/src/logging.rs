@@ -0,0 +1,113 @@
+use std::fs::OpenOptions;
+use std::path::Path;
+use std::sync::Mutex;
+
+use anyhow::{Context, Result};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, fmt};
+
+/// Keeps exporters alive for the duration of a command and flushes any
+/// buffered spans when dropped.
+pub struct LogGuard {
+    #[cfg(feature = "otel")]
+    tracer_provider: Option<opentelemetry_sdk::trace::TracerProvider>,
+}
+
+impl Drop for LogGuard {
+    fn drop(&mut self) {
+        #[cfg(feature = "otel")]
+        if let Some(provider) = self.tracer_provider.take() {
+            let _ = provider.shutdown();
+        }
+    }
+}
+
+/// Installs the global subscriber: human-readable events on stderr, JSON
+/// lines appended to `log_file` when one is configured, and OTLP export when
+/// built with the `otel` feature and an endpoint is available.
+pub fn init(
+    level: &str,
+    log_file: Option<&Path>,
+    otlp_endpoint: Option<&str>,
+) -> Result<LogGuard> {
+    let filter = EnvFilter::try_new(level)
+        .with_context(|| format!("Invalid log level: {}", level))?;
+
+    let stderr = fmt::layer()
+        .with_writer(std::io::stderr)
+        .with_target(false);
+
+    let file = match log_file {
+        Some(path) => {
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .with_context(|| format!("Failed to open log file {}", path.display()))?;
+            Some(fmt::layer().json().with_writer(Mutex::new(file)))
+        }
+        None => None,
+    };
+
+    #[cfg(feature = "otel")]
+    let (otel, guard) = {
+        use opentelemetry::trace::TracerProvider as _;
+
+        let tracer_provider = otel::tracer_provider(otlp_endpoint)?;
+        let layer = tracer_provider.as_ref().map(|provider| {
+            tracing_opentelemetry::layer().with_tracer(provider.tracer("todo-cli"))
+        });
+        (layer, LogGuard { tracer_provider })
+    };
+    #[cfg(not(feature = "otel"))]
+    let (otel, guard) = {
+        let _ = otlp_endpoint;
+        (None::<tracing_subscriber::layer::Identity>, LogGuard {})
+    };
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(stderr)
+        .with(file)
+        .with(otel)
+        .try_init()
+        .context("Failed to initialize logging")?;
+
+    Ok(guard)
+}
+
+#[cfg(feature = "otel")]
+mod otel {
+    use anyhow::{Context, Result};
+    use opentelemetry::KeyValue;
+    use opentelemetry::global;
+    use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+    use opentelemetry_sdk::propagation::TraceContextPropagator;
+    use opentelemetry_sdk::trace::TracerProvider;
+    use opentelemetry_sdk::{Resource, runtime};
+
+    /// Builds an OTLP/gRPC exporter pipeline, or `None` when neither the
+    /// config nor the standard `OTEL_EXPORTER_OTLP_ENDPOINT` names a collector.
+    pub fn tracer_provider(endpoint: Option<&str>) -> Result<Option<TracerProvider>> {
+        if endpoint.is_none() && std::env::var_os("OTEL_EXPORTER_OTLP_ENDPOINT").is_none() {
+            return Ok(None);
+        }
+
+        let mut exporter = SpanExporter::builder().with_tonic();
+        if let Some(endpoint) = endpoint {
+            exporter = exporter.with_endpoint(endpoint);
+        }
+        let exporter = exporter.build().context("Failed to create OTLP exporter")?;
+
+        // The API client injects `traceparent` through the global propagator
+        global::set_text_map_propagator(TraceContextPropagator::new());
+
+        let provider = TracerProvider::builder()
+            .with_batch_exporter(exporter, runtime::Tokio)
+            .with_resource(Resource::new([KeyValue::new("service.name", "todo-cli")]))
+            .build();
+
+        Ok(Some(provider))
+    }
+}
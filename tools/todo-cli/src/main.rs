@@ -1,13 +1,101 @@
-use anyhow::Result;
-use clap::{Parser, Subcommand};
+use std::collections::{BTreeMap, HashMap};
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::Arc;
+use std::time::Duration;
 
-mod api;
+use anyhow::{Context, Result};
+use clap::builder::FalseyValueParser;
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
+use clap_complete::engine::ArgValueCompleter;
+use serde_json::json;
+use tracing::Instrument;
+
+mod account;
+mod aliases;
+mod audit;
 mod auth;
+mod bulk;
+mod bundle;
+mod completions;
 mod config;
+mod context;
+mod depends;
+mod due;
+mod edit;
+#[cfg(test)]
+mod fake;
+mod filters;
+mod focus;
+mod goals;
+mod history;
+mod ics;
+mod journal;
+mod limits;
+mod links;
+mod locale;
+mod lock;
+mod logging;
+mod man;
+mod markdown;
+mod oidc;
+mod offline;
+mod org;
 mod output;
+mod places;
+mod prompt;
+mod purge;
+mod report;
+mod retitle;
+mod review;
+mod search;
+mod secrets;
+mod session;
+mod shell;
+mod standup;
+mod stats;
+mod style;
+mod tags;
+mod timelog;
+mod triage;
+#[cfg(feature = "tui")]
+mod tui;
+mod wip;
+mod workspace;
+
+use todo_api_client::cassette::Cassette;
+#[cfg(feature = "chaos")]
+use todo_api_client::chaos::{Chaos, Faults};
+use todo_api_client::read_only::ReadOnly;
+use todo_api_client::signing::HmacSigner;
+use todo_api_client::{api, error, secret, validation};
+
+use aliases::{Aliases, IdArg};
+use api::{ApiClient, RequestOptions, TodoApi};
+use config::{AuthScheme, Config, ConfirmPolicy};
+use error::ApiError;
+use history::Action;
+use journal::{Journal, Operation};
+use output::{Field, Layout, ListOptions};
+use style::Colorize;
+use validation::TodoInput;
 
-use api::ApiClient;
-use config::Config;
+const DEFAULT_API_URL: &str = "http://localhost:8080";
+
+/// Exit status for a command interrupted with Ctrl+C (128 + SIGINT).
+const EXIT_CANCELLED: u8 = 130;
+/// Exit status when a prompt was needed in non-interactive mode.
+const EXIT_INPUT_REQUIRED: u8 = 3;
+/// Exit status when the server rejected the credentials.
+const EXIT_UNAUTHORIZED: u8 = 4;
+/// Exit status when a todo or other resource doesn't exist.
+const EXIT_NOT_FOUND: u8 = 5;
+/// Exit status for failures worth retrying: network errors, rate limiting
+/// and server errors.
+const EXIT_TEMPORARY: u8 = 6;
+/// Exit status when read-only mode refused a command that changes data.
+const EXIT_READ_ONLY: u8 = 7;
 
 /// todo-cli: A CLI tool for managing todos via the go-api-starter API
 #[derive(Parser)]
@@ -15,69 +103,503 @@ use config::Config;
 #[command(author, version, about, long_about = None)]
 #[command(propagate_version = true)]
 struct Cli {
-    /// API server URL
-    #[arg(short, long, env = "TODO_API_URL", default_value = "http://localhost:8080")]
-    url: String,
+    /// API server URL [default: configured URL, else http://localhost:8080]
+    #[arg(short, long, env = "TODO_API_URL")]
+    url: Option<String>,
+
+    /// Output format (text, json, csv, tsv, markdown) [default: configured
+    /// format, else text]
+    #[arg(short, long)]
+    format: Option<String>,
+
+    /// Wrap JSON output in a versioned envelope:
+    /// {"schema": "v1", "kind": "todo_list", "data": [...]}
+    #[arg(long, env = "TODO_OUTPUT_SCHEMA", value_enum, value_name = "VERSION")]
+    output_schema: Option<output::Schema>,
+
+    /// Record API traffic to this cassette, with credentials scrubbed
+    #[arg(long, env = "TODO_RECORD", value_name = "PATH", hide = true)]
+    record: Option<PathBuf>,
+
+    /// Answer API requests from this cassette instead of the server
+    #[arg(long, env = "TODO_REPLAY", value_name = "PATH", hide = true, conflicts_with = "record")]
+    replay: Option<PathBuf>,
+
+    /// Delay and fail requests for resilience testing, e.g.
+    /// `p=0.2,latency=500ms,status=503,seed=42`
+    #[cfg(feature = "chaos")]
+    #[arg(long, env = "TODO_CHAOS", value_name = "SPEC", hide = true)]
+    chaos: Option<Faults>,
+
+    /// Predictable behaviour for end-to-end tests: no prompts, no emoji,
+    /// responses checked against the schemas, and credentials in a file in
+    /// the state directory instead of the keyring
+    #[arg(long, env = "TODO_TEST_HARNESS", value_parser = FalseyValueParser::new(), hide = true)]
+    test_harness: bool,
+
+    /// Config file to use instead of the one in the user config directory
+    #[arg(long = "config", env = "TODO_CONFIG", value_name = "PATH")]
+    config_file: Option<PathBuf>,
+
+    /// Log verbosity (error, warn, info, debug, trace) or a tracing filter
+    #[arg(long, env = "TODO_LOG", default_value = "warn")]
+    log_level: String,
 
-    /// Output format (text, json)
-    #[arg(short, long, default_value = "text")]
-    format: String,
+    /// Fail with exit status 3 instead of prompting (implied when stdin is
+    /// not a terminal)
+    #[arg(long, env = "TODO_NON_INTERACTIVE", value_parser = FalseyValueParser::new())]
+    non_interactive: bool,
+
+    /// Screen-reader friendly output: words instead of symbols, no emoji,
+    /// box drawing or strikethrough
+    #[arg(long, env = "TODO_ACCESSIBLE", value_parser = FalseyValueParser::new())]
+    accessible: bool,
+
+    /// Refuse every request that would change something on the server
+    #[arg(long, env = "TODO_READ_ONLY", value_parser = FalseyValueParser::new())]
+    read_only: bool,
+
+    /// Work from the local cache without contacting the server; changes
+    /// are queued for `todo sync`
+    #[arg(long, env = "TODO_OFFLINE", value_parser = FalseyValueParser::new())]
+    offline: bool,
+
+    /// Log in from TODO_EMAIL and TODO_PASSWORD first when the token is
+    /// missing or expired, and again if the server rejects it (token auth
+    /// schemes only)
+    #[arg(long, env = "TODO_LOGIN_IF_NEEDED", value_parser = FalseyValueParser::new())]
+    login_if_needed: bool,
+
+    /// Check API responses against the bundled schemas (always on under the
+    /// test harness)
+    #[arg(long, env = "TODO_VALIDATE_RESPONSES", value_parser = FalseyValueParser::new())]
+    validate_responses: bool,
+
+    /// Workspace ID to scope todo commands to [default: `workspace switch` choice]
+    #[arg(long, env = "TODO_WORKSPACE")]
+    workspace: Option<i64>,
+
+    /// Extra request header as "Name: value" (repeatable); overrides [headers] in config
+    #[arg(short = 'H', long = "header", value_parser = parse_header)]
+    headers: Vec<(String, String)>,
 
     #[command(subcommand)]
     command: Commands,
 }
 
-#[derive(Subcommand)]
+#[derive(Subcommand, Clone)]
 enum Commands {
     /// Authentication commands
     Auth {
         #[command(subcommand)]
         command: AuthCommands,
     },
+    /// Manage your account
+    Account {
+        #[command(subcommand)]
+        command: AccountCommands,
+    },
+    /// Manage shared workspaces
+    Workspace {
+        #[command(subcommand)]
+        command: WorkspaceCommands,
+    },
+    /// Answer invitations to join workspaces
+    Invites {
+        #[command(subcommand)]
+        command: InviteCommands,
+    },
     /// List all todos
     List {
-        /// Filter by completion status
-        #[arg(short, long)]
-        completed: Option<bool>,
+        /// Saved filter to apply, e.g. @work-today
+        #[arg(value_name = "@FILTER", value_parser = filters::parse_name)]
+        saved: Option<String>,
+        #[command(flatten)]
+        filter: filters::ListFilter,
+        /// Ignore the default filter and the active context
+        #[arg(long, conflicts_with = "saved")]
+        all: bool,
+        /// Wrap long titles instead of cutting them to the terminal width
+        #[arg(long)]
+        no_truncate: bool,
+        /// Show every column regardless of terminal width
+        #[arg(long, conflicts_with = "compact")]
+        wide: bool,
+        /// Show only IDs and titles regardless of terminal width
+        #[arg(long)]
+        compact: bool,
+        /// Omit the header row from CSV/TSV output
+        #[arg(long)]
+        no_header: bool,
+        /// CSV/TSV columns to print, comma-separated
+        #[arg(long, value_enum, value_delimiter = ',')]
+        columns: Vec<Field>,
+    },
+    /// Search todo titles: `report*` for prefixes, `"exact phrase"`, AND/OR/NOT
+    Search {
+        query: String,
+        /// Use this machine's index of listed todos instead of fetching them
+        #[arg(long)]
+        local: bool,
+        /// Maximum number of results
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
     },
     /// Get a specific todo by ID
     Get {
-        /// Todo ID
-        id: i64,
+        /// Todo ID or alias
+        #[arg(add = ArgValueCompleter::new(completions::todo_ids))]
+        id: IdArg,
+        /// Also show a QR code of the todo's link
+        #[arg(long)]
+        qr: bool,
     },
-    /// Create a new todo
+    /// Create one or more todos
     Create {
-        /// Todo title
-        title: String,
+        /// Todo titles, one todo each
+        #[arg(required = true)]
+        titles: Vec<String>,
+        /// Expected effort, e.g. 30m, 2h, 1d (servers that support estimates)
+        #[arg(long, value_parser = due::parse_estimate)]
+        estimate: Option<i64>,
+        /// With several titles, write per-item outcomes to this JSON file
+        #[arg(long, value_name = "FILE")]
+        report: Option<PathBuf>,
+    },
+    /// Create todos from a file written by `export --as json`, or from a
+    /// markdown checklist or org outline
+    Import {
+        file: PathBuf,
+        /// File format; markdown for .md files, org for .org, else json
+        #[arg(long, value_enum)]
+        from: Option<bulk::ImportFormat>,
+        /// Show the todos and subtasks that would be created, and stop
+        #[arg(long)]
+        dry_run: bool,
+        /// Write per-item outcomes to this JSON file
+        #[arg(long, value_name = "FILE")]
+        report: Option<PathBuf>,
     },
     /// Update a todo
     Update {
-        /// Todo ID
-        id: i64,
+        /// Todo ID or alias
+        #[arg(add = ArgValueCompleter::new(completions::todo_ids))]
+        id: IdArg,
         /// New title
         #[arg(short, long)]
         title: Option<String>,
         /// Mark as completed
         #[arg(short, long)]
         completed: Option<bool>,
+        /// Expected effort, e.g. 30m, 2h, 1d (servers that support estimates)
+        #[arg(long, value_parser = due::parse_estimate)]
+        estimate: Option<i64>,
+        /// Prompt for each field with the current value prefilled
+        #[arg(short, long, conflicts_with_all = ["title", "completed", "estimate"])]
+        interactive: bool,
+        /// Apply without showing the changes and asking first
+        #[arg(short, long)]
+        yes: bool,
     },
-    /// Delete a todo
+    /// Add text to the end of a todo's title
+    Append {
+        /// Todo ID or alias
+        #[arg(add = ArgValueCompleter::new(completions::todo_ids))]
+        id: IdArg,
+        #[arg(allow_hyphen_values = true)]
+        text: String,
+        /// Put between the title and the text, unless one already has it
+        #[arg(long, default_value = " ")]
+        sep: String,
+    },
+    /// Add text to the start of a todo's title
+    Prepend {
+        /// Todo ID or alias
+        #[arg(add = ArgValueCompleter::new(completions::todo_ids))]
+        id: IdArg,
+        #[arg(allow_hyphen_values = true)]
+        text: String,
+        /// Put between the text and the title, unless one already has it
+        #[arg(long, default_value = " ")]
+        sep: String,
+    },
+    /// Edit several todos at once as a YAML document
+    Edit {
+        /// Only include matching todos, e.g. completed=false (repeatable)
+        #[arg(long)]
+        filter: Vec<String>,
+        /// Open the document in $EDITOR and apply the changes; without this
+        /// the document is printed, as JSON with `--format json`
+        #[arg(long)]
+        editor: bool,
+        /// Apply without showing the changes and asking first
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Delete one or more todos
     Delete {
-        /// Todo ID
-        id: i64,
+        /// Todo IDs or aliases
+        #[arg(required = true, add = ArgValueCompleter::new(completions::todo_ids))]
+        ids: Vec<IdArg>,
         /// Skip confirmation
         #[arg(short, long)]
         force: bool,
+        /// With several IDs, write per-item outcomes to this JSON file
+        #[arg(long, value_name = "FILE")]
+        report: Option<PathBuf>,
+    },
+    /// Delete completed todos in bulk, e.g. `purge --completed --older-than 30d`
+    Purge {
+        /// Purge completed todos (currently the only kind)
+        #[arg(long, required = true)]
+        completed: bool,
+        /// Only those last changed longer ago than this, e.g. 30d, 2w
+        #[arg(long, value_parser = due::parse_span)]
+        older_than: Option<chrono::Duration>,
+        /// Skip confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Send changes made offline and refresh the local cache
+    Sync {
+        /// Write per-change outcomes to this JSON file
+        #[arg(long, value_name = "FILE")]
+        report: Option<PathBuf>,
     },
-    /// Mark a todo as completed
+    /// Continue an interrupted import or purge; lists them without an ID
+    Resume {
+        /// Operation ID, as printed when it started
+        op_id: Option<String>,
+        /// For imports, write per-item outcomes to this JSON file
+        #[arg(long, value_name = "FILE")]
+        report: Option<PathBuf>,
+    },
+    /// Mark one or more todos as completed
     Done {
-        /// Todo ID
-        id: i64,
+        /// Todo IDs or aliases
+        #[arg(required = true, add = ArgValueCompleter::new(completions::todo_ids))]
+        ids: Vec<IdArg>,
+        /// Complete them even if todos they depend on are still open
+        #[arg(long)]
+        force: bool,
+        /// With several IDs, write per-item outcomes to this JSON file
+        #[arg(long, value_name = "FILE")]
+        report: Option<PathBuf>,
     },
     /// Mark a todo as incomplete
     Undone {
-        /// Todo ID
-        id: i64,
+        /// Todo ID or alias
+        #[arg(add = ArgValueCompleter::new(completions::todo_ids))]
+        id: IdArg,
+    },
+    /// Mark a todo as in progress (servers that track status)
+    StartWork {
+        /// Todo ID or alias
+        #[arg(add = ArgValueCompleter::new(completions::todo_ids))]
+        id: IdArg,
+        /// Start it even when the WIP limit is reached
+        #[arg(long)]
+        force: bool,
+    },
+    /// Mark a todo as blocked (servers that track status)
+    Block {
+        /// Todo ID or alias
+        #[arg(add = ArgValueCompleter::new(completions::todo_ids))]
+        id: IdArg,
+        /// What it is waiting on
+        #[arg(long)]
+        reason: Option<String>,
+    },
+    /// Work on a todo against a countdown, logging the time spent
+    Focus {
+        /// Todo ID or alias
+        #[arg(add = ArgValueCompleter::new(completions::todo_ids))]
+        id: IdArg,
+        /// Length of each round, e.g. 25m, 50m
+        #[arg(long, value_parser = due::parse_span, default_value = "25m")]
+        pomodoro: chrono::Duration,
+    },
+    /// Make a todo wait on others (servers that support dependencies)
+    Depends {
+        /// Todo ID or alias
+        #[arg(add = ArgValueCompleter::new(completions::todo_ids))]
+        id: IdArg,
+        /// Todos it waits on (repeatable)
+        #[arg(long, required = true, add = ArgValueCompleter::new(completions::todo_ids))]
+        on: Vec<IdArg>,
+        /// Stop waiting on them instead
+        #[arg(long)]
+        remove: bool,
+    },
+    /// Link a todo to another todo or a web page (servers that support
+    /// links)
+    Link {
+        /// Todo ID or alias
+        #[arg(add = ArgValueCompleter::new(completions::todo_ids))]
+        id: IdArg,
+        /// Todo ID, alias or http(s) URL to link to
+        #[arg(long, value_name = "ID|URL", add = ArgValueCompleter::new(completions::todo_ids))]
+        to: String,
+        /// How the todos relate [default: relates-to]
+        #[arg(long, value_enum)]
+        kind: Option<links::Kind>,
+        /// Remove the link to that todo or URL instead
+        #[arg(long)]
+        remove: bool,
+    },
+    /// Open a todo's external link in the browser, or the todo itself when
+    /// it has none
+    Open {
+        /// Todo ID or alias
+        #[arg(add = ArgValueCompleter::new(completions::todo_ids))]
+        id: IdArg,
+        /// Which external link to follow when there are several, from 1
+        #[arg(long, value_name = "N")]
+        link: Option<usize>,
+    },
+    /// Write all todos to a file or stdout
+    Export {
+        /// Document format
+        #[arg(long = "as", value_enum, default_value_t = ExportFormat::Markdown)]
+        kind: ExportFormat,
+        /// File to write instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Filter by completion status
+        #[arg(short, long)]
+        completed: Option<bool>,
+    },
+    /// Render a shareable status report
+    Report {
+        /// Report format
+        #[arg(long, value_enum, default_value_t = ReportFormat::Html)]
+        format: ReportFormat,
+        /// File to write instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Go through open todos without a due date, one key per action
+    Triage,
+    /// Run todo commands at a prompt, with history and Tab completion
+    Shell,
+    /// Print a script that completes commands, flags and todo IDs, e.g.
+    /// `source <(todo completions bash)`
+    Completions {
+        #[arg(value_enum)]
+        shell: clap_complete::Shell,
+    },
+    /// Print the man page, or write one per subcommand into a directory,
+    /// for packagers
+    #[command(hide = true)]
+    Man {
+        /// Directory for todo.1, todo-list.1 and the rest
+        #[arg(long, value_name = "DIR")]
+        dir: Option<PathBuf>,
+    },
+    /// Full-screen todo list: complete, create, edit and delete with single
+    /// keys
+    #[cfg(feature = "tui")]
+    Tui {
+        /// Seconds between automatic refreshes
+        #[arg(
+            long,
+            value_name = "SECONDS",
+            default_value_t = 30,
+            value_parser = clap::value_parser!(u64).range(1..)
+        )]
+        refresh: u64,
+    },
+    /// Daily review: confirm or reopen yesterday's completed todos, then
+    /// confirm, defer or reschedule what is due today
+    Review,
+    /// Print yesterday's completions, today's plan and blockers, ready to
+    /// paste into a standup
+    Standup {
+        /// Markup to write
+        #[arg(long, value_enum, default_value_t = standup::Format::Markdown)]
+        format: standup::Format,
+    },
+    /// Show what is due today, plus overdue carryovers
+    Today {
+        /// Show tomorrow instead
+        #[arg(long, conflicts_with = "date")]
+        tomorrow: bool,
+        /// Show another day (YYYY-MM-DD)
+        #[arg(long)]
+        date: Option<chrono::NaiveDate>,
+        /// Show the whole week, starting on the locale's first day of the week
+        #[arg(long)]
+        week: bool,
+    },
+    /// Show open/done counts, completion rate, average age and estimated
+    /// effort left
+    Stats {
+        /// Break the numbers down by group
+        #[arg(long, value_enum)]
+        by: Option<stats::GroupBy>,
+    },
+    /// Print a link to a todo, e.g. to open it on a phone
+    Share {
+        /// Todo ID or alias
+        #[arg(add = ArgValueCompleter::new(completions::todo_ids))]
+        id: IdArg,
+        /// Render the link as a QR code in the terminal
+        #[arg(long)]
+        qr: bool,
+    },
+    /// Show remaining API quota, when it resets and the configured limits
+    Limits,
+    /// Show recently created, viewed or completed todos
+    Recent {
+        /// Number of todos to show
+        #[arg(short = 'n', long, default_value_t = 10)]
+        limit: usize,
+    },
+    /// Show past activity
+    History {
+        /// Show command invocations instead of todo activity
+        #[arg(long)]
+        commands: bool,
+        /// Number of entries to show
+        #[arg(short = 'n', long, default_value_t = 20)]
+        limit: usize,
+        /// Delete all recorded history
+        #[arg(long)]
+        clear: bool,
+    },
+    /// Add, remove or rename a tag across many todos
+    Tag {
+        #[command(subcommand)]
+        command: TagCommands,
+    },
+    /// Show the requests that changed something, kept in the state directory
+    Audit {
+        #[command(subcommand)]
+        command: AuditCommands,
+    },
+    /// Save `list` flags under a name for `todo list @name`
+    Filter {
+        #[command(subcommand)]
+        command: FilterCommands,
+    },
+    /// Give place tags like @home coordinates for ICS export
+    Place {
+        #[command(subcommand)]
+        command: PlaceCommands,
+    },
+    /// Show progress toward the weekly goal, or set or clear it
+    Goals {
+        #[command(subcommand)]
+        command: Option<GoalCommands>,
+        /// Past weeks to show
+        #[arg(long, default_value_t = 8, value_name = "N")]
+        weeks: usize,
+    },
+    /// Show, set or clear the context that `list`, `today` and `search`
+    /// are narrowed to
+    Context {
+        #[command(subcommand)]
+        command: Option<ContextCommands>,
     },
     /// Show configuration
     Config {
@@ -86,16 +608,64 @@ enum Commands {
     },
 }
 
-#[derive(Subcommand)]
+impl Commands {
+    /// The single todo a command acts on, for hints when it doesn't exist.
+    fn todo_id(&self) -> Option<&IdArg> {
+        match self {
+            Commands::Get { id, .. }
+            | Commands::Update { id, .. }
+            | Commands::Append { id, .. }
+            | Commands::Prepend { id, .. }
+            | Commands::Undone { id }
+            | Commands::StartWork { id, .. }
+            | Commands::Block { id, .. }
+            | Commands::Focus { id, .. }
+            | Commands::Depends { id, .. }
+            | Commands::Link { id, .. }
+            | Commands::Open { id, .. } => Some(id),
+            Commands::Delete { ids, .. } | Commands::Done { ids, .. } if ids.len() == 1 => {
+                ids.first()
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum ExportFormat {
+    /// Task list for GitHub issues and notes apps
+    Markdown,
+    Json,
+    /// Emacs org-mode outline with TODO/DONE headings
+    Org,
+    /// iCalendar tasks; todos at a place with coordinates get its location
+    Ics,
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum ReportFormat {
+    /// Standalone styled page
+    Html,
+    /// Paginated A4 document for printing or archiving
+    Pdf,
+}
+
+#[derive(Subcommand, Clone)]
 enum AuthCommands {
     /// Login to the API
     Login {
         /// Email address
-        #[arg(short, long)]
-        email: String,
+        #[arg(short, long, required_unless_present = "browser")]
+        email: Option<String>,
         /// Password (will prompt if not provided)
-        #[arg(short, long)]
+        #[arg(short, long, env = "TODO_PASSWORD", hide_env_values = true)]
         password: Option<String>,
+        /// Read the password from a file (`-` for stdin)
+        #[arg(long, value_name = "PATH")]
+        password_file: Option<PathBuf>,
+        /// Sign in through the SSO provider in [oidc] instead
+        #[arg(long, conflicts_with_all = ["email", "password_file"])]
+        browser: bool,
     },
     /// Register a new account
     Register {
@@ -103,103 +673,1391 @@ enum AuthCommands {
         #[arg(short, long)]
         email: String,
         /// Password (will prompt if not provided)
-        #[arg(short, long)]
+        #[arg(short, long, env = "TODO_PASSWORD", hide_env_values = true)]
         password: Option<String>,
+        /// Read the password from a file (`-` for stdin)
+        #[arg(long, value_name = "PATH")]
+        password_file: Option<PathBuf>,
     },
     /// Logout (clear stored token)
     Logout,
+    /// Store the proxy credentials for `auth_scheme = "basic"`
+    SetBasic {
+        /// Username
+        #[arg(short, long)]
+        username: String,
+        /// Password (will prompt if not provided)
+        #[arg(short, long)]
+        password: Option<String>,
+    },
+    /// Store the shared secret for `auth_scheme = "hmac"` request signing
+    SetSecret {
+        /// Secret (will prompt if not provided)
+        #[arg(long)]
+        secret: Option<String>,
+    },
     /// Show current auth status
     Status,
 }
 
-#[derive(Subcommand)]
+#[derive(Subcommand, Clone)]
+enum AccountCommands {
+    /// Download your data as a zip archive
+    Export {
+        /// Archive to write
+        #[arg(short, long)]
+        output: PathBuf,
+    },
+    /// Show your profile
+    Show,
+    /// Change your name or login email
+    Update {
+        /// Display name
+        #[arg(long)]
+        name: Option<String>,
+        /// New login email; may need confirming from the new address
+        #[arg(long)]
+        email: Option<String>,
+        /// Change the email without asking first
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Upload a new avatar image (PNG, JPEG or WebP)
+    SetAvatar {
+        /// Image file
+        #[arg(required_unless_present = "remove")]
+        image: Option<PathBuf>,
+        /// Clear the current avatar instead
+        #[arg(long, conflicts_with = "image")]
+        remove: bool,
+    },
+    /// Permanently delete your account and all its data
+    Delete {
+        /// Your email, to confirm without a prompt
+        #[arg(long, value_name = "EMAIL")]
+        confirm: Option<String>,
+    },
+}
+
+#[derive(Subcommand, Clone)]
+enum WorkspaceCommands {
+    /// List workspaces you belong to; * marks the current one
+    List,
+    /// Create a workspace
+    Create {
+        /// Workspace name
+        name: String,
+    },
+    /// Scope todo commands to a workspace by default
+    Switch {
+        /// Workspace ID or name; omit to go back to personal todos
+        workspace: Option<String>,
+    },
+    /// List members or change their roles
+    Members {
+        #[command(subcommand)]
+        command: MemberCommands,
+    },
+    /// Invite someone to the current workspace by email
+    Invite {
+        /// Email address to invite
+        email: String,
+        /// Role granted on joining (admin, member, viewer)
+        #[arg(long, default_value = "member")]
+        role: api::Role,
+    },
+}
+
+#[derive(Subcommand, Clone)]
+enum MemberCommands {
+    /// List a workspace's members and their roles
+    List {
+        /// Workspace ID [default: current workspace]
+        workspace: Option<i64>,
+    },
+    /// Change a member's role in the current workspace (admins only)
+    SetRole {
+        /// Member's email address
+        email: String,
+        /// New role (admin, member, viewer)
+        role: api::Role,
+    },
+}
+
+#[derive(Subcommand, Clone)]
+enum TagCommands {
+    /// Tag todos, e.g. `todo tag add work 3 4 a7` or `--filter @due-soon`
+    Add {
+        tag: String,
+        #[command(flatten)]
+        targets: TagTargets,
+    },
+    /// Take a tag off todos
+    Remove {
+        tag: String,
+        #[command(flatten)]
+        targets: TagTargets,
+    },
+    /// Rename a tag on every todo that has it
+    Rename {
+        old: String,
+        new: String,
+        /// Write per-item outcomes to this JSON file
+        #[arg(long, value_name = "FILE")]
+        report: Option<PathBuf>,
+    },
+}
+
+#[derive(clap::Args, Clone)]
+struct TagTargets {
+    /// Todo IDs or aliases
+    #[arg(
+        required_unless_present = "filter",
+        add = ArgValueCompleter::new(completions::todo_ids)
+    )]
+    ids: Vec<IdArg>,
+    /// Every todo a saved filter matches instead, e.g. @due-soon
+    #[arg(
+        long,
+        value_name = "@FILTER",
+        value_parser = filters::parse_name,
+        conflicts_with = "ids"
+    )]
+    filter: Option<String>,
+    /// Write per-item outcomes to this JSON file
+    #[arg(long, value_name = "FILE")]
+    report: Option<PathBuf>,
+}
+
+impl TagTargets {
+    fn targets(&self) -> tags::Targets<'_> {
+        tags::Targets {
+            ids: &self.ids,
+            filter: self.filter.as_deref(),
+        }
+    }
+}
+
+#[derive(Subcommand, Clone)]
+enum AuditCommands {
+    /// Show logged requests, oldest first
+    Show {
+        /// Only requests since a span ago (2h, 7d) or a date (YYYY-MM-DD)
+        #[arg(long, value_parser = audit::parse_since)]
+        since: Option<chrono::DateTime<chrono::Utc>>,
+        /// Number of entries to show
+        #[arg(short = 'n', long, default_value_t = 50)]
+        limit: usize,
+    },
+}
+
+#[derive(Subcommand, Clone)]
+enum FilterCommands {
+    /// Save a filter, e.g. `todo filter add due-soon --due-within 1d`
+    Add {
+        name: String,
+        /// `list` flags: --completed, --overdue, --due-within, --status, --at
+        #[arg(required = true, trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
+    /// Show saved filters
+    List,
+    /// Delete a saved filter
+    Remove {
+        name: String,
+    },
+}
+
+#[derive(Subcommand, Clone)]
+enum PlaceCommands {
+    /// Set where a place is, e.g. `todo place add home 52.5200,13.4050`
+    Add {
+        name: String,
+        /// Latitude and longitude
+        #[arg(value_name = "LAT,LON", allow_hyphen_values = true)]
+        position: String,
+    },
+    /// Show places with coordinates
+    List,
+    /// Forget a place's coordinates; todos keep the tag
+    Remove {
+        name: String,
+    },
+}
+
+#[derive(Subcommand, Clone)]
+enum ContextCommands {
+    /// Only show todos with this tag, e.g. `todo context set @office`
+    Set { name: String },
+    /// Show every todo again
+    Clear,
+}
+
+#[derive(Subcommand, Clone)]
+enum GoalCommands {
+    /// Set the goal, e.g. `todo goals set --per-week 15`
+    Set {
+        /// Todos to complete each week
+        #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+        per_week: u64,
+    },
+    /// Stop tracking a goal; past weeks are kept
+    Clear,
+    /// Print `7/15` for a shell prompt, from the last listing, or nothing
+    /// without a goal
+    Prompt,
+}
+
+#[derive(Subcommand, Clone)]
+enum InviteCommands {
+    /// List pending invitations addressed to you
+    List {
+        /// Include accepted, declined and expired invitations
+        #[arg(long)]
+        all: bool,
+    },
+    /// Join the invitation's workspace
+    Accept {
+        /// Invitation ID
+        id: i64,
+    },
+    /// Turn an invitation down
+    Decline {
+        /// Invitation ID
+        id: i64,
+    },
+}
+
+#[derive(Subcommand, Clone)]
 enum ConfigCommands {
     /// Show current configuration
     Show,
-    /// Set API URL
-    SetUrl {
-        /// API URL
-        url: String,
+    /// Print one setting, e.g. `todo config get theme.preset`
+    Get {
+        key: String,
+    },
+    /// Validate and save a setting
+    Set {
+        key: String,
+        value: String,
+    },
+    /// Reset a setting to its default
+    Unset {
+        key: String,
+    },
+    /// List the settings `get`/`set` accept
+    Keys,
+    /// Write settings and aliases to a file for setting up another machine
+    Export {
+        /// File to write instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Include credentials, encrypted with a passphrase
+        #[arg(long)]
+        with_secrets: bool,
+    },
+    /// Replace the config with one from `config export`
+    Import {
+        file: PathBuf,
+        /// Leave stored credentials alone even if the bundle has some
+        #[arg(long)]
+        no_secrets: bool,
     },
 }
 
 #[tokio::main]
-async fn main() -> Result<()> {
-    let cli = Cli::parse();
-    let config = Config::load()?;
-    let client = ApiClient::new(&cli.url, config.get_token());
+async fn main() -> ExitCode {
+    // The shell asking for candidates; answers and exits
+    clap_complete::CompleteEnv::with_factory(Cli::command)
+        .var(completions::VAR)
+        .complete();
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+    let command_name = matches.subcommand_name().unwrap_or_default();
+
+    // Ctrl+C during a bulk command lets it finish the current item and
+    // print its summary. Otherwise, or on a second Ctrl+C, dropping the
+    // command future aborts any in-flight requests.
+    let command = execute(cli, command_name);
+    tokio::pin!(command);
+    let result = loop {
+        tokio::select! {
+            result = &mut command => break Some(result),
+            _ = tokio::signal::ctrl_c() => {
+                eprintln!();
+                if !bulk::cancel() {
+                    break None;
+                }
+                eprintln!("Stopping after the current item; Ctrl+C again to abort.");
+            }
+        }
+    };
+
+    match result {
+        Some(result) if bulk::cancelled() => {
+            if let Err(err) = result {
+                output::print_error(&err);
+            }
+            eprintln!("Cancelled.");
+            ExitCode::from(EXIT_CANCELLED)
+        }
+        Some(Ok(())) => ExitCode::SUCCESS,
+        Some(Err(err)) => {
+            output::print_error(&err);
+            exit_code(&err)
+        }
+        None => {
+            eprintln!("Cancelled.");
+            ExitCode::from(EXIT_CANCELLED)
+        }
+    }
+}
+
+fn exit_code(err: &anyhow::Error) -> ExitCode {
+    if err.is::<prompt::InputRequired>() {
+        return ExitCode::from(EXIT_INPUT_REQUIRED);
+    }
+    match err.downcast_ref::<ApiError>() {
+        Some(ApiError::Unauthorized(_)) => ExitCode::from(EXIT_UNAUTHORIZED),
+        Some(ApiError::NotFound { .. }) => ExitCode::from(EXIT_NOT_FOUND),
+        Some(ApiError::ReadOnly(_)) => ExitCode::from(EXIT_READ_ONLY),
+        Some(err) if err.is_retryable() => ExitCode::from(EXIT_TEMPORARY),
+        _ => ExitCode::FAILURE,
+    }
+}
+
+async fn execute(cli: Cli, command_name: &str) -> Result<()> {
+    if let Some(ref path) = cli.config_file {
+        config::use_path(path.clone());
+    }
+    let mut config = Config::load()?;
+    let _log_guard = logging::init(
+        &cli.log_level,
+        config.log_file.as_deref(),
+        config.otlp_endpoint.as_deref(),
+    )?;
+    output::Theme::from_config(&config.theme)?.install();
+    if !cli.test_harness {
+        locale::Locale::from_config(config.locale.as_deref())?.install();
+    }
+    output::set_accessible(cli.accessible);
+    prompt::set_interactive(!cli.non_interactive && !cli.test_harness);
+    output::set_emoji(style::init_terminal() && !cli.test_harness);
+    if cli.test_harness {
+        secrets::Backend::File.install();
+    } else {
+        config.secret_backend.install();
+    }
+    let api_url = cli
+        .url
+        .clone()
+        .or_else(|| config.api_url.clone())
+        .unwrap_or_else(|| DEFAULT_API_URL.to_string());
+    config.load_token(&api_url);
 
-    match cli.command {
+    let mut builder = ApiClient::builder(&api_url);
+    // First, so a refused request is never signed, recorded or sent
+    if cli.read_only || config.read_only {
+        builder = builder.middleware(Arc::new(ReadOnly));
+    }
+    if cli.offline || config.offline {
+        builder = builder.middleware(Arc::new(offline::Unreachable));
+    }
+    // Replayed requests never reach the server, so there's nothing to audit
+    if cli.replay.is_none() {
+        builder = builder.middleware(Arc::new(audit::Audit::new(std::env::args().skip(1))));
+    }
+    // `auth` commands must work while the credentials are still being set up
+    let setting_up = matches!(cli.command, Commands::Auth { .. });
+    match config.auth_scheme {
+        AuthScheme::Bearer => {}
+        AuthScheme::Hmac => match Config::hmac_secret(&api_url) {
+            Ok(secret) => builder = builder.middleware(Arc::new(HmacSigner::new(secret))),
+            Err(_) if setting_up => {}
+            Err(err) => return Err(err),
+        },
+        AuthScheme::Session => builder = builder.session(session::load(&api_url)),
+        AuthScheme::Basic => match Config::basic_auth(&api_url) {
+            Ok((username, password)) => builder = builder.basic_auth(username, password),
+            Err(_) if setting_up => {}
+            Err(err) => return Err(err),
+        },
+    }
+    // Config headers first, so the same header given on the command line wins
+    for (name, value) in &config.headers {
+        builder = builder.header(name, value);
+    }
+    for (name, value) in &cli.headers {
+        builder = builder.header(name, value);
+    }
+    // Before the cassette, so replayed sessions can run under chaos too
+    #[cfg(feature = "chaos")]
+    if let Some(ref faults) = cli.chaos {
+        builder = builder.middleware(Arc::new(Chaos::new(faults.clone())));
+    }
+    // Last, so the cassette sees requests exactly as they'd be sent
+    if let Some(ref path) = cli.record {
+        builder = builder.middleware(Arc::new(Cassette::record(path)));
+    } else if let Some(ref path) = cli.replay {
+        builder = builder.middleware(Arc::new(Cassette::replay(path)?));
+    }
+    let client = builder
+        .token(config.get_token())
+        .workspace(cli.workspace.or(config.workspace))
+        .user_agent_suffix(config.user_agent_suffix.clone())
+        .timeout(config.timeout.map(Duration::from_secs))
+        .max_response_bytes(config.max_response_mb.map(|mb| mb as usize * 1024 * 1024))
+        .validate_responses(cli.validate_responses || cli.test_harness)
+        .build()?;
+
+    let format = cli
+        .format
+        .clone()
+        .or_else(|| config.format.clone())
+        .unwrap_or_else(|| "text".to_string());
+    output::set_json(format == "json");
+    if let Some(schema) = cli.output_schema.filter(|_| format == "json") {
+        output::set_schema(schema);
+    }
+    let span = tracing::info_span!("command", name = command_name);
+    let can_relogin = config.has_token() && !matches!(cli.command, Commands::Auth { .. });
+    // Scripts ask for this so CI doesn't need a separate `auth login` step.
+    // Token schemes only: a fresh session cookie can't be swapped into the client
+    let auto_login = (cli.login_if_needed || config.login_if_needed)
+        && config.auth_scheme != AuthScheme::Session
+        && !matches!(cli.command, Commands::Auth { .. });
+    let client = if auto_login && auth::needs_login(&config) {
+        let token = auth::login_from_env(&client, &config).await?;
+        config.load_token(client.base_url());
+        client.with_token(token)
+    } else {
+        client
+    };
+    let record = !matches!(
+        cli.command,
+        Commands::Recent { .. } | Commands::History { .. } | Commands::Audit { .. }
+    );
+    let todo_id = cli.command.todo_id().and_then(|id| Aliases::load().resolve(id).ok());
+    // `sync` talks to the server itself; `auth` has nothing to cache
+    let cache = !matches!(cli.command, Commands::Sync { .. } | Commands::Auth { .. });
+    if matches!(cli.command, Commands::Shell) {
+        let client = offline::Cached::new(&client, cache);
+        return shell::run(&format, config, &client).instrument(span).await;
+    }
+    let result = run(
+        cli.command.clone(),
+        &format,
+        config,
+        &offline::Cached::new(&client, cache),
+    )
+    .instrument(span.clone())
+    .await;
+
+    // A rejected token on an interactive terminal gets one chance to log in
+    // again, after which the original command is retried
+    let result = match result {
+        Err(err) if can_relogin && auth::is_expired_session(&err) => {
+            let mut config = Config::load()?;
+            let Some(token) = auth::relogin(&client, &config).await? else {
+                return Err(err);
+            };
+            config.load_token(client.base_url());
+            let client = client.with_token(token);
+            run(cli.command, &format, config, &offline::Cached::new(&client, cache))
+                .instrument(span)
+                .await
+        }
+        Err(err) if auto_login && auth::is_unauthorized(&err) => {
+            let mut config = Config::load()?;
+            let token = auth::login_from_env(&client, &config).await?;
+            config.load_token(client.base_url());
+            let client = client.with_token(token);
+            run(cli.command, &format, config, &offline::Cached::new(&client, cache))
+                .instrument(span)
+                .await
+        }
+        result => result,
+    };
+
+    // Mistyped commands and flags already get clap's suggestions
+    let result = match (result, todo_id) {
+        (Err(err), Some(id)) => Err(search::did_you_mean(err, id)),
+        (result, _) => result,
+    };
+
+    if record {
+        history::record_command(std::env::args().skip(1), result.is_ok());
+    }
+    result
+}
+
+/// Dispatches a parsed command against any `TodoApi` implementation.
+async fn run<A: TodoApi>(
+    command: Commands,
+    format: &str,
+    config: Config,
+    client: &A,
+) -> Result<()> {
+    let opts = RequestOptions::default();
+    let mut aliases = Aliases::load();
+
+    match command {
         Commands::Auth { command } => match command {
-            AuthCommands::Login { email, password } => {
-                let password = password.unwrap_or_else(|| {
-                    rpassword_prompt("Password: ")
-                });
-                auth::login(&client, &email, &password).await?;
-            }
-            AuthCommands::Register { email, password } => {
-                let password = password.unwrap_or_else(|| {
-                    rpassword_prompt("Password: ")
-                });
-                auth::register(&client, &email, &password).await?;
+            AuthCommands::Login { browser: true, .. } => {
+                auth::login_browser(client, &config).await?;
+            }
+            AuthCommands::Login {
+                email,
+                password,
+                password_file,
+                ..
+            } => {
+                let email = email.context("--email is required")?;
+                let password = auth::password(password, password_file.as_deref())?;
+                auth::login(client, &email, &password, config.auth_scheme).await?;
+            }
+            AuthCommands::Register {
+                email,
+                password,
+                password_file,
+            } => {
+                let password = auth::password(password, password_file.as_deref())?;
+                auth::register(client, &email, &password, config.auth_scheme).await?;
             }
             AuthCommands::Logout => {
-                auth::logout()?;
+                auth::logout(client.base_url(), config.auth_scheme)?;
+            }
+            AuthCommands::SetBasic { username, password } => {
+                let password = match password {
+                    Some(password) => password,
+                    None => prompt::password("Password: ")?,
+                };
+                Config::set_basic_auth(client.base_url(), &username, &password)?;
+                output::success("Basic auth credentials stored.");
+                output::print_result("credentials_stored", &[], json!({ "username": username }))?;
+            }
+            AuthCommands::SetSecret { secret } => {
+                let secret = match secret {
+                    Some(secret) => secret,
+                    None => prompt::password("Signing secret: ")?,
+                };
+                Config::set_hmac_secret(client.base_url(), &secret)?;
+                output::success("Signing secret stored.");
+                output::print_result("credentials_stored", &[], ())?;
             }
             AuthCommands::Status => {
-                auth::status(&config)?;
+                auth::status(&config, client.base_url())?;
+            }
+        },
+        Commands::Account { command } => match command {
+            AccountCommands::Export { output: path } => {
+                account::export(client, &config, &path).await?;
+            }
+            AccountCommands::Show => {
+                account::show(client, format).await?;
+            }
+            AccountCommands::Update { name, email, yes } => {
+                account::update(client, name.as_deref(), email.as_deref(), yes, format).await?;
+            }
+            AccountCommands::SetAvatar { image, remove } => match image {
+                Some(path) if !remove => account::set_avatar(client, &path).await?,
+                _ => account::remove_avatar(client).await?,
+            },
+            AccountCommands::Delete { confirm } => {
+                account::delete(client, &config, confirm.as_deref()).await?;
             }
         },
-        Commands::List { completed } => {
-            let todos = client.list_todos(completed).await?;
-            output::print_todos(&todos, &cli.format)?;
+        Commands::Workspace { command } => match command {
+            WorkspaceCommands::List => workspace::list(client, format).await?,
+            WorkspaceCommands::Create { name } => {
+                workspace::create(client, &name, format).await?;
+            }
+            WorkspaceCommands::Switch { workspace } => {
+                workspace::switch(client, workspace.as_deref()).await?;
+            }
+            WorkspaceCommands::Members { command } => match command {
+                MemberCommands::List { workspace } => {
+                    workspace::members(client, workspace, format).await?;
+                }
+                MemberCommands::SetRole { email, role } => {
+                    workspace::set_role(client, &email, role).await?;
+                }
+            },
+            WorkspaceCommands::Invite { email, role } => {
+                workspace::invite(client, &email, role, format).await?;
+            }
+        },
+        Commands::Invites { command } => match command {
+            InviteCommands::List { all } => workspace::invitations(client, all, format).await?,
+            InviteCommands::Accept { id } => workspace::respond(client, id, true).await?,
+            InviteCommands::Decline { id } => workspace::respond(client, id, false).await?,
+        },
+        Commands::List {
+            saved,
+            filter,
+            all,
+            no_truncate,
+            wide,
+            compact,
+            no_header,
+            columns,
+        } => {
+            // The default filter only stands in for a list without filter flags
+            let saved = match saved {
+                Some(name) => Some(name),
+                None if !all && filter.is_empty() => config.default_filter.clone(),
+                None => None,
+            };
+            let filter = match saved {
+                Some(ref name) => filter.or(filters::resolve(&config, name)?),
+                None => filter,
+            };
+            let completed = filter.completed;
+            // Listing is a read, so transient failures are safe to retry
+            let opts = RequestOptions::new().retries(2);
+            let mut todos = client.list_todos(completed, &opts).await?;
+            // Only an unfiltered list shows which todos are gone
+            if completed.is_none() {
+                aliases.retain(&todos.iter().map(|todo| todo.id).collect());
+            }
+            aliases.assign(todos.iter().map(|todo| todo.id));
+            aliases.save();
+            search::record(&todos, completed.is_none());
+
+            // Before filtering, so blockers outside the filter still count
+            let blocked_by = depends::open_blockers(&todos);
+            let capacity = wip::Capacity::of(&todos, &config)
+                .filter(|_| completed != Some(true))
+                .filter(|_| !matches!(format, "json" | "csv" | "tsv" | "markdown" | "md"));
+            let now = chrono::Utc::now();
+            filter.retain(&mut todos, now);
+            if let Some(context) = context::Active::load().filter(|_| !all) {
+                context.print_header();
+                context.retain(&mut todos);
+            }
+            if let Some(capacity) = capacity {
+                capacity.print_header();
+            }
+            if config.overdue_first {
+                // Stable, so the server's order holds within each half
+                todos.sort_by_key(|todo| !todo.is_overdue(now));
+            }
+            let layout = match (wide, compact) {
+                (true, _) => Some(Layout::Wide),
+                (_, true) => Some(Layout::Compact),
+                _ => None,
+            };
+            let options = ListOptions {
+                layout,
+                no_truncate,
+                aliases: aliases.by_id(),
+                no_header,
+                fields: columns,
+                blocked_by,
+            };
+            output::print_todos(&todos, format, &options)?;
         }
-        Commands::Get { id } => {
-            let todo = client.get_todo(id).await?;
-            output::print_todo(&todo, &cli.format)?;
+        Commands::Get { id, qr } => {
+            let id = aliases.resolve(&id)?;
+            // Interactive lookups fail fast instead of hanging on a slow server
+            let opts = RequestOptions::new().timeout(Duration::from_secs(5));
+            let todo = client.get_todo(id, &opts).await?;
+            history::record_todo(&todo, Action::Viewed);
+            output::print_todo(&todo, format)?;
+            if qr {
+                output::print_qr(&config.todo_link(client.base_url(), todo.id))?;
+            }
         }
-        Commands::Create { title } => {
-            let todo = client.create_todo(&title).await?;
-            output::print_todo(&todo, &cli.format)?;
-            println!("✅ Todo created successfully!");
+        Commands::Create {
+            titles,
+            estimate,
+            report,
+        } if titles.len() > 1 => {
+            let limits = client
+                .limits(&RequestOptions::new().timeout(Duration::from_secs(2)))
+                .await
+                .unwrap_or_default();
+            let mut summary = bulk::Summary::new("created");
+            for title in titles {
+                if bulk::cancelled() {
+                    summary.skipped(title, None, bulk::CANCELLED_REASON);
+                    continue;
+                }
+                let input = TodoInput {
+                    title: Some(&title),
+                    ..Default::default()
+                };
+                if let Err(err) = input.validate(&limits) {
+                    summary.failed(title, None, err);
+                    continue;
+                }
+                match create(client, &title, estimate, &opts).await {
+                    Ok(todo) => summary.succeeded(format!("#{} {}", todo.id, todo.title), todo.id),
+                    Err(err) => summary.failed(title, None, err),
+                }
+            }
+            summary.finish(report.as_deref())?;
+        }
+        Commands::Create {
+            titles, estimate, ..
+        } => {
+            let title = &titles[0];
+            let input = TodoInput {
+                title: Some(title),
+                ..Default::default()
+            };
+            validate_input(client, &input).await?;
+            let todo = create(client, title, estimate, &opts).await?;
+            output::print_changed("created", &todo, format)?;
+            output::success("Todo created successfully!");
         }
-        Commands::Update { id, title, completed } => {
-            let todo = client.update_todo(id, title.as_deref(), completed).await?;
-            output::print_todo(&todo, &cli.format)?;
-            println!("✅ Todo updated successfully!");
+        Commands::Import {
+            file,
+            from,
+            dry_run,
+            report,
+        } => {
+            let from = from.unwrap_or_else(|| bulk::ImportFormat::detect(&file));
+            if dry_run {
+                bulk::preview(&file, from, format)?;
+            } else {
+                bulk::import(client, &file, from, report.as_deref()).await?;
+            }
+        }
+        Commands::Update {
+            id,
+            title,
+            completed,
+            estimate,
+            interactive,
+            yes,
+        } => {
+            let id = aliases.resolve(&id)?;
+            let current = client.get_todo(id, &opts).await?;
+            let changes = if interactive {
+                output::print_todo(&current, "text")?;
+                prompt_changes(&current)?
+            } else {
+                Changes {
+                    title: title.filter(|title| *title != current.title),
+                    completed: completed.filter(|completed| *completed != current.completed),
+                    ..Default::default()
+                }
+            };
+            let Changes {
+                title,
+                completed,
+                due,
+                tags,
+            } = changes;
+            let estimate = estimate.filter(|&minutes| Some(minutes) != current.estimate_minutes);
+            if title.is_none()
+                && completed.is_none()
+                && estimate.is_none()
+                && due.is_none()
+                && tags.is_none()
+            {
+                output::say!("Nothing to update.");
+                return output::print_result("unchanged", &[id], &current);
+            }
+            let input = TodoInput {
+                title: title.as_deref(),
+                due,
+                tags: tags.as_deref().unwrap_or_default(),
+            };
+            validate_input(client, &input).await?;
+
+            output::print_diff(
+                &current,
+                title.as_deref(),
+                completed,
+                estimate,
+                due,
+                tags.as_deref(),
+            );
+            if !confirm_changes(yes || config.confirm == ConfirmPolicy::Never)? {
+                output::say!("Cancelled.");
+                return output::print_result("cancelled", &[id], ());
+            }
+            let mut todo = current;
+            if title.is_some() || completed.is_some() {
+                todo = client.update_todo(id, title.as_deref(), completed, &opts).await?;
+            }
+            if let Some(minutes) = estimate {
+                todo = client.set_estimate(id, minutes, &opts).await?;
+            }
+            if let Some(due) = due {
+                todo = client.set_due(id, due, &opts).await?;
+            }
+            if let Some(ref tags) = tags {
+                todo = client.set_tags(id, tags, &opts).await?;
+            }
+            history::record_todo(&todo, Action::Updated);
+            output::print_changed("updated", &todo, format)?;
+            output::success("Todo updated successfully!");
+        }
+        Commands::Append { id, text, sep } => {
+            let id = aliases.resolve(&id)?;
+            let todo = retitle::amend(client, id, &text, &sep, retitle::End::End, &opts).await?;
+            history::record_todo(&todo, Action::Updated);
+            output::print_changed("updated", &todo, format)?;
+            output::success("Todo updated successfully!");
+        }
+        Commands::Prepend { id, text, sep } => {
+            let id = aliases.resolve(&id)?;
+            let todo = retitle::amend(client, id, &text, &sep, retitle::End::Start, &opts).await?;
+            history::record_todo(&todo, Action::Updated);
+            output::print_changed("updated", &todo, format)?;
+            output::success("Todo updated successfully!");
+        }
+        Commands::Edit {
+            filter,
+            editor,
+            yes,
+        } => {
+            if editor && !prompt::interactive() {
+                return Err(prompt::required("Can't open an editor in non-interactive mode"));
+            }
+            let todos = edit::matching(client, &filter, &opts).await?;
+            if !editor {
+                if format == "json" {
+                    let rows: Vec<_> = todos.iter().map(edit::Row::from).collect();
+                    return output::print_json("edit_document", &rows);
+                }
+                print!("{}", edit::to_document(&todos)?);
+                return Ok(());
+            }
+            if todos.is_empty() {
+                output::say!("No todos match.");
+                return output::print_result("unchanged", &[], ());
+            }
+
+            let changes = edit::edit_in_editor(&todos).await?;
+            let ids: Vec<_> = changes.iter().map(|change| change.id).collect();
+            if changes.is_empty() {
+                output::say!("Nothing to update.");
+                return output::print_result("unchanged", &[], ());
+            }
+            let limits = client
+                .limits(&RequestOptions::new().timeout(Duration::from_secs(2)))
+                .await
+                .unwrap_or_default();
+            for change in &changes {
+                let input = TodoInput {
+                    title: change.title.as_deref(),
+                    ..Default::default()
+                };
+                input
+                    .validate(&limits)
+                    .with_context(|| format!("Todo #{}", change.id))?;
+            }
+
+            for change in &changes {
+                if let Some(todo) = todos.iter().find(|todo| todo.id == change.id) {
+                    output::print_diff(
+                        todo,
+                        change.title.as_deref(),
+                        change.completed,
+                        None,
+                        None,
+                        None,
+                    );
+                }
+            }
+            if !confirm_changes(yes || config.confirm == ConfirmPolicy::Never)? {
+                output::say!("Cancelled.");
+                return output::print_result("cancelled", &ids, ());
+            }
+
+            let results = edit::apply(client, &changes, &opts).await;
+            let mut updated = Vec::new();
+            let mut failed = 0;
+            for (change, result) in changes.iter().zip(results) {
+                match result {
+                    Ok(todo) => {
+                        history::record_todo(&todo, Action::Updated);
+                        updated.push(todo);
+                    }
+                    Err(err) => {
+                        failed += 1;
+                        let err = anyhow::Error::from(err).context(format!("Todo #{}", change.id));
+                        output::print_error(&err);
+                    }
+                }
+            }
+            if failed > 0 {
+                anyhow::bail!("{} of {} updates failed", failed, changes.len());
+            }
+            output::success(&format!("Updated {} todos.", changes.len()));
+            output::print_result("updated", &ids, &updated)?;
         }
-        Commands::Delete { id, force } => {
+        Commands::Delete { ids, force, report } => {
+            let force = force || config.confirm == ConfirmPolicy::Never;
+            let ids = ids
+                .iter()
+                .map(|id| aliases.resolve(id))
+                .collect::<Result<Vec<_>>>()?;
+            if !force && !prompt::interactive() {
+                return Err(prompt::required("Not deleting without confirmation; pass --force"));
+            }
+            // Show what is about to go so the user confirms the right items
+            let mut titles = HashMap::new();
             if !force {
-                println!("Are you sure you want to delete todo #{}? [y/N]", id);
-                let mut input = String::new();
-                std::io::stdin().read_line(&mut input)?;
-                if !input.trim().eq_ignore_ascii_case("y") {
-                    println!("Cancelled.");
-                    return Ok(());
+                let todos = futures::future::try_join_all(
+                    ids.iter().map(|&id| client.get_todo(id, &opts)),
+                )
+                .await?;
+                let question = match todos.as_slice() {
+                    [todo] => {
+                        output::print_todo(todo, "text")?;
+                        format!("Are you sure you want to delete todo #{}?", todo.id)
+                    }
+                    todos => {
+                        let options = ListOptions {
+                            layout: Some(Layout::Wide),
+                            aliases: aliases.by_id(),
+                            ..Default::default()
+                        };
+                        output::print_todos(todos, "text", &options)?;
+                        format!("Are you sure you want to delete these {} todos?", todos.len())
+                    }
+                };
+                if !prompt::confirm(&question, false)? {
+                    output::say!("Cancelled.");
+                    return output::print_result("cancelled", &ids, ());
+                }
+                titles.extend(todos.into_iter().map(|todo| (todo.id, todo.title)));
+            }
+
+            if let [id] = ids[..] {
+                client.delete_todo(id, &opts).await?;
+                history::record_deleted(id, titles.remove(&id));
+                aliases.forget(id);
+                aliases.save();
+                search::forget(&[id]);
+                output::success(&format!("Todo #{} deleted successfully!", id));
+                return output::print_result("deleted", &[id], ());
+            }
+
+            let mut summary = bulk::Summary::new("deleted");
+            let mut deleted = Vec::new();
+            for id in ids {
+                if bulk::cancelled() {
+                    summary.skipped(format!("#{}", id), Some(id), bulk::CANCELLED_REASON);
+                    continue;
+                }
+                match client.delete_todo(id, &opts).await {
+                    Ok(()) => {
+                        history::record_deleted(id, titles.remove(&id));
+                        aliases.forget(id);
+                        deleted.push(id);
+                        summary.succeeded(format!("#{}", id), id);
+                    }
+                    Err(err) => summary.failed(format!("#{}", id), Some(id), err),
+                }
+            }
+            aliases.save();
+            search::forget(&deleted);
+            summary.finish(report.as_deref())?;
+        }
+        Commands::Purge {
+            completed: _,
+            older_than,
+            yes,
+        } => {
+            let yes = yes || config.confirm == ConfirmPolicy::Never;
+            purge::completed(client, older_than, yes, &mut aliases).await?;
+        }
+        Commands::Sync { report } => offline::sync(client, report.as_deref()).await?,
+        Commands::Resume { op_id: None, .. } => journal::list()?,
+        Commands::Resume {
+            op_id: Some(id),
+            report,
+        } => {
+            let (journal, operation) = Journal::open(&id)?;
+            match operation {
+                Operation::Import { file, sha256, from } => {
+                    bulk::resume_import(client, &file, &sha256, from, journal, report.as_deref())
+                        .await?;
+                }
+                Operation::Purge { ids } => {
+                    purge::resume(client, ids, &mut aliases, journal).await?;
+                }
+            }
+        }
+        Commands::Search {
+            query,
+            local,
+            limit,
+        } => {
+            let mut index = search::Index::open()?;
+            if !local {
+                let opts = RequestOptions::new().retries(2);
+                let todos = client.list_todos(None, &opts).await?;
+                index.update(&todos, true)?;
+            }
+            let mut todos = index.search(&query, limit)?;
+            if let Some(context) = context::Active::load() {
+                context.print_header();
+                context.retain(&mut todos);
+            }
+            aliases.assign(todos.iter().map(|todo| todo.id));
+            aliases.save();
+            let options = ListOptions {
+                aliases: aliases.by_id(),
+                ..Default::default()
+            };
+            output::print_todos(&todos, format, &options)?;
+        }
+        Commands::Done { ids, force, report } if ids.len() > 1 => {
+            let mut summary = bulk::Summary::new("completed");
+            for arg in &ids {
+                if bulk::cancelled() {
+                    summary.skipped(arg.to_string(), None, bulk::CANCELLED_REASON);
+                    continue;
+                }
+                let id = match aliases.resolve(arg) {
+                    Ok(id) => id,
+                    Err(err) => {
+                        summary.failed(arg.to_string(), None, err);
+                        continue;
+                    }
+                };
+                // In order, so a dependency listed first no longer blocks
+                if !force && let Err(err) = depends::check_done(client, id, &opts).await {
+                    summary.failed(format!("#{}", id), Some(id), err);
+                    continue;
+                }
+                match client.update_todo(id, None, Some(true), &opts).await {
+                    Ok(todo) => {
+                        history::record_todo(&todo, Action::Completed);
+                        summary.succeeded(format!("#{} {}", todo.id, todo.title), todo.id);
+                    }
+                    Err(err) => summary.failed(format!("#{}", id), Some(id), err),
                 }
             }
-            client.delete_todo(id).await?;
-            println!("✅ Todo #{} deleted successfully!", id);
+            summary.finish(report.as_deref())?;
         }
-        Commands::Done { id } => {
-            let todo = client.update_todo(id, None, Some(true)).await?;
-            output::print_todo(&todo, &cli.format)?;
-            println!("✅ Todo marked as completed!");
+        Commands::Done { ids, force, .. } => {
+            let id = aliases.resolve(&ids[0])?;
+            if !force {
+                depends::check_done(client, id, &opts).await?;
+            }
+            let todo = client.update_todo(id, None, Some(true), &opts).await?;
+            history::record_todo(&todo, Action::Completed);
+            output::print_changed("completed", &todo, format)?;
+            output::success("Todo marked as completed!");
         }
         Commands::Undone { id } => {
-            let todo = client.update_todo(id, None, Some(false)).await?;
-            output::print_todo(&todo, &cli.format)?;
-            println!("✅ Todo marked as incomplete!");
+            let id = aliases.resolve(&id)?;
+            let todo = client.update_todo(id, None, Some(false), &opts).await?;
+            history::record_todo(&todo, Action::Reopened);
+            output::print_changed("reopened", &todo, format)?;
+            output::success("Todo marked as incomplete!");
+        }
+        Commands::StartWork { id, force } => {
+            let id = aliases.resolve(&id)?;
+            if !force {
+                wip::check(client, &config, id).await?;
+            }
+            let todo = client
+                .set_status(id, api::TodoStatus::InProgress, None, &opts)
+                .await?;
+            history::record_todo(&todo, Action::Updated);
+            output::print_changed("started", &todo, format)?;
+            output::success("Todo marked as in progress!");
+        }
+        Commands::Block { id, reason } => {
+            let id = aliases.resolve(&id)?;
+            let todo = client
+                .set_status(id, api::TodoStatus::Blocked, reason.as_deref(), &opts)
+                .await?;
+            history::record_todo(&todo, Action::Updated);
+            output::print_changed("blocked", &todo, format)?;
+            output::success("Todo marked as blocked!");
+        }
+        Commands::Focus { id, pomodoro } => {
+            let id = aliases.resolve(&id)?;
+            focus::run(client, id, pomodoro, format).await?;
+        }
+        Commands::Depends { id, on, remove } => {
+            let id = aliases.resolve(&id)?;
+            let on = on
+                .iter()
+                .map(|arg| aliases.resolve(arg))
+                .collect::<Result<Vec<_>>>()?;
+            let todo = if remove {
+                depends::remove(client, id, &on).await?
+            } else {
+                depends::add(client, id, &on).await?
+            };
+            history::record_todo(&todo, Action::Updated);
+            output::print_changed("updated", &todo, format)?;
+            output::success(&if remove {
+                format!("#{} no longer depends on {}", id, depends::list(&on))
+            } else {
+                format!("#{} now depends on {}", id, depends::list(&on))
+            });
+        }
+        Commands::Link {
+            id,
+            to,
+            kind,
+            remove,
+        } => {
+            let id = aliases.resolve(&id)?;
+            let link = links::parse(&to, kind, &aliases)?;
+            let todo = if remove {
+                links::remove(client, id, &link).await?
+            } else {
+                links::add(client, id, link.clone()).await?
+            };
+            history::record_todo(&todo, Action::Updated);
+            output::print_changed("updated", &todo, format)?;
+            output::success(&if remove {
+                format!("#{} no longer links to {}", id, to)
+            } else {
+                format!("#{} now {}", id, links::describe(&link))
+            });
+        }
+        Commands::Open { id, link } => {
+            let id = aliases.resolve(&id)?;
+            let todo = client.get_todo(id, &opts).await?;
+            let url = match links::external(&todo, link)? {
+                Some(url) => url,
+                None => config.todo_link(client.base_url(), todo.id),
+            };
+            history::record_todo(&todo, Action::Viewed);
+            if open::that(&url).is_err() {
+                output::say!("Open this URL:");
+            }
+            // Printed either way, for headless sessions and blocked browsers
+            output::say!("  {}", url);
+            output::print_result("opened", &[todo.id], json!({ "url": url }))?;
+        }
+        Commands::Export {
+            kind,
+            output: path,
+            completed,
+        } => {
+            let mut todos = client.list_todos(completed, &RequestOptions::new().retries(2)).await?;
+            todos.retain(|t| completed.is_none_or(|c| t.completed == c));
+            let document = match kind {
+                ExportFormat::Markdown => output::render_markdown(&todos),
+                ExportFormat::Json => serde_json::to_string_pretty(&todos)? + "\n",
+                ExportFormat::Org => org::render(&todos),
+                ExportFormat::Ics => ics::render(&todos, &config, chrono::Utc::now()),
+            };
+            write_document(path.as_deref(), document.as_bytes())?;
+        }
+        Commands::Report {
+            format: kind,
+            output: path,
+        } => {
+            if matches!(kind, ReportFormat::Pdf)
+                && path.is_none()
+                && std::io::stdout().is_terminal()
+            {
+                anyhow::bail!("Refusing to print a PDF to the terminal; pass --output");
+            }
+            let todos = client.list_todos(None, &RequestOptions::new().retries(2)).await?;
+            let generated = chrono::Local::now();
+            let document = match kind {
+                ReportFormat::Html => {
+                    report::render_html(&todos, client.base_url(), generated).into_bytes()
+                }
+                ReportFormat::Pdf => report::render_pdf(&todos, client.base_url(), generated)?,
+            };
+            write_document(path.as_deref(), &document)?;
+        }
+        Commands::Today {
+            tomorrow,
+            date,
+            week,
+        } => {
+            let today = chrono::Local::now().date_naive();
+            let date = match (tomorrow, date) {
+                (true, _) => today + chrono::Duration::days(1),
+                (_, Some(date)) => date,
+                _ => today,
+            };
+            let mut todos = client.list_todos(None, &RequestOptions::new().retries(2)).await?;
+            if let Some(context) = context::Active::load() {
+                context.print_header();
+                context.retain(&mut todos);
+            }
+            let agenda = if week {
+                due::Agenda::for_week(todos, date, locale::first_weekday())
+            } else {
+                due::Agenda::for_day(todos, date)
+            };
+            let options = ListOptions {
+                aliases: aliases.by_id(),
+                ..Default::default()
+            };
+            output::print_agenda(&agenda, format, &options)?;
+        }
+        Commands::Review => review::run(client).await?,
+        Commands::Standup { format: markup } => {
+            let todos = client.list_todos(None, &RequestOptions::new().retries(2)).await?;
+            let standup = standup::Standup::compile(todos, chrono::Local::now().date_naive());
+            if output::json() {
+                output::print_json("standup", &standup)?;
+            } else {
+                print!("{}", standup.render(markup));
+            }
         }
+        Commands::Triage => {
+            let confirm = config.confirm != ConfirmPolicy::Never;
+            triage::run(client, &mut aliases, confirm).await?;
+        }
+        Commands::Completions { shell } => completions::print(shell)?,
+        Commands::Man { dir } => man::generate(Cli::command(), dir.as_deref())?,
+        // `execute` starts the shell itself, and the shell won't nest
+        Commands::Shell => unreachable!("todo shell is started by execute"),
+        #[cfg(feature = "tui")]
+        Commands::Tui { refresh } => {
+            let confirm = config.confirm != ConfirmPolicy::Never;
+            tui::run(client, &mut aliases, confirm, Duration::from_secs(refresh)).await?;
+        }
+        Commands::Stats { by } => {
+            let todos = client.list_todos(None, &RequestOptions::new().retries(2)).await?;
+            let rows = stats::compute(&todos, by, chrono::Utc::now());
+            stats::print(&rows, format)?;
+            if let Some((start, week)) = goals::this_week(&todos, &config) {
+                goals::record(start, week);
+                if format != "json" {
+                    println!();
+                    goals::print_progress(&week);
+                }
+            }
+        }
+        Commands::Share { id, qr } => {
+            let id = aliases.resolve(&id)?;
+            // Fetch first so a typo'd ID fails here, not on the phone
+            let todo = client.get_todo(id, &opts).await?;
+            let link = config.todo_link(client.base_url(), todo.id);
+            if qr {
+                output::print_qr(&link)?;
+            }
+            if output::json() {
+                output::print_result("shared", &[todo.id], json!({ "url": link }))?;
+            } else {
+                println!("{}", link);
+            }
+        }
+        Commands::Limits => {
+            let quota = client.quota(&RequestOptions::new().timeout(Duration::from_secs(5))).await?;
+            limits::print(&quota, format)?;
+        }
+        Commands::Recent { limit } => {
+            let entries = history::load()?;
+            history::print(&history::recent(&entries, limit), format)?;
+        }
+        Commands::History {
+            commands,
+            limit,
+            clear,
+        } => {
+            if clear {
+                history::clear()?;
+                output::success("History cleared.");
+                output::print_result("history_cleared", &[], ())?;
+            } else {
+                let entries = history::load()?;
+                history::print(&history::latest(&entries, commands, limit), format)?;
+            }
+        }
+        Commands::Audit {
+            command: AuditCommands::Show { since, limit },
+        } => audit::show(since, limit, format)?,
+        Commands::Tag { command } => match command {
+            TagCommands::Add { tag, targets } => {
+                let report = targets.report.as_deref();
+                tags::add(client, &config, &aliases, &tag, targets.targets(), report).await?;
+            }
+            TagCommands::Remove { tag, targets } => {
+                let report = targets.report.as_deref();
+                tags::remove(client, &config, &aliases, &tag, targets.targets(), report).await?;
+            }
+            TagCommands::Rename { old, new, report } => {
+                tags::rename(client, &old, &new, report.as_deref()).await?;
+            }
+        },
+        Commands::Filter { command } => match command {
+            FilterCommands::Add { name, args } => filters::add(&name, &args)?,
+            FilterCommands::List => filters::list(&config)?,
+            FilterCommands::Remove { name } => filters::remove(&name)?,
+        },
+        Commands::Place { command } => match command {
+            PlaceCommands::Add { name, position } => places::add(&name, &position)?,
+            PlaceCommands::List => places::list(&config)?,
+            PlaceCommands::Remove { name } => places::remove(&name)?,
+        },
+        Commands::Goals { command, weeks } => match command {
+            Some(GoalCommands::Set { per_week }) => goals::set(per_week as usize)?,
+            Some(GoalCommands::Clear) => goals::clear()?,
+            Some(GoalCommands::Prompt) => {
+                if let Some(segment) = goals::segment(&config) {
+                    println!("{}", segment);
+                }
+            }
+            None => goals::show(client, &config, weeks).await?,
+        },
+        Commands::Context { command } => match command {
+            Some(ContextCommands::Set { name }) => context::set(&name)?,
+            Some(ContextCommands::Clear) => context::clear()?,
+            None => context::show()?,
+        },
         Commands::Config { command } => {
             match command {
+                Some(ConfigCommands::Show) | None if output::json() => {
+                    let values = config::KEYS
+                        .iter()
+                        .map(|key| Ok((key.name, config.get(key.name)?)))
+                        .collect::<Result<BTreeMap<_, _>>>()?;
+                    output::print_json("config", &values)?;
+                }
                 Some(ConfigCommands::Show) | None => {
                     config.print();
                 }
-                Some(ConfigCommands::SetUrl { url }) => {
-                    let mut config = config;
-                    config.set_url(&url)?;
-                    println!("✅ API URL set to: {}", url);
+                Some(ConfigCommands::Get { key }) => match config.get(&key)? {
+                    value if output::json() => {
+                        output::print_json("config_value", &json!({ "key": key, "value": value }))?;
+                    }
+                    Some(value) => println!("{}", value),
+                    None => eprintln!("{}", "(not set)".dimmed()),
+                },
+                Some(ConfigCommands::Set { key, value }) => {
+                    Config::update(|config| config.set(&key, &value))?;
+                    output::success(&format!("Set {} to {}", key, value));
+                    output::print_result("config_set", &[], json!({ "key": key, "value": value }))?;
+                }
+                Some(ConfigCommands::Unset { key }) => {
+                    Config::update(|config| config.unset(&key))?;
+                    output::success(&format!("Unset {}", key));
+                    output::print_result("config_unset", &[], json!({ "key": key }))?;
+                }
+                Some(ConfigCommands::Keys) if output::json() => {
+                    let keys: Vec<_> = config::KEYS
+                        .iter()
+                        .map(|key| json!({ "name": key.name, "help": key.help }))
+                        .collect();
+                    output::print_json("config_keys", &keys)?;
+                }
+                Some(ConfigCommands::Keys) => {
+                    for key in config::KEYS {
+                        println!("{:<20} {}", key.name, key.help.dimmed());
+                    }
+                }
+                Some(ConfigCommands::Export {
+                    output: path,
+                    with_secrets,
+                }) => {
+                    bundle::export(&config, client.base_url(), path.as_deref(), with_secrets)?;
+                }
+                Some(ConfigCommands::Import { file, no_secrets }) => {
+                    bundle::import(&file, no_secrets)?;
                 }
             }
         }
@@ -208,13 +2066,188 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
-fn rpassword_prompt(prompt: &str) -> String {
-    print!("{}", prompt);
-    use std::io::Write;
-    std::io::stdout().flush().unwrap();
+/// What `todo update` changes; `None` leaves a field as it is.
+#[derive(Default)]
+struct Changes {
+    title: Option<String>,
+    completed: Option<bool>,
+    due: Option<chrono::DateTime<chrono::Utc>>,
+    tags: Option<Vec<String>>,
+}
+
+/// Walks through the editable fields of `todo`, each with its current value
+/// as the default, returning only the values the user changed.
+fn prompt_changes(todo: &api::Todo) -> Result<Changes> {
+    let title = prompt::input("Title", Some(&todo.title))?;
+    let completed = prompt::confirm("Completed?", todo.completed)?;
+
+    let current_due = todo
+        .due()
+        .map(|due| due.with_timezone(&chrono::Local).format("%Y-%m-%d").to_string());
+    let due = loop {
+        let answer = prompt::input(
+            "Due (today, tomorrow, YYYY-MM-DD or a span like 3d)",
+            current_due.as_deref(),
+        )?;
+        if answer.is_empty() || Some(&answer) == current_due.as_ref() {
+            break None;
+        }
+        match triage::parse_due(&answer) {
+            Ok(due) => break Some(due),
+            Err(message) => eprintln!("{}", message.red()),
+        }
+    };
+
+    let current_tags = todo.tags.join(", ");
+    let answer = prompt::input(
+        "Tags, comma-separated",
+        Some(current_tags.as_str()).filter(|tags| !tags.is_empty()),
+    )?;
+    let tags: Vec<_> = answer
+        .split(',')
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty())
+        .map(str::to_string)
+        .collect();
+
+    Ok(Changes {
+        title: Some(title).filter(|title| *title != todo.title),
+        completed: Some(completed).filter(|completed| *completed != todo.completed),
+        due,
+        tags: Some(tags).filter(|tags| *tags != todo.tags),
+    })
+}
+
+/// Parses a `--header` value written like curl's, `Name: value`.
+fn parse_header(s: &str) -> Result<(String, String), String> {
+    let (name, value) = s
+        .split_once(':')
+        .ok_or_else(|| format!("'{}' is not a header like 'X-Tenant: acme'", s))?;
+    Ok((name.trim().to_string(), value.trim().to_string()))
+}
+
+/// Writes a generated document to `path`, or to stdout without one.
+fn write_document(path: Option<&Path>, document: &[u8]) -> Result<()> {
+    match path {
+        Some(path) => {
+            std::fs::write(path, document)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            output::success(&format!("Wrote {}", path.display()));
+            output::print_result("written", &[], json!({ "path": path }))?;
+        }
+        None => std::io::stdout().write_all(document)?,
+    }
+    Ok(())
+}
+
+/// Asks before applying the changes just printed. Without a terminal to ask
+/// on, `--yes` is required so scripts can't overwrite todos by accident.
+fn confirm_changes(yes: bool) -> Result<bool> {
+    if yes {
+        return Ok(true);
+    }
+    if !prompt::interactive() {
+        return Err(prompt::required("Not applying changes without confirmation; pass --yes"));
+    }
+    prompt::confirm("Apply these changes?", true)
+}
+
+/// Creates a todo and records it, then sets its estimate if one was given.
+async fn create<A: TodoApi>(
+    client: &A,
+    title: &str,
+    estimate: Option<i64>,
+    opts: &RequestOptions,
+) -> Result<api::Todo> {
+    let todo = client.create_todo(title, opts).await?;
+    history::record_todo(&todo, Action::Created);
+    match estimate {
+        Some(minutes) => client
+            .set_estimate(todo.id, minutes, opts)
+            .await
+            .with_context(|| format!("Created #{} without its estimate", todo.id)),
+        None => Ok(todo),
+    }
+}
+
+/// Checks user input before it is sent, using the server's advertised limits.
+async fn validate_input<A: TodoApi>(client: &A, input: &TodoInput<'_>) -> Result<()> {
+    // Limits are advisory, so a slow or unreachable /config falls back to
+    // the defaults rather than blocking the command
+    let opts = RequestOptions::new().timeout(Duration::from_secs(2));
+    let limits = client.limits(&opts).await.unwrap_or_default();
+    input.validate(&limits)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fake::{self, FakeApi};
+
+    async fn todo(client: &FakeApi, args: &[&str]) -> Result<()> {
+        fake::isolate();
+        let cli = Cli::try_parse_from(std::iter::once("todo").chain(args.iter().copied()))?;
+        run(cli.command, "text", Config::default(), client).await
+    }
+
+    #[tokio::test]
+    async fn list_leaves_the_todos_as_they_are() {
+        let client = FakeApi::with_todos(&["write tests", "ship"]);
+        todo(&client, &["list"]).await.unwrap();
+        let titles: Vec<_> = client.todos().into_iter().map(|todo| todo.title).collect();
+        assert_eq!(titles, ["write tests", "ship"]);
+    }
 
-    // Simple password input (no echo suppression on Windows without extra deps)
-    let mut password = String::new();
-    std::io::stdin().read_line(&mut password).unwrap();
-    password.trim().to_string()
+    #[tokio::test]
+    async fn done_completes_each_todo() {
+        let client = FakeApi::with_todos(&["one", "two", "three"]);
+        todo(&client, &["done", "1"]).await.unwrap();
+        todo(&client, &["done", "2", "3"]).await.unwrap();
+        assert!(client.todos().iter().all(|todo| todo.completed));
+    }
+
+    #[tokio::test]
+    async fn done_waits_for_open_dependencies() {
+        let client = FakeApi::with_todos(&["deploy", "review"]);
+        client.edit(1, |todo| todo.depends_on = vec![2]);
+        let err = todo(&client, &["done", "1"]).await.unwrap_err();
+        assert!(err.to_string().contains("blocked by #2"), "{}", err);
+        assert!(!client.todo(1).unwrap().completed);
+
+        todo(&client, &["done", "2", "1"]).await.unwrap();
+        assert!(client.todo(1).unwrap().completed);
+    }
+
+    #[tokio::test]
+    async fn delete_removes_only_the_given_todos() {
+        let client = FakeApi::with_todos(&["one", "two", "three"]);
+        todo(&client, &["delete", "--force", "1", "3"]).await.unwrap();
+        let ids: Vec<_> = client.todos().into_iter().map(|todo| todo.id).collect();
+        assert_eq!(ids, [2]);
+    }
+
+    #[tokio::test]
+    async fn delete_of_a_missing_todo_fails() {
+        let client = FakeApi::with_todos(&["one"]);
+        let err = todo(&client, &["delete", "--force", "7"]).await.unwrap_err();
+        assert!(err.to_string().contains("#7 not found"), "{}", err);
+        assert_eq!(client.todos().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn export_leaves_out_todos_the_server_did_not_filter() {
+        let client = FakeApi::with_todos(&["open", "done"]);
+        client.edit(2, |todo| todo.completed = true);
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("todos.json");
+        let path = path.to_str().unwrap();
+
+        let args = ["export", "--as", "json", "-o", path, "--completed", "false"];
+        todo(&client, &args).await.unwrap();
+        let json = std::fs::read_to_string(path).unwrap();
+        let todos: Vec<api::Todo> = serde_json::from_str(&json).unwrap();
+        let titles: Vec<_> = todos.into_iter().map(|todo| todo.title).collect();
+        assert_eq!(titles, ["open"]);
+    }
 }
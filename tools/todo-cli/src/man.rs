@@ -0,0 +1,45 @@
+//! `todo man`: roff man pages generated from the clap definitions, for
+//! packagers. One page for `todo` itself and one per subcommand, named the
+//! git way: `todo-list.1`, `todo-tag-add.1`.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use clap::Command;
+use clap_mangen::Man;
+
+use crate::output;
+
+/// Writes every page into `dir`, or just the top-level page to stdout.
+pub fn generate(command: Command, dir: Option<&Path>) -> Result<()> {
+    let Some(dir) = dir else {
+        return Man::new(command)
+            .render(&mut io::stdout())
+            .context("Failed to write the man page");
+    };
+
+    fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    let mut written = 0;
+    write_pages(command, dir, &mut written)?;
+    output::success(&format!("Wrote {} man pages to {}", written, dir.display()));
+    Ok(())
+}
+
+/// Writes the page for `command`, then for each of its subcommands with
+/// the parent's name as a prefix.
+fn write_pages(command: Command, dir: &Path, written: &mut usize) -> Result<()> {
+    let name = command.get_name().to_string();
+    let path = dir.join(format!("{}.1", name));
+    let mut page = Vec::new();
+    Man::new(command.clone()).render(&mut page)?;
+    fs::write(&path, page).with_context(|| format!("Failed to write {}", path.display()))?;
+    *written += 1;
+
+    for sub in command.get_subcommands().filter(|sub| !sub.is_hide_set()) {
+        let sub = sub.clone().name(format!("{}-{}", name, sub.get_name()));
+        write_pages(sub, dir, written)?;
+    }
+    Ok(())
+}
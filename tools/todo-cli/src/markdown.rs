@@ -0,0 +1,108 @@
+//! Markdown checklists for `todo import --from markdown`. Each `- [ ]` or
+//! `- [x]` item becomes a todo, items indented under another become its
+//! subtasks, and the headings an item sits under become its tags.
+
+use crate::bulk::Imported;
+
+/// Reads the checklist items of `content` in document order. Other list
+/// items, paragraphs and fenced code blocks are left out.
+pub fn parse(content: &str) -> Vec<Imported> {
+    let mut items: Vec<Imported> = Vec::new();
+    // Headings by level, for the tags of the items below them
+    let mut headings: Vec<(usize, String)> = Vec::new();
+    // Indent and index of the items the next one may be nested in
+    let mut open: Vec<(usize, usize)> = Vec::new();
+    let mut in_code = false;
+
+    for line in content.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code = !in_code;
+            continue;
+        }
+        if in_code {
+            continue;
+        }
+
+        if let Some((level, heading)) = heading(trimmed) {
+            headings.retain(|&(other, _)| other < level);
+            let tag = slug(heading);
+            if !tag.is_empty() {
+                headings.push((level, tag));
+            }
+            open.clear();
+            continue;
+        }
+
+        let Some((completed, title)) = checklist_item(trimmed) else {
+            continue;
+        };
+        let indent = indent(line);
+        while open.last().is_some_and(|&(other, _)| other >= indent) {
+            open.pop();
+        }
+        open.push((indent, items.len()));
+        items.push(Imported {
+            title: title.to_string(),
+            completed,
+            tags: headings.iter().map(|(_, tag)| tag.clone()).collect(),
+            due: None,
+            parent: open.iter().rev().nth(1).map(|&(_, parent)| parent),
+        });
+    }
+    items
+}
+
+/// `## Title` as (2, "Title").
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    if !(1..=6).contains(&level) {
+        return None;
+    }
+    let rest = &line[level..];
+    if !rest.is_empty() && !rest.starts_with(' ') {
+        return None;
+    }
+    Some((level, rest.trim().trim_end_matches('#').trim()))
+}
+
+/// `- [x] Title` as (true, "Title"), with any list marker (`-`, `*`, `+`,
+/// `1.`, `1)`).
+fn checklist_item(line: &str) -> Option<(bool, &str)> {
+    let rest = match line.strip_prefix(['-', '*', '+']) {
+        Some(rest) => rest,
+        None => {
+            let digits = line.chars().take_while(char::is_ascii_digit).count();
+            if digits == 0 {
+                return None;
+            }
+            line[digits..].strip_prefix(['.', ')'])?
+        }
+    };
+    let rest = rest.strip_prefix(' ')?.trim_start();
+    let completed = match rest.get(..3)? {
+        "[ ]" => false,
+        "[x]" | "[X]" => true,
+        _ => return None,
+    };
+    let title = rest[3..].trim();
+    (!title.is_empty()).then_some((completed, title))
+}
+
+/// Leading whitespace in columns, a tab counting as four.
+fn indent(line: &str) -> usize {
+    line.chars()
+        .take_while(|c| c.is_whitespace())
+        .map(|c| if c == '\t' { 4 } else { 1 })
+        .sum()
+}
+
+/// A heading as a tag: `Q3 Planning!` becomes `q3-planning`.
+pub fn slug(heading: &str) -> String {
+    heading
+        .split(|c: char| !c.is_alphanumeric() && c != '_' && c != '-')
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join("-")
+}
@@ -0,0 +1,1093 @@
+//! Offline mode. Todos are cached in SQLite in the state directory as they
+//! are listed, fetched and changed. When the server can't be reached, or
+//! with `--offline`, reads come from the cache and creating, updating,
+//! completing and deleting todos is queued there instead; `todo sync`
+//! sends the queue in order and refreshes the cache. Todos created offline
+//! have negative IDs until they are synced.
+
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::{Context, Result, anyhow};
+use chrono::{DateTime, Utc};
+use http::Extensions;
+use reqwest::{Method, Request, Response, StatusCode};
+use reqwest_middleware::{Middleware, Next};
+use rusqlite::{Connection, OptionalExtension, params};
+use serde::{Deserialize, Serialize};
+
+use crate::aliases::Aliases;
+use crate::api::{
+    AuthResponse, Invitation, Link, Member, Page, Quota, RequestOptions, Role, Todo, TodoApi,
+    TodoStatus, User, Workspace,
+};
+use crate::bulk::{self, Summary};
+use crate::error::{self, ApiError, ClientError};
+use crate::history::{self, Action};
+use crate::style::Colorize;
+use crate::validation::Limits;
+use crate::{config, output, search};
+
+/// Refused by [`Unreachable`]; the request was never sent.
+#[derive(Debug, thiserror::Error)]
+#[error("Offline mode: not sending {method} {path}")]
+pub struct OfflineRequest {
+    pub method: Method,
+    pub path: String,
+}
+
+/// A change the queue can't hold, made while the server is out of reach.
+#[derive(Debug, thiserror::Error)]
+#[error(
+    "Changing {0} isn't available offline; only creating, renaming, completing and deleting todos is queued"
+)]
+pub struct NotQueued(pub &'static str);
+
+/// Fails every request as if the server were out of reach, for `--offline`.
+pub struct Unreachable;
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl Middleware for Unreachable {
+    async fn handle(
+        &self,
+        req: Request,
+        _extensions: &mut Extensions,
+        _next: Next<'_>,
+    ) -> reqwest_middleware::Result<Response> {
+        Err(reqwest_middleware::Error::Middleware(
+            OfflineRequest {
+                method: req.method().clone(),
+                path: req.url().path().to_string(),
+            }
+            .into(),
+        ))
+    }
+}
+
+/// Whether `err` means the server couldn't be reached, as opposed to a
+/// request it refused or one that middleware stopped on purpose.
+fn unreachable(err: &ApiError) -> bool {
+    match err {
+        ApiError::Network(reqwest_middleware::Error::Reqwest(err)) => {
+            err.is_connect() || err.is_timeout()
+        }
+        ApiError::Network(reqwest_middleware::Error::Middleware(err)) => err.is::<OfflineRequest>(),
+        _ => false,
+    }
+}
+
+/// A change made offline, waiting for `todo sync`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+enum Change {
+    Create {
+        /// The local (negative) ID it has until synced
+        id: i64,
+        title: String,
+        /// Sent as the idempotency key, so a create that reached the
+        /// server before the connection dropped isn't made twice
+        key: String,
+        at: DateTime<Utc>,
+    },
+    Update {
+        id: i64,
+        title: Option<String>,
+        completed: Option<bool>,
+        /// The todo as cached when it was changed, to tell which fields
+        /// were changed on the server since
+        base: Box<Todo>,
+        at: DateTime<Utc>,
+    },
+    Delete {
+        id: i64,
+        title: String,
+    },
+}
+
+impl Change {
+    fn id(&self) -> i64 {
+        match *self {
+            Change::Create { id, .. } | Change::Update { id, .. } | Change::Delete { id, .. } => id,
+        }
+    }
+
+    /// How the change shows in the sync summary.
+    fn item(&self, id: i64) -> String {
+        match self {
+            Change::Create { title, .. } => format!("create \"{}\"", title),
+            Change::Update {
+                title: Some(title), ..
+            } => format!("#{} rename to \"{}\"", id, title),
+            Change::Update {
+                completed: Some(true),
+                base,
+                ..
+            } => format!("#{} {}: done", id, base.title),
+            Change::Update { base, .. } => format!("#{} {}: reopen", id, base.title),
+            Change::Delete { title, .. } => format!("#{} {}: delete", id, title),
+        }
+    }
+}
+
+struct Store {
+    conn: Connection,
+    /// The server and workspace the cache is for
+    scope: String,
+}
+
+impl Store {
+    fn open<A: TodoApi>(client: &A) -> Result<Self> {
+        let path = config::state_dir()?.join("offline.db");
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).context("Failed to create state directory")?;
+        }
+        let conn = Connection::open(&path)
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+        // Another `todo` may be writing; wait for it rather than fail
+        conn.busy_timeout(Duration::from_secs(5))
+            .context("Failed to configure offline cache")?;
+        Self::new(conn, client)
+    }
+
+    /// The cache in `conn`, for `client`'s server and workspace.
+    fn new<A: TodoApi>(conn: Connection, client: &A) -> Result<Self> {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS todos (
+                scope TEXT NOT NULL,
+                id INTEGER NOT NULL,
+                todo TEXT NOT NULL,
+                PRIMARY KEY (scope, id)
+            );
+            CREATE TABLE IF NOT EXISTS queue (
+                seq INTEGER PRIMARY KEY AUTOINCREMENT,
+                scope TEXT NOT NULL,
+                change TEXT NOT NULL
+            );",
+        )
+        .context("Failed to create offline cache")?;
+
+        let scope = match client.workspace() {
+            Some(workspace) => format!("{} workspace {}", client.base_url(), workspace),
+            None => client.base_url().to_string(),
+        };
+        Ok(Self { conn, scope })
+    }
+
+    /// Cached todos, synced ones by ID and then those created offline.
+    fn todos(&self, completed: Option<bool>) -> Result<Vec<Todo>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT todo FROM todos WHERE scope = ?1 ORDER BY id < 0, abs(id)")?;
+        let rows = stmt
+            .query_map([&self.scope], |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        let mut todos = Vec::with_capacity(rows.len());
+        for row in rows {
+            let todo: Todo =
+                serde_json::from_str(&row).context("Corrupt entry in offline cache")?;
+            if completed.is_none_or(|completed| todo.completed == completed) {
+                todos.push(todo);
+            }
+        }
+        Ok(todos)
+    }
+
+    fn todo(&self, id: i64) -> Result<Option<Todo>> {
+        let row: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT todo FROM todos WHERE scope = ?1 AND id = ?2",
+                params![self.scope, id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        row.map(|row| serde_json::from_str(&row).context("Corrupt entry in offline cache"))
+            .transpose()
+    }
+
+    /// Caches `todos`. With `complete`, they are every todo the server has,
+    /// so the rest of the cache is replaced, and changes still queued are
+    /// applied on top.
+    fn save(&self, todos: &[Todo], complete: bool) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        if complete {
+            tx.execute("DELETE FROM todos WHERE scope = ?1", [&self.scope])?;
+        }
+        {
+            let mut insert =
+                tx.prepare("INSERT OR REPLACE INTO todos (scope, id, todo) VALUES (?1, ?2, ?3)")?;
+            for todo in todos {
+                insert.execute(params![self.scope, todo.id, serde_json::to_string(todo)?])?;
+            }
+        }
+        tx.commit().context("Failed to update offline cache")?;
+        if complete {
+            for (_, change) in self.queue()? {
+                self.apply(&change)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn remove(&self, id: i64) -> Result<()> {
+        self.conn.execute(
+            "DELETE FROM todos WHERE scope = ?1 AND id = ?2",
+            params![self.scope, id],
+        )?;
+        Ok(())
+    }
+
+    /// Queued changes, oldest first, with their place in the queue.
+    fn queue(&self) -> Result<Vec<(i64, Change)>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT seq, change FROM queue WHERE scope = ?1 ORDER BY seq")?;
+        let rows = stmt
+            .query_map([&self.scope], |row| {
+                Ok((row.get(0)?, row.get::<_, String>(1)?))
+            })?
+            .collect::<rusqlite::Result<Vec<(i64, String)>>>()?;
+        rows.into_iter()
+            .map(|(seq, change)| {
+                let change = serde_json::from_str(&change).context("Corrupt offline change")?;
+                Ok((seq, change))
+            })
+            .collect()
+    }
+
+    /// Queues `change` and applies it to the cache, returning the todo as
+    /// it now stands.
+    fn push(&self, change: Change) -> Result<Option<Todo>> {
+        self.conn.execute(
+            "INSERT INTO queue (scope, change) VALUES (?1, ?2)",
+            params![self.scope, serde_json::to_string(&change)?],
+        )?;
+        self.apply(&change)
+    }
+
+    fn dequeue(&self, seq: i64) -> Result<()> {
+        self.conn
+            .execute("DELETE FROM queue WHERE seq = ?1", [seq])?;
+        Ok(())
+    }
+
+    /// Drops the queued changes to a todo created offline, which is then
+    /// as if it never was.
+    fn discard(&self, id: i64) -> Result<()> {
+        for (seq, change) in self.queue()? {
+            if change.id() == id {
+                self.dequeue(seq)?;
+            }
+        }
+        self.remove(id)
+    }
+
+    /// Moves a todo created offline to the ID the server gave it, in the
+    /// cache and in the changes still queued for it, so they reach the
+    /// right todo even if this sync stops before them.
+    fn renumber(&self, local: i64, synced: i64) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        for (seq, mut change) in self.queue()? {
+            if change.id() != local {
+                continue;
+            }
+            match change {
+                Change::Create { ref mut id, .. }
+                | Change::Update { ref mut id, .. }
+                | Change::Delete { ref mut id, .. } => *id = synced,
+            }
+            tx.execute(
+                "UPDATE queue SET change = ?1 WHERE seq = ?2",
+                params![serde_json::to_string(&change)?, seq],
+            )?;
+        }
+        if let Some(mut todo) = self.todo(local)? {
+            todo.id = synced;
+            tx.execute(
+                "UPDATE OR REPLACE todos SET id = ?1, todo = ?2 WHERE scope = ?3 AND id = ?4",
+                params![synced, serde_json::to_string(&todo)?, self.scope, local],
+            )?;
+        }
+        tx.commit().context("Failed to update offline cache")
+    }
+
+    fn apply(&self, change: &Change) -> Result<Option<Todo>> {
+        let todo = match change {
+            Change::Create { id, title, at, .. } => Todo {
+                id: *id,
+                user_id: 0,
+                title: title.clone(),
+                completed: false,
+                created_at: at.to_rfc3339(),
+                updated_at: at.to_rfc3339(),
+                due_at: None,
+                tags: Vec::new(),
+                status: None,
+                blocked_reason: None,
+                depends_on: Vec::new(),
+                estimate_minutes: None,
+                links: Vec::new(),
+            },
+            Change::Update {
+                id,
+                title,
+                completed,
+                at,
+                ..
+            } => {
+                let Some(mut todo) = self.todo(*id)? else {
+                    return Ok(None);
+                };
+                if let Some(title) = title {
+                    todo.title = title.clone();
+                }
+                if let Some(completed) = *completed {
+                    todo.completed = completed;
+                    if todo.status.is_some() {
+                        todo.status = Some(if completed {
+                            TodoStatus::Done
+                        } else {
+                            TodoStatus::Todo
+                        });
+                    }
+                }
+                todo.updated_at = at.to_rfc3339();
+                todo
+            }
+            Change::Delete { id, .. } => {
+                self.remove(*id)?;
+                return Ok(None);
+            }
+        };
+        self.save(std::slice::from_ref(&todo), false)?;
+        Ok(Some(todo))
+    }
+
+    /// The ID for the next todo created offline: one below the lowest yet.
+    fn next_local_id(&self) -> Result<i64> {
+        let lowest: Option<i64> = self.conn.query_row(
+            "SELECT min(id) FROM todos WHERE scope = ?1",
+            [&self.scope],
+            |row| row.get(0),
+        )?;
+        Ok(lowest.unwrap_or(0).min(0) - 1)
+    }
+}
+
+/// A client that keeps the offline cache up to date and falls back on it
+/// when the server is out of reach. Without a cache (for `sync` and `auth`
+/// commands, or when it can't be opened) every call goes to the server.
+pub struct Cached<'a, A> {
+    inner: &'a A,
+    store: Option<Store>,
+    noticed: Cell<bool>,
+}
+
+impl<'a, A: TodoApi> Cached<'a, A> {
+    pub fn new(inner: &'a A, enabled: bool) -> Self {
+        let store = enabled
+            .then(|| Store::open(inner))
+            .transpose()
+            .unwrap_or_else(|err| {
+                tracing::warn!("Offline cache unavailable: {:#}", err);
+                None
+            });
+        Self {
+            inner,
+            store,
+            noticed: Cell::new(false),
+        }
+    }
+
+    /// Caches what a successful call returned; the cache is a convenience,
+    /// so failures are logged, not returned.
+    fn remember(&self, todos: &[Todo], complete: bool) {
+        if let Some(ref store) = self.store
+            && let Err(err) = store.save(todos, complete)
+        {
+            tracing::warn!("Failed to update offline cache: {:#}", err);
+        }
+    }
+
+    /// Answers from the cache when `err` means the server is out of reach.
+    /// `err` is returned as it was for other errors, when there is no
+    /// cache, or when the cache has no answer.
+    fn offline<T>(
+        &self,
+        err: ApiError,
+        fallback: impl FnOnce(&Store) -> Result<Option<T>>,
+    ) -> error::Result<T> {
+        let Some(ref store) = self.store else {
+            return Err(err);
+        };
+        if !unreachable(&err) {
+            return Err(err);
+        }
+        match fallback(store) {
+            Ok(Some(value)) => {
+                self.notice(store);
+                Ok(value)
+            }
+            Ok(None) => Err(err),
+            Err(cache_err) => {
+                tracing::warn!("Offline cache unavailable: {:#}", cache_err);
+                Err(err)
+            }
+        }
+    }
+
+    /// Caches the todo a change the queue can't hold returned. Out of
+    /// reach, the change fails as [`NotQueued`] rather than with the
+    /// network error.
+    fn online(&self, what: &'static str, result: error::Result<Todo>) -> error::Result<Todo> {
+        match result {
+            Ok(todo) => {
+                self.remember(std::slice::from_ref(&todo), false);
+                Ok(todo)
+            }
+            Err(err) if unreachable(&err) => Err(ApiError::Network(
+                reqwest_middleware::Error::Middleware(NotQueued(what).into()),
+            )),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Says once per command that it's working from the cache.
+    fn notice(&self, store: &Store) {
+        if self.noticed.replace(true) {
+            return;
+        }
+        let queued = store.queue().map(|queue| queue.len()).unwrap_or_default();
+        eprintln!(
+            "{}",
+            format!(
+                "Offline: using the local cache; {} change(s) queued for `todo sync`.",
+                queued
+            )
+            .dimmed()
+        );
+    }
+
+    /// Queues an update to a todo created offline, which the server
+    /// doesn't know yet.
+    fn update_local(
+        &self,
+        id: i64,
+        title: Option<&str>,
+        completed: Option<bool>,
+    ) -> error::Result<Todo> {
+        let result = self.store.as_ref().map(|store| {
+            let Some(base) = store.todo(id)? else {
+                return Ok(None);
+            };
+            store.push(Change::Update {
+                id,
+                title: title.map(str::to_string),
+                completed,
+                base: Box::new(base),
+                at: Utc::now(),
+            })
+        });
+        match result {
+            Some(Ok(Some(todo))) => Ok(todo),
+            Some(Err(err)) => {
+                tracing::warn!("Offline cache unavailable: {:#}", err);
+                Err(not_synced(id))
+            }
+            _ => Err(not_synced(id)),
+        }
+    }
+}
+
+/// A 404 for a todo created offline that the cache no longer has.
+fn not_synced(id: i64) -> ApiError {
+    ApiError::NotFound {
+        id: None,
+        error: ClientError {
+            status: StatusCode::NOT_FOUND,
+            code: None,
+            message: format!("Todo #{} was created offline and isn't cached anymore", id),
+            fields: Vec::new(),
+            request_id: None,
+        },
+    }
+}
+
+impl<A: TodoApi> TodoApi for Cached<'_, A> {
+    fn base_url(&self) -> &str {
+        self.inner.base_url()
+    }
+
+    fn workspace(&self) -> Option<i64> {
+        self.inner.workspace()
+    }
+
+    async fn login(
+        &self,
+        email: &str,
+        password: &str,
+        opts: &RequestOptions,
+    ) -> error::Result<AuthResponse> {
+        self.inner.login(email, password, opts).await
+    }
+
+    async fn register(
+        &self,
+        email: &str,
+        password: &str,
+        opts: &RequestOptions,
+    ) -> error::Result<AuthResponse> {
+        self.inner.register(email, password, opts).await
+    }
+
+    async fn list_todos_page(
+        &self,
+        completed: Option<bool>,
+        cursor: Option<&str>,
+        opts: &RequestOptions,
+    ) -> error::Result<Page<Todo>> {
+        self.inner.list_todos_page(completed, cursor, opts).await
+    }
+
+    async fn list_todos(
+        &self,
+        completed: Option<bool>,
+        opts: &RequestOptions,
+    ) -> error::Result<Vec<Todo>> {
+        match self.inner.list_todos(completed, opts).await {
+            Ok(todos) => {
+                self.remember(&todos, completed.is_none());
+                // Show changes that are still queued as if they were made
+                let queued = self.store.as_ref().and_then(|store| {
+                    let queue = store.queue().ok()?;
+                    (!queue.is_empty()).then(|| store.todos(completed).ok())?
+                });
+                Ok(queued.unwrap_or(todos))
+            }
+            Err(err) => self.offline(err, |store| store.todos(completed).map(Some)),
+        }
+    }
+
+    async fn get_todo(&self, id: i64, opts: &RequestOptions) -> error::Result<Todo> {
+        if id < 0 {
+            let cached = self
+                .store
+                .as_ref()
+                .and_then(|store| store.todo(id).ok().flatten());
+            return cached.ok_or_else(|| not_synced(id));
+        }
+        match self.inner.get_todo(id, opts).await {
+            Ok(todo) => {
+                self.remember(std::slice::from_ref(&todo), false);
+                Ok(todo)
+            }
+            Err(err) => self.offline(err, |store| store.todo(id)),
+        }
+    }
+
+    async fn create_todo(&self, title: &str, opts: &RequestOptions) -> error::Result<Todo> {
+        match self.inner.create_todo(title, opts).await {
+            Ok(todo) => {
+                self.remember(std::slice::from_ref(&todo), false);
+                Ok(todo)
+            }
+            Err(err) => self.offline(err, |store| {
+                let mut key = [0u8; 16];
+                getrandom::getrandom(&mut key).context("Failed to generate random bytes")?;
+                store.push(Change::Create {
+                    id: store.next_local_id()?,
+                    title: title.to_string(),
+                    key: hex::encode(key),
+                    at: Utc::now(),
+                })
+            }),
+        }
+    }
+
+    async fn update_todo(
+        &self,
+        id: i64,
+        title: Option<&str>,
+        completed: Option<bool>,
+        opts: &RequestOptions,
+    ) -> error::Result<Todo> {
+        if id < 0 {
+            return self.update_local(id, title, completed);
+        }
+        match self.inner.update_todo(id, title, completed, opts).await {
+            Ok(todo) => {
+                self.remember(std::slice::from_ref(&todo), false);
+                Ok(todo)
+            }
+            Err(err) => self.offline(err, |store| {
+                let Some(base) = store.todo(id)? else {
+                    return Ok(None);
+                };
+                store.push(Change::Update {
+                    id,
+                    title: title.map(str::to_string),
+                    completed,
+                    base: Box::new(base),
+                    at: Utc::now(),
+                })
+            }),
+        }
+    }
+
+    async fn set_due(
+        &self,
+        id: i64,
+        due: DateTime<Utc>,
+        opts: &RequestOptions,
+    ) -> error::Result<Todo> {
+        self.online("due dates", self.inner.set_due(id, due, opts).await)
+    }
+
+    async fn set_tags(
+        &self,
+        id: i64,
+        tags: &[String],
+        opts: &RequestOptions,
+    ) -> error::Result<Todo> {
+        self.online("tags", self.inner.set_tags(id, tags, opts).await)
+    }
+
+    async fn set_estimate(
+        &self,
+        id: i64,
+        minutes: i64,
+        opts: &RequestOptions,
+    ) -> error::Result<Todo> {
+        self.online("estimates", self.inner.set_estimate(id, minutes, opts).await)
+    }
+
+    async fn set_dependencies(
+        &self,
+        id: i64,
+        depends_on: &[i64],
+        opts: &RequestOptions,
+    ) -> error::Result<Todo> {
+        self.online("dependencies", self.inner.set_dependencies(id, depends_on, opts).await)
+    }
+
+    async fn set_links(
+        &self,
+        id: i64,
+        links: &[Link],
+        opts: &RequestOptions,
+    ) -> error::Result<Todo> {
+        self.online("links", self.inner.set_links(id, links, opts).await)
+    }
+
+    async fn set_status(
+        &self,
+        id: i64,
+        status: TodoStatus,
+        reason: Option<&str>,
+        opts: &RequestOptions,
+    ) -> error::Result<Todo> {
+        self.online("statuses", self.inner.set_status(id, status, reason, opts).await)
+    }
+
+    async fn delete_todo(&self, id: i64, opts: &RequestOptions) -> error::Result<()> {
+        if id < 0 {
+            let Some(ref store) = self.store else {
+                return Err(not_synced(id));
+            };
+            return store.discard(id).map_err(|err| {
+                tracing::warn!("Offline cache unavailable: {:#}", err);
+                not_synced(id)
+            });
+        }
+        match self.inner.delete_todo(id, opts).await {
+            Ok(()) => {
+                if let Some(ref store) = self.store
+                    && let Err(err) = store.remove(id)
+                {
+                    tracing::warn!("Failed to update offline cache: {:#}", err);
+                }
+                Ok(())
+            }
+            Err(err) => self.offline(err, |store| {
+                let Some(todo) = store.todo(id)? else {
+                    return Ok(None);
+                };
+                store.push(Change::Delete {
+                    id,
+                    title: todo.title,
+                })?;
+                Ok(Some(()))
+            }),
+        }
+    }
+
+    async fn limits(&self, opts: &RequestOptions) -> error::Result<Limits> {
+        self.inner.limits(opts).await
+    }
+
+    async fn quota(&self, opts: &RequestOptions) -> error::Result<Quota> {
+        self.inner.quota(opts).await
+    }
+
+    async fn delete_account(&self, opts: &RequestOptions) -> error::Result<()> {
+        self.inner.delete_account(opts).await
+    }
+
+    async fn profile(&self, opts: &RequestOptions) -> error::Result<User> {
+        self.inner.profile(opts).await
+    }
+
+    async fn set_avatar(&self, png: Vec<u8>, opts: &RequestOptions) -> error::Result<()> {
+        self.inner.set_avatar(png, opts).await
+    }
+
+    async fn remove_avatar(&self, opts: &RequestOptions) -> error::Result<()> {
+        self.inner.remove_avatar(opts).await
+    }
+
+    async fn update_profile(
+        &self,
+        name: Option<&str>,
+        email: Option<&str>,
+        opts: &RequestOptions,
+    ) -> error::Result<User> {
+        self.inner.update_profile(name, email, opts).await
+    }
+
+    async fn list_workspaces(&self, opts: &RequestOptions) -> error::Result<Vec<Workspace>> {
+        self.inner.list_workspaces(opts).await
+    }
+
+    async fn create_workspace(
+        &self,
+        name: &str,
+        opts: &RequestOptions,
+    ) -> error::Result<Workspace> {
+        self.inner.create_workspace(name, opts).await
+    }
+
+    async fn workspace_members(
+        &self,
+        id: i64,
+        opts: &RequestOptions,
+    ) -> error::Result<Vec<Member>> {
+        self.inner.workspace_members(id, opts).await
+    }
+
+    async fn invite_member(
+        &self,
+        workspace: i64,
+        email: &str,
+        role: Role,
+        opts: &RequestOptions,
+    ) -> error::Result<Invitation> {
+        self.inner.invite_member(workspace, email, role, opts).await
+    }
+
+    async fn set_member_role(
+        &self,
+        workspace: i64,
+        user_id: i64,
+        role: Role,
+        opts: &RequestOptions,
+    ) -> error::Result<Member> {
+        self.inner
+            .set_member_role(workspace, user_id, role, opts)
+            .await
+    }
+
+    async fn list_invitations(&self, opts: &RequestOptions) -> error::Result<Vec<Invitation>> {
+        self.inner.list_invitations(opts).await
+    }
+
+    async fn respond_to_invitation(
+        &self,
+        id: i64,
+        accept: bool,
+        opts: &RequestOptions,
+    ) -> error::Result<Invitation> {
+        self.inner.respond_to_invitation(id, accept, opts).await
+    }
+}
+
+/// What sending one queued change came to.
+enum Sent {
+    /// The server has it; the ID is the todo's on the server
+    Done(i64),
+    /// Nothing left to send, for the given reason
+    Skipped(i64, &'static str),
+}
+
+/// `todo sync`: sends the queued changes in order, then refreshes the
+/// cache. A change the server rejects is reported and dropped; if the
+/// server goes out of reach partway, the rest stay queued.
+pub async fn sync<A: TodoApi>(client: &A, report: Option<&Path>) -> Result<()> {
+    let store = Store::open(client)?;
+    send_queue(client, &store, report).await
+}
+
+async fn send_queue<A: TodoApi>(client: &A, store: &Store, report: Option<&Path>) -> Result<()> {
+    let queue = store.queue()?;
+    let opts = RequestOptions::new().retries(2);
+
+    // Server IDs of the todos created offline so far
+    let mut ids: HashMap<i64, i64> = HashMap::new();
+    let mut summary = Summary::new("synced");
+    // How many changes are left when sync stops early, and why
+    let mut stopped = None;
+    for (i, (seq, change)) in queue.iter().enumerate() {
+        // Ctrl+C: the rest stay queued for the next sync
+        if bulk::cancelled() {
+            stopped = Some((queue.len() - i, None));
+            break;
+        }
+        let id = ids.get(&change.id()).copied().unwrap_or(change.id());
+        match send(client, change, id, &opts).await {
+            Ok(Sent::Done(synced)) => {
+                if synced != id {
+                    store.renumber(id, synced)?;
+                    ids.insert(id, synced);
+                }
+                summary.succeeded(change.item(synced), synced);
+            }
+            Ok(Sent::Skipped(id, reason)) => summary.skipped(change.item(id), Some(id), reason),
+            Err(err) if unreachable(&err) => {
+                stopped = Some((queue.len() - i, Some(err)));
+                break;
+            }
+            Err(err) => summary.failed(change.item(id), (id > 0).then_some(id), err),
+        }
+        store.dequeue(*seq)?;
+    }
+    let mut aliases = Aliases::load();
+    for local in ids.keys() {
+        aliases.forget(*local);
+    }
+    aliases.save();
+
+    if let Some((left, err)) = stopped {
+        summary.finish(report)?;
+        return Err(match err {
+            Some(err) => anyhow::Error::from(err).context(format!(
+                "Server out of reach; {} change(s) still queued",
+                left
+            )),
+            None => anyhow!("Sync stopped; {} change(s) still queued", left),
+        });
+    }
+
+    let todos = client.list_todos(None, &opts).await?;
+    store.save(&todos, true)?;
+    search::record(&todos, true);
+
+    if queue.is_empty() {
+        output::success(&format!("Nothing to sync; cached {} todos.", todos.len()));
+        return output::print_result("synced", &[], ());
+    }
+    summary.finish(report)
+}
+
+async fn send<A: TodoApi>(
+    client: &A,
+    change: &Change,
+    id: i64,
+    opts: &RequestOptions,
+) -> error::Result<Sent> {
+    // A todo whose create failed has nothing on the server to change
+    if id < 0 && !matches!(change, Change::Create { .. }) {
+        return Ok(Sent::Skipped(id, "its create wasn't synced"));
+    }
+    match change {
+        Change::Create { title, key, .. } => {
+            let todo = client
+                .create_todo(title, &opts.clone().idempotency_key(key.clone()))
+                .await?;
+            history::record_todo(&todo, Action::Created);
+            Ok(Sent::Done(todo.id))
+        }
+        Change::Update {
+            title,
+            completed,
+            base,
+            ..
+        } => {
+            let current = match client.get_todo(id, opts).await {
+                Ok(current) => current,
+                Err(ApiError::NotFound { .. }) => {
+                    return Ok(Sent::Skipped(id, "deleted on the server"));
+                }
+                Err(err) => return Err(err),
+            };
+            // A field changed on the server since keeps the server's value;
+            // the rest get the offline change
+            let title = title
+                .as_deref()
+                .filter(|_| current.title == base.title)
+                .filter(|title| *title != current.title);
+            let completed = completed
+                .filter(|_| current.completed == base.completed)
+                .filter(|completed| *completed != current.completed);
+            if title.is_none() && completed.is_none() {
+                return Ok(Sent::Skipped(id, "changed on the server too; kept that"));
+            }
+            let todo = client
+                .update_todo(
+                    id,
+                    title,
+                    completed,
+                    &opts.clone().if_match(current.updated_at),
+                )
+                .await?;
+            let action = match completed {
+                Some(true) => Action::Completed,
+                _ => Action::Updated,
+            };
+            history::record_todo(&todo, action);
+            Ok(Sent::Done(todo.id))
+        }
+        Change::Delete { title, .. } => match client.delete_todo(id, opts).await {
+            Ok(()) => {
+                history::record_deleted(id, Some(title.clone()));
+                search::forget(&[id]);
+                Ok(Sent::Done(id))
+            }
+            Err(ApiError::NotFound { .. }) => Ok(Sent::Skipped(id, "already deleted")),
+            Err(err) => Err(err),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fake::{self, FakeApi};
+
+    /// `server` behind a client whose cache is held in memory, filled with
+    /// what the server has.
+    async fn cached(server: &FakeApi) -> Cached<'_, FakeApi> {
+        fake::isolate();
+        let store = Store::new(Connection::open_in_memory().unwrap(), server).unwrap();
+        let client = Cached {
+            inner: server,
+            store: Some(store),
+            noticed: Cell::new(false),
+        };
+        client.list_todos(None, &RequestOptions::default()).await.unwrap();
+        client
+    }
+
+    fn store<'a>(client: &'a Cached<'_, FakeApi>) -> &'a Store {
+        client.store.as_ref().unwrap()
+    }
+
+    #[tokio::test]
+    async fn a_todo_created_offline_syncs_under_the_server_id() {
+        let server = FakeApi::with_todos(&["existing"]);
+        let client = cached(&server).await;
+        let opts = RequestOptions::default();
+
+        server.unreachable.set(true);
+        let created = client.create_todo("draft", &opts).await.unwrap();
+        assert_eq!(created.id, -1);
+        client.update_todo(-1, Some("final"), Some(true), &opts).await.unwrap();
+
+        server.unreachable.set(false);
+        send_queue(&server, store(&client), None).await.unwrap();
+        let synced = server.todo(2).expect("created on the server");
+        assert_eq!(synced.title, "final");
+        assert!(synced.completed);
+        assert!(store(&client).queue().unwrap().is_empty());
+        assert!(store(&client).todo(-1).unwrap().is_none());
+        assert_eq!(store(&client).todo(2).unwrap().unwrap().title, "final");
+    }
+
+    #[tokio::test]
+    async fn a_title_changed_on_the_server_wins() {
+        let server = FakeApi::with_todos(&["draft"]);
+        let client = cached(&server).await;
+        let opts = RequestOptions::default();
+
+        server.unreachable.set(true);
+        client.update_todo(1, Some("offline"), None, &opts).await.unwrap();
+        server.unreachable.set(false);
+        server.edit(1, |todo| todo.title = "server".to_string());
+
+        send_queue(&server, store(&client), None).await.unwrap();
+        assert_eq!(server.todo(1).unwrap().title, "server");
+        assert_eq!(store(&client).todo(1).unwrap().unwrap().title, "server");
+    }
+
+    #[tokio::test]
+    async fn an_update_to_a_todo_deleted_on_the_server_is_skipped() {
+        let server = FakeApi::with_todos(&["gone", "kept"]);
+        let client = cached(&server).await;
+        let opts = RequestOptions::default();
+
+        server.unreachable.set(true);
+        client.update_todo(1, None, Some(true), &opts).await.unwrap();
+        server.unreachable.set(false);
+        server.remove(1);
+
+        send_queue(&server, store(&client), None).await.unwrap();
+        assert!(store(&client).queue().unwrap().is_empty());
+        assert!(store(&client).todo(1).unwrap().is_none());
+        assert_eq!(server.todos().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn sync_stops_while_the_server_is_out_of_reach() {
+        let server = FakeApi::with_todos(&["one", "two"]);
+        let client = cached(&server).await;
+        let opts = RequestOptions::default();
+
+        server.unreachable.set(true);
+        client.update_todo(1, None, Some(true), &opts).await.unwrap();
+        client.delete_todo(2, &opts).await.unwrap();
+
+        let err = send_queue(&server, store(&client), None).await.unwrap_err();
+        assert!(err.to_string().contains("2 change(s) still queued"), "{}", err);
+        assert_eq!(store(&client).queue().unwrap().len(), 2);
+        assert!(server.todos().iter().all(|todo| !todo.completed));
+    }
+
+    #[tokio::test]
+    async fn changes_queued_for_a_synced_todo_follow_its_new_id() {
+        let server = FakeApi::with_todos(&[]);
+        let client = cached(&server).await;
+        let opts = RequestOptions::default();
+
+        server.unreachable.set(true);
+        client.create_todo("draft", &opts).await.unwrap();
+        client.update_todo(-1, None, Some(true), &opts).await.unwrap();
+
+        store(&client).renumber(-1, 7).unwrap();
+        let ids: Vec<_> = store(&client)
+            .queue()
+            .unwrap()
+            .iter()
+            .map(|(_, change)| change.id())
+            .collect();
+        assert_eq!(ids, [7, 7]);
+        assert_eq!(store(&client).todo(7).unwrap().unwrap().title, "draft");
+        assert!(store(&client).todo(-1).unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn changes_the_queue_cannot_hold_say_so() {
+        let server = FakeApi::with_todos(&["one"]);
+        let client = cached(&server).await;
+
+        server.unreachable.set(true);
+        let err = client
+            .set_tags(1, &["work".to_string()], &RequestOptions::default())
+            .await
+            .unwrap_err();
+        let message = format!("{:#}", anyhow::Error::from(err));
+        assert!(message.contains("Changing tags isn't available offline"), "{}", message);
+        assert!(store(&client).queue().unwrap().is_empty());
+    }
+}
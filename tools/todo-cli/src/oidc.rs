@@ -0,0 +1,158 @@
+//! Browser-based OpenID Connect login (authorization code flow with PKCE)
+//! for SSO-protected instances. The identity provider redirects back to a
+//! one-shot server on 127.0.0.1, so no client secret is involved.
+
+use std::time::Duration;
+
+use anyhow::{Context, Result, bail};
+use base64::Engine;
+use base64::prelude::BASE64_URL_SAFE_NO_PAD;
+use reqwest::Url;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+
+use crate::config::OidcConfig;
+use crate::output;
+use crate::secret::SecretString;
+
+/// How long to wait for the user to finish signing in.
+const LOGIN_TIMEOUT: Duration = Duration::from_secs(300);
+
+const CALLBACK_PAGE: &str = "<!doctype html><title>todo-cli</title>\
+    <p>Signed in. You can close this window and return to the terminal.</p>";
+
+#[derive(Debug, Deserialize)]
+struct Discovery {
+    authorization_endpoint: String,
+    token_endpoint: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: SecretString,
+}
+
+/// Runs the whole flow and returns the access token to use with the API.
+pub async fn login(config: &OidcConfig) -> Result<SecretString> {
+    let (Some(issuer), Some(client_id)) = (config.issuer.as_deref(), config.client_id.as_deref())
+    else {
+        bail!("Set issuer and client_id in the [oidc] config section to log in with a browser");
+    };
+
+    let http = reqwest::Client::new();
+    let discovery_url = format!(
+        "{}/.well-known/openid-configuration",
+        issuer.trim_end_matches('/')
+    );
+    let discovery: Discovery = http
+        .get(&discovery_url)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .with_context(|| format!("Failed to fetch {}", discovery_url))?
+        .json()
+        .await
+        .context("Failed to parse OpenID configuration")?;
+
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .await
+        .context("Failed to start the login callback server")?;
+    let redirect_uri = format!("http://127.0.0.1:{}/callback", listener.local_addr()?.port());
+
+    let verifier = random_token()?;
+    let state = random_token()?;
+    let challenge = BASE64_URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()));
+
+    let mut authorize = Url::parse(&discovery.authorization_endpoint)
+        .context("Invalid authorization endpoint")?;
+    authorize
+        .query_pairs_mut()
+        .append_pair("response_type", "code")
+        .append_pair("client_id", client_id)
+        .append_pair("redirect_uri", &redirect_uri)
+        .append_pair("scope", &config.scopes.join(" "))
+        .append_pair("state", &state)
+        .append_pair("code_challenge", &challenge)
+        .append_pair("code_challenge_method", "S256");
+
+    output::say!("{}", output::with_icon("🌐", "Opening your browser to sign in..."));
+    if open::that(authorize.as_str()).is_err() {
+        output::say!("Open this URL to continue:");
+    }
+    // Printed either way, for headless sessions and blocked browsers
+    output::say!("  {}", authorize);
+
+    let code = tokio::time::timeout(LOGIN_TIMEOUT, wait_for_code(&listener, &state))
+        .await
+        .context("Timed out waiting for the browser sign-in")??;
+
+    let token: TokenResponse = http
+        .post(&discovery.token_endpoint)
+        .form(&[
+            ("grant_type", "authorization_code"),
+            ("code", &code),
+            ("redirect_uri", &redirect_uri),
+            ("client_id", client_id),
+            ("code_verifier", &verifier),
+        ])
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .context("Failed to exchange the authorization code")?
+        .json()
+        .await
+        .context("Failed to parse token response")?;
+
+    Ok(token.access_token)
+}
+
+/// Serves the redirect and returns its `code`, ignoring stray requests such
+/// as the browser's favicon fetch.
+async fn wait_for_code(listener: &TcpListener, state: &str) -> Result<String> {
+    loop {
+        let (mut stream, _) = listener.accept().await?;
+        let mut request_line = String::new();
+        BufReader::new(&mut stream).read_line(&mut request_line).await?;
+
+        // e.g. `GET /callback?code=...&state=... HTTP/1.1`
+        let Some(target) = request_line.split_whitespace().nth(1) else {
+            continue;
+        };
+        let url = Url::parse(&format!("http://127.0.0.1{}", target))?;
+        if url.path() != "/callback" {
+            stream.write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n").await?;
+            continue;
+        }
+
+        let response = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: {}\r\n\
+             Connection: close\r\n\r\n{}",
+            CALLBACK_PAGE.len(),
+            CALLBACK_PAGE
+        );
+        stream.write_all(response.as_bytes()).await?;
+
+        let param = |name: &str| {
+            url.query_pairs()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.into_owned())
+        };
+        if let Some(error) = param("error") {
+            let description = param("error_description").unwrap_or_default();
+            bail!("Sign-in failed: {} {}", error, description);
+        }
+        if param("state").as_deref() != Some(state) {
+            bail!("Sign-in response did not match this login attempt");
+        }
+        return param("code").context("Sign-in response had no authorization code");
+    }
+}
+
+/// 32 random bytes, base64url-encoded: a valid PKCE verifier (43 chars).
+fn random_token() -> Result<String> {
+    let mut bytes = [0u8; 32];
+    getrandom::getrandom(&mut bytes).context("Failed to generate random bytes")?;
+    Ok(BASE64_URL_SAFE_NO_PAD.encode(bytes))
+}
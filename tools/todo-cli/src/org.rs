@@ -0,0 +1,237 @@
+//! Emacs org-mode for `todo export --as org` and `todo import --from org`.
+//! Each todo is a heading with a TODO or DONE keyword, its tags and its
+//! due date as a DEADLINE. A todo's subtasks (the todos it depends on) are
+//! nested under it, and read back the same way.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveTime, Timelike, Utc};
+
+use crate::api::Todo;
+use crate::bulk::Imported;
+use crate::due;
+use crate::markdown;
+
+/// Renders `todos` as an org outline. A todo that another one depends on
+/// is nested under the first such todo; the rest are top-level headings.
+pub fn render(todos: &[Todo]) -> String {
+    let index: HashMap<i64, usize> = todos.iter().enumerate().map(|(i, t)| (t.id, i)).collect();
+    let mut parents: Vec<Option<usize>> = vec![None; todos.len()];
+    for (i, todo) in todos.iter().enumerate() {
+        for id in &todo.depends_on {
+            if let Some(&child) = index.get(id)
+                && child != i
+                && parents[child].is_none()
+            {
+                parents[child] = Some(i);
+            }
+        }
+    }
+    let mut children: Vec<Vec<usize>> = vec![Vec::new(); todos.len()];
+    for (child, parent) in parents.iter().enumerate() {
+        if let Some(parent) = parent {
+            children[*parent].push(child);
+        }
+    }
+
+    let mut out = String::new();
+    let mut written = vec![false; todos.len()];
+    // Roots first; then whatever a dependency cycle kept from being one
+    let roots = (0..todos.len()).filter(|&i| parents[i].is_none());
+    for i in roots.chain(0..todos.len()) {
+        write_heading(&mut out, todos, &children, &mut written, i, 1);
+    }
+    out
+}
+
+fn write_heading(
+    out: &mut String,
+    todos: &[Todo],
+    children: &[Vec<usize>],
+    written: &mut [bool],
+    i: usize,
+    level: usize,
+) {
+    if written[i] {
+        return;
+    }
+    written[i] = true;
+
+    let todo = &todos[i];
+    let keyword = if todo.completed { "DONE" } else { "TODO" };
+    // A newline in a title would end the heading early
+    let title = todo.title.split_whitespace().collect::<Vec<_>>().join(" ");
+    out.push_str(&format!("{} {} {}", "*".repeat(level), keyword, title));
+    if !todo.tags.is_empty() {
+        out.push_str(&format!(" :{}:", todo.tags.join(":")));
+    }
+    out.push('\n');
+    if let Some(due) = todo.due() {
+        out.push_str(&format!(
+            "{}DEADLINE: {}\n",
+            " ".repeat(level + 1),
+            timestamp(due)
+        ));
+    }
+    for &child in &children[i] {
+        write_heading(out, todos, children, written, child, level + 1);
+    }
+}
+
+/// `<2026-10-20 Tue 14:00>`, or just the date for the end of a local day,
+/// which is what a due date given as a date becomes.
+fn timestamp(due: DateTime<Utc>) -> String {
+    let local = due.with_timezone(&Local);
+    if local.hour() == 23 && local.minute() == 59 {
+        local.format("<%Y-%m-%d %a>").to_string()
+    } else {
+        local.format("<%Y-%m-%d %a %H:%M>").to_string()
+    }
+}
+
+/// Reads the TODO and DONE headings of `content` in document order. A todo
+/// heading nested under another becomes its subtask; headings without a
+/// keyword become tags of the todos below them, as do a heading's own
+/// `:tags:`. SCHEDULED has no counterpart in the API and is skipped.
+pub fn parse(content: &str) -> Vec<Imported> {
+    let mut items: Vec<Imported> = Vec::new();
+    // Plain headings by level, with the tags they give the todos below
+    let mut headings: Vec<(usize, Vec<String>)> = Vec::new();
+    // Level and index of the todos the next one may be nested in
+    let mut open: Vec<(usize, usize)> = Vec::new();
+    // The todo whose heading was the line before, for its planning line
+    let mut planning: Option<usize> = None;
+    let mut in_block = false;
+
+    for line in content.lines() {
+        let trimmed = line.trim_start();
+        let lower = trimmed.to_ascii_lowercase();
+        if lower.starts_with("#+begin_") {
+            in_block = true;
+            continue;
+        }
+        if lower.starts_with("#+end_") {
+            in_block = false;
+            continue;
+        }
+        if in_block {
+            continue;
+        }
+
+        let Some((level, rest)) = heading(line) else {
+            if let Some(i) = planning.take()
+                && let Some(due) = deadline(trimmed)
+            {
+                items[i].due = Some(due);
+            }
+            continue;
+        };
+        planning = None;
+        headings.retain(|&(other, _)| other < level);
+        open.retain(|&(other, _)| other < level);
+
+        let (completed, rest) = match keyword(rest) {
+            Some(found) => found,
+            None => {
+                let (title, mut tags) = split_tags(rest);
+                let tag = markdown::slug(title);
+                if !tag.is_empty() {
+                    tags.insert(0, tag);
+                }
+                headings.push((level, tags));
+                continue;
+            }
+        };
+        let (title, own) = split_tags(strip_priority(rest));
+        if title.is_empty() {
+            continue;
+        }
+        let mut tags: Vec<String> = Vec::new();
+        for tag in headings.iter().flat_map(|(_, tags)| tags).chain(&own) {
+            if !tags.contains(tag) {
+                tags.push(tag.clone());
+            }
+        }
+
+        planning = Some(items.len());
+        open.push((level, items.len()));
+        items.push(Imported {
+            title: title.to_string(),
+            completed,
+            tags,
+            due: None,
+            parent: open.iter().rev().nth(1).map(|&(_, parent)| parent),
+        });
+    }
+    items
+}
+
+/// `** TODO Title` as (2, "TODO Title"). Headings start in the first
+/// column.
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|&c| c == '*').count();
+    if level == 0 {
+        return None;
+    }
+    let rest = line[level..].strip_prefix(' ')?;
+    Some((level, rest.trim()))
+}
+
+/// `TODO Title` as (false, "Title") and `DONE Title` as (true, "Title").
+fn keyword(rest: &str) -> Option<(bool, &str)> {
+    let (word, title) = rest.split_once(' ').unwrap_or((rest, ""));
+    match word {
+        "TODO" => Some((false, title.trim_start())),
+        "DONE" => Some((true, title.trim_start())),
+        _ => None,
+    }
+}
+
+/// Drops a `[#A]` priority cookie.
+fn strip_priority(rest: &str) -> &str {
+    match rest.strip_prefix("[#") {
+        Some(after) if after.get(1..2) == Some("]") => after[2..].trim_start(),
+        _ => rest,
+    }
+}
+
+/// `Title :work:urgent:` as ("Title", ["work", "urgent"]).
+fn split_tags(rest: &str) -> (&str, Vec<String>) {
+    let (title, last) = match rest.rsplit_once(char::is_whitespace) {
+        Some((title, last)) => (title.trim_end(), last),
+        None => ("", rest),
+    };
+    if last.len() < 2 || !last.starts_with(':') || !last.ends_with(':') {
+        return (rest, Vec::new());
+    }
+    let tags = last
+        .split(':')
+        .filter(|tag| !tag.is_empty())
+        .map(str::to_string)
+        .collect();
+    (title, tags)
+}
+
+/// The DEADLINE of a planning line like
+/// `SCHEDULED: <2026-10-19 Mon> DEADLINE: <2026-10-20 Tue 14:00>`. A date
+/// without a time means the end of that local day.
+fn deadline(line: &str) -> Option<DateTime<Utc>> {
+    let (_, rest) = line.split_once("DEADLINE:")?;
+    let rest = rest.trim_start().strip_prefix('<')?;
+    let (stamp, _) = rest.split_once('>')?;
+    let mut parts = stamp.split_whitespace();
+    let date = NaiveDate::parse_from_str(parts.next()?, "%Y-%m-%d").ok()?;
+    // After the day name; a range like 10:00-11:00 is due when it starts
+    let time = parts.find_map(|part| {
+        let start = part.split('-').next()?;
+        NaiveTime::parse_from_str(start, "%H:%M").ok()
+    });
+    match time {
+        Some(time) => date
+            .and_time(time)
+            .and_local_timezone(Local)
+            .earliest()
+            .map(|at| at.with_timezone(&Utc)),
+        None => Some(due::start_of(date + Duration::days(1)) - Duration::minutes(1)),
+    }
+}
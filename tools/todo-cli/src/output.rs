@@ -1,34 +1,446 @@
-use anyhow::Result;
-use colored::Colorize;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicBool, Ordering};
 
-use crate::api::Todo;
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Local, Utc};
+use serde::Serialize;
+use terminal_size::{Width, terminal_size};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
-pub fn print_todos(todos: &[Todo], format: &str) -> Result<()> {
-    match format {
-        "json" => {
-            println!("{}", serde_json::to_string_pretty(todos)?);
+use crate::api::{Todo, TodoStatus};
+use crate::config::ThemeConfig;
+use crate::depends;
+use crate::due::{self, Agenda};
+use crate::error::{ApiError, FieldError};
+use crate::{links, locale};
+use crate::style::{Colorize, Style};
+use crate::validation::InvalidInput;
+
+/// Styles for the semantic roles in todo output.
+#[derive(Debug)]
+pub struct Theme {
+    completed: Style,
+    pending: Style,
+    id: Style,
+    header: Style,
+    overdue: Style,
+    in_progress: Style,
+    blocked: Style,
+}
+
+static THEME: OnceLock<Theme> = OnceLock::new();
+
+impl Theme {
+    /// Built-in presets; `mono` relies on weight alone for terminals
+    /// without color or with colors that clash.
+    fn preset(name: &str) -> Result<Self> {
+        let [completed, pending, id, header, overdue, in_progress, blocked] = match name {
+            "default" => ["green", "yellow", "dimmed", "bold", "bold red", "cyan", "magenta"],
+            "solarized" => [
+                "#859900",
+                "#b58900",
+                "#586e75",
+                "bold #268bd2",
+                "bold #dc322f",
+                "#2aa198",
+                "#d33682",
+            ],
+            "mono" => ["dimmed", "", "dimmed", "bold underline", "bold", "italic", "underline"],
+            _ => bail!("Unknown theme preset '{}' (default, solarized, mono)", name),
+        };
+        Ok(Self {
+            completed: Style::parse(completed)?,
+            pending: Style::parse(pending)?,
+            id: Style::parse(id)?,
+            header: Style::parse(header)?,
+            overdue: Style::parse(overdue)?,
+            in_progress: Style::parse(in_progress)?,
+            blocked: Style::parse(blocked)?,
+        })
+    }
+
+    /// Starts from the configured preset and applies any per-role overrides.
+    pub fn from_config(config: &ThemeConfig) -> Result<Self> {
+        let mut theme = Self::preset(config.preset.as_deref().unwrap_or("default"))?;
+        let overrides = [
+            (&mut theme.completed, &config.completed, "completed"),
+            (&mut theme.pending, &config.pending, "pending"),
+            (&mut theme.id, &config.id, "id"),
+            (&mut theme.header, &config.header, "header"),
+            (&mut theme.overdue, &config.overdue, "overdue"),
+            (&mut theme.in_progress, &config.in_progress, "in_progress"),
+            (&mut theme.blocked, &config.blocked, "blocked"),
+        ];
+        for (style, spec, role) in overrides {
+            if let Some(spec) = spec {
+                *style = Style::parse(spec).with_context(|| format!("Invalid theme.{}", role))?;
+            }
+        }
+        Ok(theme)
+    }
+
+    /// Installs the theme used by every print function for this process.
+    pub fn install(self) {
+        let _ = THEME.set(self);
+    }
+}
+
+fn theme() -> &'static Theme {
+    THEME.get_or_init(|| Theme::preset("default").expect("default theme is valid"))
+}
+
+static ACCESSIBLE: AtomicBool = AtomicBool::new(false);
+static EMOJI: AtomicBool = AtomicBool::new(true);
+static JSON: AtomicBool = AtomicBool::new(false);
+
+/// `println!` for human-readable text. In JSON mode it goes to stderr, so
+/// stdout carries nothing but the JSON result.
+macro_rules! say {
+    ($($arg:tt)*) => {
+        if $crate::output::json() {
+            eprintln!($($arg)*)
+        } else {
+            println!($($arg)*)
+        }
+    };
+}
+pub(crate) use say;
+
+/// Switches to screen-reader friendly output: no emoji, box drawing or
+/// strikethrough, and status spelled out instead of shown by symbol or color.
+pub fn set_accessible(on: bool) {
+    ACCESSIBLE.store(on, Ordering::Relaxed);
+}
+
+pub fn accessible() -> bool {
+    ACCESSIBLE.load(Ordering::Relaxed)
+}
+
+/// Falls back to ASCII markers on terminals that can't draw emoji.
+pub fn set_emoji(on: bool) {
+    EMOJI.store(on, Ordering::Relaxed);
+}
+
+fn emoji() -> bool {
+    EMOJI.load(Ordering::Relaxed)
+}
+
+/// Marks the process as producing JSON (`--format json`): every command
+/// prints one JSON document on stdout and decorations go to stderr.
+pub fn set_json(on: bool) {
+    JSON.store(on, Ordering::Relaxed);
+}
+
+pub fn json() -> bool {
+    JSON.load(Ordering::Relaxed)
+}
+
+/// `message` led by `icon`, an ASCII stand-in where emoji don't render, or
+/// just `message` in accessible mode.
+pub fn with_icon(icon: &str, message: &str) -> String {
+    if accessible() {
+        return message.to_string();
+    }
+    if emoji() {
+        return format!("{} {}", icon, message);
+    }
+    match icon.trim_end() {
+        "✅" => format!("[ok] {}", message),
+        "❌" => format!("[error] {}", message),
+        "⚠️" => format!("[!] {}", message),
+        _ => message.to_string(),
+    }
+}
+
+/// Versions of the JSON envelope selected with `--output-schema`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Schema {
+    /// `{"schema": "v1", "kind": ..., "data": ...}`
+    V1,
+}
+
+impl Schema {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Schema::V1 => "v1",
+        }
+    }
+}
+
+static SCHEMA: OnceLock<Schema> = OnceLock::new();
+
+/// Wraps JSON output in the versioned envelope for this process.
+pub fn set_schema(schema: Schema) {
+    let _ = SCHEMA.set(schema);
+}
+
+#[derive(Serialize)]
+struct Envelope<'a, T: ?Sized> {
+    schema: &'static str,
+    kind: &'a str,
+    data: &'a T,
+}
+
+/// Prints `data` as JSON, inside the envelope when a schema was chosen.
+/// `kind` names the shape of `data` (`todo`, `todo_list`, ...) and is part
+/// of the schema, so keep existing kinds stable.
+pub fn print_json<T: Serialize + ?Sized>(kind: &str, data: &T) -> Result<()> {
+    let json = match SCHEMA.get() {
+        Some(schema) => serde_json::to_string_pretty(&Envelope {
+            schema: schema.as_str(),
+            kind,
+            data,
+        })?,
+        None => serde_json::to_string_pretty(data)?,
+    };
+    println!("{}", json);
+    Ok(())
+}
+
+/// What a command changed, printed as the `result` kind in JSON mode.
+#[derive(Serialize)]
+struct Outcome<'a> {
+    action: &'a str,
+    ids: &'a [i64],
+    #[serde(skip_serializing_if = "serde_json::Value::is_null")]
+    state: serde_json::Value,
+}
+
+/// Prints the result of a command that changed something: `action` taken,
+/// the todo (or workspace, invitation, ...) `ids` it affected, and their
+/// new `state`, if any. Does nothing outside JSON mode, where [`success`]
+/// says the same in prose.
+pub fn print_result(action: &str, ids: &[i64], state: impl Serialize) -> Result<()> {
+    if !json() {
+        return Ok(());
+    }
+    let outcome = Outcome {
+        action,
+        ids,
+        state: serde_json::to_value(state)?,
+    };
+    print_json("result", &outcome)
+}
+
+/// Prints a todo a command just changed: in full for text formats, or as a
+/// result naming `action` in JSON mode.
+pub fn print_changed(action: &str, todo: &Todo, format: &str) -> Result<()> {
+    if json() {
+        print_result(action, &[todo.id], todo)
+    } else {
+        print_todo(todo, format)
+    }
+}
+
+/// Prints the confirmation that ends a successful command.
+pub fn success(message: &str) {
+    say!("{}", with_icon("✅", message).green());
+}
+
+/// Width of the longest accessible status words, `PENDING:` and `OVERDUE:`.
+const STATUS_WORD_WIDTH: usize = 8;
+
+/// Status marker for a list row: a symbol, or a padded word when accessible.
+fn status_marker(todo: &Todo, now: DateTime<Utc>) -> String {
+    let word = |word: &str| format!("{:<width$}", word, width = STATUS_WORD_WIDTH);
+    let status = todo.status();
+    if status == TodoStatus::Done {
+        let marker = match (accessible(), emoji()) {
+            (true, _) => word("DONE:"),
+            (false, true) => "✓".to_string(),
+            (false, false) => "x".to_string(),
+        };
+        theme().completed.paint(&marker)
+    } else if status == TodoStatus::Blocked {
+        let marker = match (accessible(), emoji()) {
+            (true, _) => word("BLOCKED:"),
+            (false, true) => "⊘".to_string(),
+            (false, false) => "#".to_string(),
+        };
+        theme().blocked.paint(&marker)
+    } else if todo.is_overdue(now) {
+        let marker = if accessible() { word("OVERDUE:") } else { "!".to_string() };
+        theme().overdue.paint(&marker)
+    } else if status == TodoStatus::InProgress {
+        let marker = match (accessible(), emoji()) {
+            (true, _) => word("STARTED:"),
+            (false, true) => "◐".to_string(),
+            (false, false) => ">".to_string(),
+        };
+        theme().in_progress.paint(&marker)
+    } else {
+        let marker = match (accessible(), emoji()) {
+            (true, _) => word("PENDING:"),
+            (false, true) => "○".to_string(),
+            (false, false) => "o".to_string(),
+        };
+        theme().pending.paint(&marker)
+    }
+}
+
+/// How much of each todo a list row shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layout {
+    /// ID and title only, for narrow terminals
+    Compact,
+    /// Status, ID and title
+    Normal,
+    /// Every column, including timestamps
+    Wide,
+}
+
+impl Layout {
+    /// Picks a layout for a terminal `width` columns wide; output that isn't
+    /// going to a terminal gets the normal layout.
+    fn for_width(width: Option<usize>) -> Self {
+        match width {
+            Some(width) if width < 60 => Layout::Compact,
+            Some(width) if width >= 120 => Layout::Wide,
+            _ => Layout::Normal,
+        }
+    }
+}
+
+/// Presentation overrides for `print_todos`.
+#[derive(Debug, Default)]
+pub struct ListOptions {
+    /// Forces a layout instead of choosing one from the terminal width
+    pub layout: Option<Layout>,
+    /// Wrap long titles instead of truncating them
+    pub no_truncate: bool,
+    /// Local short aliases shown next to the IDs
+    pub aliases: HashMap<i64, String>,
+    /// Leave out the header row of CSV/TSV output
+    pub no_header: bool,
+    /// CSV/TSV columns, in order; empty means the defaults
+    pub fields: Vec<Field>,
+    /// Open dependencies of todos that are waiting on them
+    pub blocked_by: HashMap<i64, Vec<i64>>,
+}
+
+/// A todo attribute selectable as a CSV/TSV column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+#[value(rename_all = "snake_case")]
+pub enum Field {
+    Id,
+    Title,
+    Completed,
+    CreatedAt,
+    UpdatedAt,
+    /// Workflow status; not a default column, so existing scripts keep
+    /// their layout
+    Status,
+}
+
+impl Field {
+    const DEFAULT: [Field; 5] = [
+        Field::Id,
+        Field::Title,
+        Field::Completed,
+        Field::CreatedAt,
+        Field::UpdatedAt,
+    ];
+
+    /// Header name, matching the JSON field.
+    fn name(self) -> &'static str {
+        match self {
+            Field::Id => "id",
+            Field::Title => "title",
+            Field::Completed => "completed",
+            Field::CreatedAt => "created_at",
+            Field::UpdatedAt => "updated_at",
+            Field::Status => "status",
         }
+    }
+
+    fn value(self, todo: &Todo) -> String {
+        match self {
+            Field::Id => todo.id.to_string(),
+            Field::Title => todo.title.clone(),
+            Field::Completed => todo.completed.to_string(),
+            Field::CreatedAt => todo.created_at.clone(),
+            Field::UpdatedAt => todo.updated_at.clone(),
+            Field::Status => todo.status().to_string(),
+        }
+    }
+}
+
+pub fn print_todos(todos: &[Todo], format: &str, options: &ListOptions) -> Result<()> {
+    match format {
+        "json" => print_json("todo_list", todos)?,
+        "csv" => print_delimited(todos, b',', options)?,
+        "tsv" => print_delimited(todos, b'\t', options)?,
+        "markdown" | "md" => print!("{}", render_markdown(todos)),
         _ => {
             if todos.is_empty() {
-                println!("{}", "No todos found.".dimmed());
+                say!("{}", "No todos found.".dimmed());
                 return Ok(());
             }
 
-            println!("{}", format!("📋 {} todos:", todos.len()).bold());
-            println!();
+            let header = with_icon("📋", &format!("{} todos:", todos.len()));
+            say!("{}", theme().header.paint(&header));
+            say!();
 
+            let columns = Columns::new(todos, options);
             for todo in todos {
-                print_todo_line(todo);
+                columns.print_row(todo);
             }
         }
     }
     Ok(())
 }
 
+/// Prints an agenda: overdue carryovers first, then each day in it.
+pub fn print_agenda(agenda: &Agenda, format: &str, options: &ListOptions) -> Result<()> {
+    if format == "json" {
+        return print_json("agenda", agenda);
+    }
+
+    if agenda.overdue.is_empty() && agenda.due.is_empty() {
+        let span = if agenda.until == agenda.date {
+            locale::weekday_date(agenda.date)
+        } else {
+            format!("{} – {}", locale::date(agenda.date), locale::date(agenda.until))
+        };
+        say!("{}", format!("Nothing due {}.", span).dimmed());
+        return Ok(());
+    }
+
+    let mut sections = vec![(format!("Overdue ({})", agenda.overdue.len()), &agenda.overdue[..])];
+    // One section per day; `due` is sorted, so each day's todos are adjacent
+    let local_day = |todo: &Todo| todo.due().map(|at| at.with_timezone(&Local).date_naive());
+    let mut rest = &agenda.due[..];
+    while let Some(first) = rest.first() {
+        let day = local_day(first);
+        let count = rest.iter().take_while(|todo| local_day(todo) == day).count();
+        let (todos, tail) = rest.split_at(count);
+        let day = locale::weekday_date(day.unwrap_or(agenda.date));
+        sections.push((format!("Due {} ({})", day, todos.len()), todos));
+        rest = tail;
+    }
+    for (heading, todos) in sections {
+        if todos.is_empty() {
+            continue;
+        }
+        say!("{}", theme().header.paint(&heading));
+        let columns = Columns::new(todos, options);
+        for todo in todos {
+            columns.print_row(todo);
+        }
+        say!();
+    }
+    if agenda.estimated_minutes > 0 {
+        say!("{} {}", "Estimated effort:".dimmed(), due::format_effort(agenda.estimated_minutes));
+    }
+    Ok(())
+}
+
 pub fn print_todo(todo: &Todo, format: &str) -> Result<()> {
     match format {
-        "json" => {
-            println!("{}", serde_json::to_string_pretty(todo)?);
+        "json" => print_json("todo", todo)?,
+        "csv" | "tsv" | "markdown" | "md" => {
+            print_todos(std::slice::from_ref(todo), format, &ListOptions::default())?;
         }
         _ => {
             print_todo_detail(todo);
@@ -37,41 +449,393 @@ pub fn print_todo(todo: &Todo, format: &str) -> Result<()> {
     Ok(())
 }
 
-fn print_todo_line(todo: &Todo) {
-    let status = if todo.completed {
-        "✓".green()
-    } else {
-        "○".yellow()
-    };
+/// Renders a GitHub-style task list, one `- [ ] title (#id)` line per todo.
+pub fn render_markdown(todos: &[Todo]) -> String {
+    let mut out = String::new();
+    for todo in todos {
+        let check = if todo.completed { "x" } else { " " };
+        // A newline in a title would end the list item early
+        let title = todo.title.split_whitespace().collect::<Vec<_>>().join(" ");
+        match todo.due() {
+            Some(due) => out.push_str(&format!(
+                "- [{}] {} (#{}, due {})\n",
+                check,
+                title,
+                todo.id,
+                due.format("%Y-%m-%d")
+            )),
+            None => out.push_str(&format!("- [{}] {} (#{})\n", check, title, todo.id)),
+        }
+    }
+    out
+}
 
-    let title = if todo.completed {
-        todo.title.strikethrough().dimmed().to_string()
+/// Writes one row per todo, quoting fields as needed, for spreadsheets and
+/// `awk`/`cut` pipelines.
+fn print_delimited(todos: &[Todo], delimiter: u8, options: &ListOptions) -> Result<()> {
+    let fields = if options.fields.is_empty() {
+        &Field::DEFAULT[..]
     } else {
-        todo.title.clone()
+        &options.fields[..]
     };
 
-    println!("  {} #{} {}", status, todo.id.to_string().dimmed(), title);
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(delimiter)
+        .from_writer(std::io::stdout().lock());
+    if !options.no_header {
+        writer.write_record(fields.iter().map(|field| field.name()))?;
+    }
+    for todo in todos {
+        writer.write_record(fields.iter().map(|field| field.value(todo)))?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Renders `data` as a QR code using half-block characters, two modules per
+/// line, with the quiet zone a phone camera needs.
+pub fn print_qr(data: &str) -> Result<()> {
+    let code = qrcode::QrCode::new(data.as_bytes()).context("Link is too long for a QR code")?;
+    // Blocks are drawn in the foreground color, which is light on the usual
+    // dark terminal, so light modules get the blocks
+    let image = code
+        .render::<qrcode::render::unicode::Dense1x2>()
+        .dark_color(qrcode::render::unicode::Dense1x2::Light)
+        .light_color(qrcode::render::unicode::Dense1x2::Dark)
+        .quiet_zone(true)
+        .build();
+    say!("{}", image);
+    Ok(())
+}
+
+/// Prints the fields an update would change on `todo`, old values in red and
+/// new ones in green.
+pub fn print_diff(
+    todo: &Todo,
+    title: Option<&str>,
+    completed: Option<bool>,
+    estimate: Option<i64>,
+    due: Option<DateTime<Utc>>,
+    tags: Option<&[String]>,
+) {
+    let arrow = if accessible() { "changes to" } else { "→" };
+    say!("{} {}", format!("#{}", todo.id).bold(), todo.title.dimmed());
+    if let Some(title) = title {
+        say!("  {}: {} {} {}", "title".dimmed(), todo.title.red(), arrow, title.green());
+    }
+    if let Some(completed) = completed {
+        say!(
+            "  {}: {} {} {}",
+            "completed".dimmed(),
+            todo.completed.to_string().red(),
+            arrow,
+            completed.to_string().green()
+        );
+    }
+    if let Some(minutes) = estimate {
+        let old = todo.estimate_minutes.map_or("none".to_string(), due::format_effort);
+        say!(
+            "  {}: {} {} {}",
+            "estimate".dimmed(),
+            old.red(),
+            arrow,
+            due::format_effort(minutes).green()
+        );
+    }
+    if let Some(due) = due {
+        let old = todo.due_at.as_deref().map_or("none".to_string(), format_datetime);
+        let new = format_datetime(&due.to_rfc3339());
+        say!("  {}: {} {} {}", "due".dimmed(), old.red(), arrow, new.green());
+    }
+    if let Some(tags) = tags {
+        let list = |tags: &[String]| match tags {
+            [] => "none".to_string(),
+            tags => tags.join(", "),
+        };
+        say!("  {}: {} {} {}", "tags".dimmed(), list(&todo.tags).red(), arrow, list(tags).green());
+    }
+}
+
+/// Column geometry shared by every row of one list.
+struct Columns<'a> {
+    layout: Layout,
+    now: DateTime<Utc>,
+    aliases: &'a HashMap<i64, String>,
+    blocked_by: &'a HashMap<i64, Vec<i64>>,
+    id_width: usize,
+    /// Display columns the title must fit in, when writing to a terminal
+    fit: Option<usize>,
+    /// Width the title is padded to so the wide layout's dates line up
+    pad: usize,
+    no_truncate: bool,
+}
+
+/// Width of a formatted date, e.g. 16 for "2025-12-11 10:00"
+fn date_width() -> usize {
+    format_datetime("2025-12-11T10:00:00Z").width()
+}
+
+impl<'a> Columns<'a> {
+    fn new(todos: &[Todo], options: &'a ListOptions) -> Self {
+        let width = terminal_width();
+        let layout = options.layout.unwrap_or_else(|| Layout::for_width(width));
+        let id_width = todos
+            .iter()
+            .map(|todo| id_label(todo, &options.aliases).width())
+            .max()
+            .unwrap_or(2);
+
+        let fixed = Self::indent(layout, id_width) + Self::suffix_width(layout);
+        let fit = width.map(|w| w.saturating_sub(fixed).max(10));
+        let pad = match layout {
+            Layout::Wide => fit.unwrap_or_else(|| {
+                todos.iter().map(|todo| todo.title.width()).max().unwrap_or(0)
+            }),
+            _ => 0,
+        };
+
+        Self {
+            layout,
+            now: Utc::now(),
+            aliases: &options.aliases,
+            blocked_by: &options.blocked_by,
+            id_width,
+            fit,
+            pad,
+            no_truncate: options.no_truncate,
+        }
+    }
+
+    /// Columns before the title: `#12 ` or `  ○ #12 `, with the status
+    /// spelled out (even when compact) in accessible mode.
+    fn indent(layout: Layout, id_width: usize) -> usize {
+        let status = if accessible() { STATUS_WORD_WIDTH + 1 } else { 1 + 1 };
+        match layout {
+            Layout::Compact if accessible() => status + id_width + 1,
+            Layout::Compact => id_width + 1,
+            Layout::Normal | Layout::Wide => 2 + status + id_width + 1,
+        }
+    }
+
+    /// Columns after the title: the created and updated dates when wide.
+    fn suffix_width(layout: Layout) -> usize {
+        match layout {
+            Layout::Wide => 2 * (2 + date_width()),
+            Layout::Compact | Layout::Normal => 0,
+        }
+    }
+
+    /// Prints one row. On a terminal, long titles are cut to fit with an
+    /// ellipsis, or wrapped under the title column when `no_truncate` is set.
+    fn print_row(&self, todo: &Todo) {
+        let status = status_marker(todo, self.now);
+        let overdue = todo.is_overdue(self.now);
+        let note = self.blocked_by.get(&todo.id).map(|ids| blocked_note(ids));
+        let note_width = note.as_ref().map_or(0, |note| note.width() + 1);
+
+        let style = |line: &str| {
+            if todo.completed && !accessible() {
+                line.strikethrough().dimmed().to_string()
+            } else if note.is_some() {
+                line.dimmed().to_string()
+            } else if overdue {
+                theme().overdue.paint(line)
+            } else {
+                line.to_string()
+            }
+        };
+
+        let lines: Vec<String> = match self.fit {
+            Some(fit) if self.no_truncate => textwrap::wrap(&todo.title, fit)
+                .iter()
+                .map(|line| line.to_string())
+                .collect(),
+            Some(fit) => vec![truncate(&todo.title, fit.saturating_sub(note_width).max(10))],
+            None => vec![todo.title.clone()],
+        };
+
+        let id = format!("{:<width$}", id_label(todo, self.aliases), width = self.id_width);
+        let mut lines = lines.iter();
+        let first = lines.next().map(String::as_str).unwrap_or_default();
+        let first = match note {
+            Some(ref note) => format!("{} {}", first, note),
+            None => first.to_string(),
+        };
+        let first = first.as_str();
+
+        match self.layout {
+            Layout::Compact if accessible() => {
+                say!("{} {} {}", status, theme().id.paint(&id), style(first))
+            }
+            Layout::Compact => say!("{} {}", theme().id.paint(&id), style(first)),
+            Layout::Normal => {
+                say!("  {} {} {}", status, theme().id.paint(&id), style(first))
+            }
+            Layout::Wide => {
+                let padding = " ".repeat(self.pad.saturating_sub(first.width()));
+                say!(
+                    "  {} {} {}{}  {}  {}",
+                    status,
+                    theme().id.paint(&id),
+                    style(first),
+                    padding,
+                    format_datetime(&todo.created_at).dimmed(),
+                    format_datetime(&todo.updated_at).dimmed()
+                );
+            }
+        }
+
+        let indent = " ".repeat(Self::indent(self.layout, self.id_width));
+        for line in lines {
+            say!("{}{}", indent, style(line));
+        }
+    }
+}
+
+/// `⛔ blocked by #3`, after the title of a todo waiting on others.
+fn blocked_note(ids: &[i64]) -> String {
+    let note = format!("blocked by {}", depends::list(ids));
+    match (accessible(), emoji()) {
+        (true, _) => format!("({})", note),
+        (false, true) => format!("⛔ {}", note),
+        (false, false) => format!("[{}]", note),
+    }
+}
+
+/// `#12`, or `a3 #12` when the todo has an alias.
+fn id_label(todo: &Todo, aliases: &HashMap<i64, String>) -> String {
+    match aliases.get(&todo.id) {
+        Some(alias) => format!("{} #{}", alias, todo.id),
+        None => format!("#{}", todo.id),
+    }
+}
+
+/// Terminal width in columns, or `None` when stdout isn't a terminal.
+fn terminal_width() -> Option<usize> {
+    terminal_size().map(|(Width(width), _)| width as usize)
+}
+
+/// Shortens `text` to at most `width` display columns, ending in `…` when
+/// anything was cut. Wide CJK characters and emoji count as two columns.
+fn truncate(text: &str, width: usize) -> String {
+    if text.width() <= width {
+        return text.to_string();
+    }
+
+    let budget = width.saturating_sub(1);
+    let mut used = 0;
+    let mut out = String::new();
+    for c in text.chars() {
+        let w = UnicodeWidthChar::width(c).unwrap_or(0);
+        if used + w > budget {
+            break;
+        }
+        used += w;
+        out.push(c);
+    }
+    out.push('…');
+    out
 }
 
 fn print_todo_detail(todo: &Todo) {
-    let status = if todo.completed {
-        "Completed".green()
-    } else {
-        "Pending".yellow()
+    let status = match todo.status() {
+        TodoStatus::Done => theme().completed.paint("Completed"),
+        TodoStatus::InProgress => theme().in_progress.paint("In progress"),
+        TodoStatus::Blocked => match todo.blocked_reason {
+            Some(ref reason) => theme().blocked.paint(&format!("Blocked: {}", reason)),
+            None => theme().blocked.paint("Blocked"),
+        },
+        TodoStatus::Todo => theme().pending.paint("Pending"),
     };
 
-    println!("{}", "─".repeat(40).dimmed());
-    println!("  {} #{}", "Todo".bold(), todo.id);
-    println!("  {}: {}", "Title".dimmed(), todo.title);
-    println!("  {}: {}", "Status".dimmed(), status);
-    println!("  {}: {}", "Created".dimmed(), format_datetime(&todo.created_at));
-    println!("  {}: {}", "Updated".dimmed(), format_datetime(&todo.updated_at));
-    println!("{}", "─".repeat(40).dimmed());
+    let rule = if accessible() { String::new() } else { "─".repeat(40) };
+    say!("{}", rule.dimmed());
+    say!("  {} {}", theme().header.paint("Todo"), theme().id.paint(&format!("#{}", todo.id)));
+    say!("  {}: {}", "Title".dimmed(), todo.title);
+    say!("  {}: {}", "Status".dimmed(), status);
+    say!("  {}: {}", "Created".dimmed(), format_datetime(&todo.created_at));
+    say!("  {}: {}", "Updated".dimmed(), format_datetime(&todo.updated_at));
+    if !todo.tags.is_empty() {
+        say!("  {}: {}", "Tags".dimmed(), todo.tags.join(", "));
+    }
+    if let Some(minutes) = todo.estimate_minutes {
+        say!("  {}: {}", "Estimate".dimmed(), due::format_effort(minutes));
+    }
+    if !todo.depends_on.is_empty() {
+        say!("  {}: {}", "Depends on".dimmed(), depends::list(&todo.depends_on));
+    }
+    for link in &todo.links {
+        say!("  {}: {}", "Link".dimmed(), links::describe(link));
+    }
+    let backlinks = links::backlinks(todo.id);
+    if !backlinks.is_empty() {
+        say!("  {}: {}", "Linked from".dimmed(), backlinks.join(", "));
+    }
+    if let Some(ref due) = todo.due_at {
+        let due = format_datetime(due);
+        if todo.is_overdue(Utc::now()) {
+            let label = if accessible() { format!("{} (overdue)", due) } else { due };
+            say!("  {}: {}", "Due".dimmed(), theme().overdue.paint(&label));
+        } else {
+            say!("  {}: {}", "Due".dimmed(), due);
+        }
+    }
+    say!("{}", rule.dimmed());
 }
 
 fn format_datetime(dt: &str) -> String {
     // Try to parse and format nicely, fallback to original
     chrono::DateTime::parse_from_rfc3339(dt)
-        .map(|d| d.format("%Y-%m-%d %H:%M").to_string())
+        .map(|d| locale::datetime(&d))
         .unwrap_or_else(|_| dt.to_string())
 }
+
+/// Prints a command failure, expanding API error metadata when present.
+pub fn print_error(err: &anyhow::Error) {
+    eprintln!("{} {:#}", "Error:".red().bold(), err);
+
+    if let Some(invalid) = err.downcast_ref::<InvalidInput>() {
+        print_field_errors(&invalid.fields);
+    }
+
+    let Some(api_err) = err.downcast_ref::<ApiError>() else {
+        return;
+    };
+
+    print_field_errors(api_err.fields());
+
+    if let Some(client_err) = api_err.client_error() {
+        let status = match client_err.code {
+            Some(ref code) => format!("{} ({})", client_err.status, code),
+            None => client_err.status.to_string(),
+        };
+        eprintln!("  {}: {}", "Status".dimmed(), status);
+
+        if let Some(ref request_id) = client_err.request_id {
+            eprintln!("  {}: {}", "Request ID".dimmed(), request_id);
+        }
+    }
+
+    if let Some(hint) = hint(api_err) {
+        eprintln!("{}", hint.dimmed());
+    }
+}
+
+/// What the user can do about an API failure, when there's something.
+fn hint(err: &ApiError) -> Option<&'static str> {
+    match err {
+        ApiError::Unauthorized(_) => Some("Log in again with `todo auth login`."),
+        ApiError::RateLimited(_) => Some("Rate limited; `todo limits` shows when it resets."),
+        ApiError::Conflict(_) => Some("The todo changed in the meantime; fetch it and try again."),
+        ApiError::ReadOnly(_) => Some("Nothing was sent; turn off read-only mode to make changes."),
+        ApiError::Network(_) => Some("Check the API URL and that the server is up."),
+        err if err.is_retryable() => Some("This looks temporary; retrying may succeed."),
+        _ => None,
+    }
+}
+
+fn print_field_errors(fields: &[FieldError]) {
+    for field in fields {
+        eprintln!("  • {}: {}", field.field.bold(), field.message);
+    }
+}
@@ -0,0 +1,97 @@
+//! Places: tags like `@home` or `@office` that say where a todo can be
+//! done. `todo list --at home` narrows to them, and places given
+//! coordinates in the `[places]` config section carry them into ICS
+//! export, where phone calendars can remind on arrival.
+
+use anyhow::{Result, bail};
+use serde_json::json;
+
+use crate::api::Todo;
+use crate::config::Config;
+use crate::output;
+use crate::style::Colorize;
+
+/// Coordinates of a place, written `lat,lon` in the config.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Geo {
+    pub lat: f64,
+    pub lon: f64,
+}
+
+impl Geo {
+    pub fn parse(s: &str) -> Result<Self> {
+        let Some((lat, lon)) = s.split_once(',') else {
+            bail!("'{}' isn't a position like 52.5200,13.4050", s);
+        };
+        let (Ok(lat), Ok(lon)) = (lat.trim().parse::<f64>(), lon.trim().parse::<f64>()) else {
+            bail!("'{}' isn't a position like 52.5200,13.4050", s);
+        };
+        if !(-90.0..=90.0).contains(&lat) || !(-180.0..=180.0).contains(&lon) {
+            bail!("'{}' is off the map: latitude is ±90, longitude ±180", s);
+        }
+        Ok(Self { lat, lon })
+    }
+}
+
+/// The `--at` argument: `@home` or `home` as the tag `home`.
+pub fn parse_name(s: &str) -> Result<String, String> {
+    let name = s.trim().trim_start_matches('@');
+    if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_') {
+        return Err("places are tags, e.g. @home: letters, digits, '-' and '_'".to_string());
+    }
+    Ok(name.to_string())
+}
+
+/// Whether `todo` is tagged with the place `name`.
+pub fn is_at(todo: &Todo, name: &str) -> bool {
+    todo.tags.iter().any(|tag| tag == name)
+}
+
+/// The first of the todo's tags that is a place with coordinates. Entries
+/// that no longer parse are skipped rather than failing an export.
+pub fn located<'a>(todo: &'a Todo, config: &Config) -> Option<(&'a str, Geo)> {
+    todo.tags.iter().find_map(|tag| {
+        let geo = Geo::parse(config.places.get(tag)?).ok()?;
+        Some((tag.as_str(), geo))
+    })
+}
+
+pub fn add(name: &str, position: &str) -> Result<()> {
+    let name = parse_name(name).map_err(anyhow::Error::msg)?;
+    let geo = Geo::parse(position)?;
+    let position = format!("{},{}", geo.lat, geo.lon);
+
+    let replaced = Config::update(|config| {
+        Ok(config.places.insert(name.clone(), position.clone()).is_some())
+    })?;
+    let verb = if replaced { "moved" } else { "added" };
+    output::success(&format!("Place @{} {}; tag todos with it to use it.", name, verb));
+    output::print_result("place_saved", &[], json!({ "name": name, "position": position }))
+}
+
+pub fn list(config: &Config) -> Result<()> {
+    if output::json() {
+        return output::print_json("place_list", &config.places);
+    }
+    if config.places.is_empty() {
+        println!("{}", "No places; add one with `todo place add`.".dimmed());
+        return Ok(());
+    }
+
+    let width = config.places.keys().map(|name| name.chars().count()).max().unwrap_or(0);
+    for (name, position) in &config.places {
+        println!("  @{:<width$}  {}", name, position);
+    }
+    Ok(())
+}
+
+/// Forgets a place's coordinates; todos keep the tag.
+pub fn remove(name: &str) -> Result<()> {
+    let name = name.trim_start_matches('@');
+    Config::update(|config| match config.places.remove(name) {
+        Some(_) => Ok(()),
+        None => bail!("No place @{}; see `todo place list`", name),
+    })?;
+    output::success(&format!("Place @{} removed.", name));
+    output::print_result("place_removed", &[], json!({ "name": name }))
+}
@@ -0,0 +1,97 @@
+use std::io::{self, IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use anyhow::Result;
+
+static INTERACTIVE: AtomicBool = AtomicBool::new(true);
+
+/// A prompt that non-interactive mode refused to show. `main` exits with a
+/// dedicated status for it so scripts can tell it apart from API failures.
+#[derive(Debug, thiserror::Error)]
+#[error("{0}")]
+pub struct InputRequired(pub String);
+
+/// Turns prompts into [`InputRequired`] errors. Always off when stdin isn't
+/// a terminal, since nobody could answer.
+pub fn set_interactive(on: bool) {
+    INTERACTIVE.store(on && io::stdin().is_terminal(), Ordering::Relaxed);
+}
+
+pub fn interactive() -> bool {
+    INTERACTIVE.load(Ordering::Relaxed)
+}
+
+pub fn required(message: impl Into<String>) -> anyhow::Error {
+    InputRequired(message.into()).into()
+}
+
+/// Prints `label` and reads one trimmed line from stdin, falling back to
+/// `default` when the answer is empty.
+pub fn input(label: &str, default: Option<&str>) -> Result<String> {
+    require_interactive(label)?;
+    match default {
+        Some(default) => print!("{} [{}]: ", label, default),
+        None => print!("{}: ", label),
+    }
+    let line = read_line()?;
+
+    Ok(if line.is_empty() {
+        default.unwrap_or_default().to_string()
+    } else {
+        line
+    })
+}
+
+/// Asks a yes/no question; an empty answer picks `default`.
+pub fn confirm(question: &str, default: bool) -> Result<bool> {
+    require_interactive(question)?;
+    let hint = if default { "[Y/n]" } else { "[y/N]" };
+    print!("{} {} ", question, hint);
+    let answer = read_line()?;
+
+    Ok(match answer.to_lowercase().as_str() {
+        "" => default,
+        "y" | "yes" => true,
+        _ => false,
+    })
+}
+
+/// Shows `prompt` and reads one keypress, lowercased, without waiting for
+/// Enter. Ctrl+C reads as `q`, since the terminal is in raw mode and no
+/// signal arrives.
+pub fn key(prompt: &str) -> Result<char> {
+    require_interactive(prompt)?;
+    print!("{} ", prompt);
+    io::stdout().flush()?;
+    let key = match console::Term::stdout().read_char() {
+        Ok(key) => key.to_ascii_lowercase(),
+        Err(err) if err.kind() == io::ErrorKind::Interrupted => 'q',
+        Err(err) => return Err(err.into()),
+    };
+    println!("{}", key);
+    Ok(key)
+}
+
+/// Reads a password without echoing it.
+pub fn password(prompt: &str) -> Result<String> {
+    require_interactive(prompt)?;
+    Ok(rpassword::prompt_password(prompt)?)
+}
+
+/// Fails instead of waiting on a stdin nobody is typing into (CI, cron).
+fn require_interactive(prompt: &str) -> Result<()> {
+    if !interactive() {
+        return Err(required(format!(
+            "Can't ask \"{}\" in non-interactive mode",
+            prompt.trim_end_matches([':', ' '])
+        )));
+    }
+    Ok(())
+}
+
+fn read_line() -> Result<String> {
+    io::stdout().flush()?;
+    let mut line = String::new();
+    io::stdin().read_line(&mut line)?;
+    Ok(line.trim().to_string())
+}
@@ -0,0 +1,197 @@
+//! `todo purge`: bulk cleanup of completed todos, deleted in batches paced
+//! to stay under the server's rate limit.
+
+use std::time::Duration;
+
+use anyhow::{Result, bail};
+use chrono::{DateTime, Utc};
+use indicatif::{ProgressBar, ProgressStyle};
+
+use crate::aliases::Aliases;
+use crate::bulk;
+use crate::api::{RequestOptions, Todo, TodoApi};
+use crate::error::ApiError;
+use crate::history;
+use crate::journal::{Journal, Operation};
+use crate::output::{self, Layout, ListOptions};
+use crate::prompt;
+use crate::search;
+use crate::validation::Limits;
+
+/// Deletes sent concurrently before pausing.
+const BATCH_SIZE: usize = 10;
+/// Pause between batches when the server doesn't advertise a rate limit.
+const DEFAULT_PAUSE: Duration = Duration::from_secs(1);
+
+/// Deletes completed todos last changed more than `older_than` ago (all of
+/// them when `None`), after listing them and asking unless `yes`. Progress
+/// is journaled for `todo resume`.
+pub async fn completed<A: TodoApi>(
+    client: &A,
+    older_than: Option<chrono::Duration>,
+    yes: bool,
+    aliases: &mut Aliases,
+) -> Result<()> {
+    let opts = RequestOptions::new().retries(2);
+    let mut todos = client.list_todos(Some(true), &opts).await?;
+    // The server may ignore the filter, and purging an open todo loses it
+    todos.retain(|todo| todo.completed);
+    if let Some(age) = older_than {
+        let cutoff = Utc::now() - age;
+        // The API has no completion time, so the last update stands in for it
+        todos.retain(|todo| updated(todo).is_some_and(|at| at < cutoff));
+    }
+    if todos.is_empty() {
+        output::say!("Nothing to purge.");
+        return output::print_result("purged", &[], ());
+    }
+
+    let options = ListOptions {
+        layout: Some(Layout::Compact),
+        aliases: aliases.by_id(),
+        ..Default::default()
+    };
+    output::print_todos(&todos, "text", &options)?;
+    if !yes {
+        if !prompt::interactive() {
+            return Err(prompt::required("Not purging without confirmation; pass --yes"));
+        }
+        let question = format!("Delete these {} todos?", todos.len());
+        if !prompt::confirm(&question, false)? {
+            output::say!("Cancelled.");
+            let ids: Vec<_> = todos.iter().map(|todo| todo.id).collect();
+            return output::print_result("cancelled", &ids, ());
+        }
+    }
+
+    let ids = todos.iter().map(|todo| todo.id).collect();
+    let journal = Journal::start(Operation::Purge { ids })?;
+    let todos = todos
+        .into_iter()
+        .map(|todo| (todo.id, Some(todo.title)))
+        .collect();
+    delete(client, todos, aliases, journal).await
+}
+
+/// Continues an interrupted purge of the todos confirmed when it started,
+/// without asking again. Those deleted since count as done.
+pub async fn resume<A: TodoApi>(
+    client: &A,
+    ids: Vec<i64>,
+    aliases: &mut Aliases,
+    journal: Journal,
+) -> Result<()> {
+    let todos = ids
+        .into_iter()
+        .filter(|id| !journal.is_done(&id.to_string()))
+        .map(|id| (id, None))
+        .collect();
+    delete(client, todos, aliases, journal).await
+}
+
+/// Deletes `todos` (IDs, with titles for the history when known) in paced
+/// batches, journaling each one.
+async fn delete<A: TodoApi>(
+    client: &A,
+    todos: Vec<(i64, Option<String>)>,
+    aliases: &mut Aliases,
+    mut journal: Journal,
+) -> Result<()> {
+    let limits = client
+        .limits(&RequestOptions::new().timeout(Duration::from_secs(2)))
+        .await
+        .unwrap_or_default();
+    let pause = pause(&limits);
+    let progress = ProgressBar::new(todos.len() as u64).with_style(
+        ProgressStyle::with_template("{bar:30} {pos}/{len} deleted")
+            .expect("progress template is valid"),
+    );
+
+    let opts = RequestOptions::default();
+    let mut deleted = Vec::new();
+    let mut failed = 0;
+    let _running = bulk::running();
+    for (i, batch) in todos.chunks(BATCH_SIZE).enumerate() {
+        // Ctrl+C lets the batch in flight finish
+        if bulk::cancelled() {
+            break;
+        }
+        if i > 0 {
+            tokio::time::sleep(pause).await;
+        }
+        let results = futures::future::join_all(
+            batch.iter().map(|&(id, _)| client.delete_todo(id, &opts)),
+        )
+        .await;
+        for ((id, title), result) in batch.iter().zip(results) {
+            let result = match result {
+                // Gone already, e.g. deleted before an interrupted run died
+                Err(ApiError::NotFound { .. }) => Ok(()),
+                result => result,
+            };
+            match result {
+                Ok(()) => {
+                    journal.record(id.to_string(), Some(*id))?;
+                    history::record_deleted(*id, title.clone());
+                    aliases.forget(*id);
+                    deleted.push(*id);
+                }
+                Err(err) => {
+                    failed += 1;
+                    let err = anyhow::Error::from(err).context(format!("Todo #{}", id));
+                    progress.suspend(|| output::print_error(&err));
+                }
+            }
+            progress.inc(1);
+        }
+    }
+    progress.finish_and_clear();
+    aliases.save();
+    search::forget(&deleted);
+
+    journal.close(failed == 0 && !bulk::cancelled())?;
+    if failed > 0 {
+        bail!("{} of {} deletes failed", failed, todos.len());
+    }
+    if bulk::cancelled() {
+        output::say!("Purged {} of {} completed todos before stopping.", deleted.len(), todos.len());
+    } else {
+        output::success(&format!("Purged {} completed todos.", deleted.len()));
+    }
+    output::print_result("purged", &deleted, ())
+}
+
+fn updated(todo: &Todo) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(&todo.updated_at)
+        .ok()
+        .map(|at| at.with_timezone(&Utc))
+}
+
+/// Spreads batches so they average out to the advertised requests per
+/// minute.
+fn pause(limits: &Limits) -> Duration {
+    match limits.requests_per_minute {
+        Some(per_minute) if per_minute > 0 => {
+            Duration::from_secs_f64(BATCH_SIZE as f64 * 60.0 / per_minute as f64)
+        }
+        _ => DEFAULT_PAUSE,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fake::{self, FakeApi};
+
+    #[tokio::test]
+    async fn open_todos_survive_a_purge() {
+        fake::isolate();
+        let client = FakeApi::with_todos(&["done", "open", "also done"]);
+        client.edit(1, |todo| todo.completed = true);
+        client.edit(3, |todo| todo.completed = true);
+
+        completed(&client, None, true, &mut Aliases::load()).await.unwrap();
+        let titles: Vec<_> = client.todos().into_iter().map(|todo| todo.title).collect();
+        assert_eq!(titles, ["open"]);
+    }
+}
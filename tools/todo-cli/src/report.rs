@@ -0,0 +1,210 @@
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use printpdf::{
+    BuiltinFont, IndirectFontRef, Mm, PdfDocument, PdfDocumentReference, PdfLayerReference,
+};
+
+use crate::api::Todo;
+
+const HTML_TEMPLATE: &str = include_str!("../templates/report.html");
+
+/// Counts shown at the top of every report.
+#[derive(Debug)]
+pub struct Summary {
+    pub total: usize,
+    pub completed: usize,
+}
+
+impl Summary {
+    pub fn of(todos: &[Todo]) -> Self {
+        Self {
+            total: todos.len(),
+            completed: todos.iter().filter(|todo| todo.completed).count(),
+        }
+    }
+
+    pub fn pending(&self) -> usize {
+        self.total - self.completed
+    }
+
+    /// Share of todos completed, 0–100.
+    pub fn percent_done(&self) -> usize {
+        (self.completed * 100).checked_div(self.total).unwrap_or(0)
+    }
+}
+
+/// Todos split into the groups a report shows, pending first.
+pub fn groups(todos: &[Todo]) -> [(&'static str, Vec<&Todo>); 2] {
+    let (completed, pending): (Vec<_>, Vec<_>) = todos.iter().partition(|todo| todo.completed);
+    [("Pending", pending), ("Completed", completed)]
+}
+
+/// Renders a standalone HTML page, with inline styles and no external
+/// assets, so it can be mailed or dropped on a file share as-is.
+pub fn render_html(todos: &[Todo], source: &str, generated: DateTime<Local>) -> String {
+    let summary = Summary::of(todos);
+    let stats = format!(
+        "<div class=\"stats\">\n{}{}{}</div>\n\
+         <div class=\"bar\"><div style=\"width: {}%\"></div></div>\n",
+        stat(summary.total, "total"),
+        stat(summary.pending(), "pending"),
+        stat(summary.completed, &format!("completed ({}%)", summary.percent_done())),
+        summary.percent_done(),
+    );
+
+    let mut tables = String::new();
+    for (name, todos) in groups(todos) {
+        if todos.is_empty() {
+            continue;
+        }
+        tables.push_str(&format!("<h2>{} ({})</h2>\n<table>\n", name, todos.len()));
+        tables.push_str("<tr><th>ID</th><th>Title</th><th>Created</th><th>Updated</th></tr>\n");
+        for todo in todos {
+            tables.push_str(&format!(
+                "<tr class=\"{}\"><td class=\"id\">#{}</td><td class=\"title\">{}</td>\
+                 <td class=\"date\">{}</td><td class=\"date\">{}</td></tr>\n",
+                if todo.completed { "completed" } else { "pending" },
+                todo.id,
+                escape(&todo.title),
+                escape(&date(&todo.created_at)),
+                escape(&date(&todo.updated_at)),
+            ));
+        }
+        tables.push_str("</table>\n");
+    }
+
+    HTML_TEMPLATE
+        .replace("{{generated}}", &generated.format("%Y-%m-%d %H:%M").to_string())
+        .replace("{{source}}", &escape(source))
+        .replace("{{summary}}", &stats)
+        .replace("{{groups}}", &tables)
+}
+
+/// A4 portrait, in millimetres.
+const PAGE_WIDTH: f32 = 210.0;
+const PAGE_HEIGHT: f32 = 297.0;
+const MARGIN: f32 = 20.0;
+const LINE_HEIGHT: f32 = 6.0;
+/// Characters of 11pt Helvetica that fit between the margins, roughly.
+const TITLE_CHARS: usize = 80;
+
+/// Renders a paginated A4 document: the summary, then each group as an
+/// agenda-style checklist, with page numbers in the footer.
+pub fn render_pdf(todos: &[Todo], source: &str, generated: DateTime<Local>) -> Result<Vec<u8>> {
+    let (doc, page, layer) =
+        PdfDocument::new("Todo report", Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Layer 1");
+    let regular = doc.add_builtin_font(BuiltinFont::Helvetica)?;
+    let bold = doc.add_builtin_font(BuiltinFont::HelveticaBold)?;
+
+    let mut writer = PdfWriter {
+        doc: &doc,
+        layer: doc.get_page(page).get_layer(layer),
+        page: 1,
+        y: PAGE_HEIGHT - MARGIN,
+    };
+
+    writer.line("Todo report", 18.0, &bold);
+    writer.line(
+        &format!("Generated {} from {}", generated.format("%Y-%m-%d %H:%M"), source),
+        9.0,
+        &regular,
+    );
+    writer.gap();
+
+    let summary = Summary::of(todos);
+    writer.line(
+        &format!(
+            "{} total, {} pending, {} completed ({}%)",
+            summary.total,
+            summary.pending(),
+            summary.completed,
+            summary.percent_done()
+        ),
+        11.0,
+        &regular,
+    );
+
+    for (name, todos) in groups(todos) {
+        if todos.is_empty() {
+            continue;
+        }
+        writer.gap();
+        writer.line(&format!("{} ({})", name, todos.len()), 13.0, &bold);
+        for todo in todos {
+            let check = if todo.completed { "[x]" } else { "[ ]" };
+            let line = format!(
+                "{} #{}  {}  ({})",
+                check,
+                todo.id,
+                shorten(&todo.title, TITLE_CHARS),
+                date(&todo.updated_at)
+            );
+            writer.line(&line, 11.0, &regular);
+        }
+    }
+    writer.footer(&regular);
+
+    Ok(doc.save_to_bytes()?)
+}
+
+/// Lays text out top to bottom, starting a new page when one fills up.
+struct PdfWriter<'a> {
+    doc: &'a PdfDocumentReference,
+    layer: PdfLayerReference,
+    page: usize,
+    y: f32,
+}
+
+impl PdfWriter<'_> {
+    fn line(&mut self, text: &str, size: f32, font: &IndirectFontRef) {
+        if self.y < MARGIN + LINE_HEIGHT {
+            self.footer(font);
+            let (page, layer) = self.doc.add_page(Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Layer 1");
+            self.layer = self.doc.get_page(page).get_layer(layer);
+            self.page += 1;
+            self.y = PAGE_HEIGHT - MARGIN;
+        }
+        self.layer.use_text(text, size, Mm(MARGIN), Mm(self.y), font);
+        self.y -= LINE_HEIGHT * (size / 11.0).max(1.0);
+    }
+
+    fn gap(&mut self) {
+        self.y -= LINE_HEIGHT / 2.0;
+    }
+
+    fn footer(&self, font: &IndirectFontRef) {
+        let text = format!("Page {}", self.page);
+        self.layer.use_text(text, 9.0, Mm(PAGE_WIDTH / 2.0 - 5.0), Mm(MARGIN / 2.0), font);
+    }
+}
+
+/// Cuts `text` to `max` characters with an ASCII ellipsis; the built-in PDF
+/// fonts only cover Latin-1.
+fn shorten(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    let cut: String = text.chars().take(max.saturating_sub(3)).collect();
+    format!("{}...", cut)
+}
+
+fn stat(value: usize, label: &str) -> String {
+    format!(
+        "<div class=\"stat\"><div class=\"value\">{}</div><div class=\"label\">{}</div></div>\n",
+        value, label
+    )
+}
+
+/// `2025-12-11` from an RFC 3339 timestamp, or the input unchanged.
+fn date(timestamp: &str) -> String {
+    DateTime::parse_from_rfc3339(timestamp)
+        .map(|d| d.format("%Y-%m-%d").to_string())
+        .unwrap_or_else(|_| timestamp.to_string())
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,71 @@
+//! `todo append` and `todo prepend`: add to a title without retyping it.
+//! The change is based on the title as read, so it's sent with `If-Match`
+//! and redone from a fresh read when a server that versions todos says
+//! another write got there first.
+
+use std::time::Duration;
+
+use anyhow::Result;
+
+use crate::api::{RequestOptions, Todo, TodoApi};
+use crate::error::ApiError;
+use crate::validation::TodoInput;
+
+/// Reads and writes before giving up on a todo that keeps changing.
+const ATTEMPTS: u32 = 3;
+
+/// Which end of the title the text goes on.
+#[derive(Debug, Clone, Copy)]
+pub enum End {
+    Start,
+    End,
+}
+
+/// `title` with `text` added at `end` and `sep` between them, unless
+/// either already has it there or the title is empty.
+pub fn join(title: &str, text: &str, sep: &str, end: End) -> String {
+    if title.is_empty() {
+        return text.to_string();
+    }
+    match end {
+        End::End if title.ends_with(sep) || text.starts_with(sep) => format!("{}{}", title, text),
+        End::End => format!("{}{}{}", title, sep, text),
+        End::Start if text.ends_with(sep) || title.starts_with(sep) => format!("{}{}", text, title),
+        End::Start => format!("{}{}{}", text, sep, title),
+    }
+}
+
+/// Adds `text` to the title of todo `id` and returns the updated todo.
+pub async fn amend<A: TodoApi>(
+    client: &A,
+    id: i64,
+    text: &str,
+    sep: &str,
+    end: End,
+    opts: &RequestOptions,
+) -> Result<Todo> {
+    let limits = client
+        .limits(&RequestOptions::new().timeout(Duration::from_secs(2)))
+        .await
+        .unwrap_or_default();
+
+    let mut attempt = 1;
+    loop {
+        let current = client.get_todo(id, opts).await?;
+        let title = join(&current.title, text, sep, end);
+        let input = TodoInput {
+            title: Some(&title),
+            ..Default::default()
+        };
+        input.validate(&limits)?;
+
+        let write = opts.clone().if_match(current.updated_at);
+        match client.update_todo(id, Some(&title), None, &write).await {
+            Err(ApiError::Conflict(_)) if attempt < ATTEMPTS => {
+                tracing::debug!(attempt, "Todo changed since it was read; retrying");
+                attempt += 1;
+            }
+            result => return Ok(result?),
+        }
+    }
+}
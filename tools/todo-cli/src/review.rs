@@ -0,0 +1,145 @@
+//! `todo review`: a GTD-style daily review. Goes through what was completed
+//! yesterday, then what is due today along with overdue carryovers, one key
+//! per item, so each is confirmed, reopened or given a new date.
+
+use std::time::Duration as StdDuration;
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, Local, NaiveDate, Utc};
+
+use crate::api::{RequestOptions, Todo, TodoApi};
+use crate::bulk::Summary;
+use crate::history::{self, Action};
+use crate::style::Colorize;
+use crate::validation::Limits;
+use crate::{due, locale, output, prompt, stats, triage};
+
+const DONE_KEYS: &str = "[y] confirm  [r]eopen  [q]uit";
+const DUE_KEYS: &str = "[y] confirm  [d]efer a day  [r]eschedule  [c]omplete  [q]uit";
+
+enum Choice {
+    Confirm,
+    Reopen,
+    Due(DateTime<Utc>),
+    Complete,
+    Quit,
+}
+
+pub async fn run<A: TodoApi>(client: &A) -> Result<()> {
+    if !prompt::interactive() {
+        return Err(prompt::required("`todo review` reads keys from a terminal"));
+    }
+
+    let todos = client.list_todos(None, &RequestOptions::new().retries(2)).await?;
+    let limits = client
+        .limits(&RequestOptions::new().timeout(StdDuration::from_secs(2)))
+        .await
+        .unwrap_or_default();
+    let today = Local::now().date_naive();
+    let yesterday = today - Duration::days(1);
+
+    let done: Vec<Todo> = todos
+        .iter()
+        .filter(|todo| todo.completed && stats::completed_on(todo) == Some(yesterday))
+        .cloned()
+        .collect();
+    let agenda = due::Agenda::for_day(todos, today);
+    let due: Vec<Todo> = agenda
+        .overdue
+        .into_iter()
+        .chain(agenda.due)
+        .filter(|todo| !todo.completed)
+        .collect();
+    if done.is_empty() && due.is_empty() {
+        let message = "Nothing to review: nothing was done yesterday or is due today.";
+        output::say!("{}", message.dimmed());
+        return Ok(());
+    }
+
+    let opts = RequestOptions::default();
+    let mut summary = Summary::new("reviewed");
+    let sections = [
+        (format!("Completed {}", locale::weekday_date(yesterday)), done, DONE_KEYS),
+        (format!("Due {}", locale::weekday_date(today)), due, DUE_KEYS),
+    ];
+    'review: for (heading, todos, keys) in sections {
+        if todos.is_empty() {
+            continue;
+        }
+        output::say!();
+        output::say!("{}", format!("{} ({})", heading, todos.len()).bold());
+        let total = todos.len();
+        for (i, todo) in todos.into_iter().enumerate() {
+            let item = format!("#{} {}", todo.id, todo.title);
+            output::say!();
+            match todo.due() {
+                Some(at) => output::say!(
+                    "{} {} {}",
+                    format!("[{}/{}]", i + 1, total).dimmed(),
+                    item,
+                    format!("(due {})", locale::datetime(&at.with_timezone(&Local))).dimmed()
+                ),
+                None => output::say!("{} {}", format!("[{}/{}]", i + 1, total).dimmed(), item),
+            }
+
+            let result = match choose(keys, &todo, today, &limits)? {
+                Choice::Confirm => {
+                    summary.skipped(item, Some(todo.id), "confirmed");
+                    continue;
+                }
+                Choice::Quit => break 'review,
+                Choice::Reopen => client
+                    .update_todo(todo.id, None, Some(false), &opts)
+                    .await
+                    .map(|todo| (todo, Action::Reopened, "reopened".to_string())),
+                Choice::Complete => client
+                    .update_todo(todo.id, None, Some(true), &opts)
+                    .await
+                    .map(|todo| (todo, Action::Completed, "completed".to_string())),
+                Choice::Due(at) => client.set_due(todo.id, at, &opts).await.map(|todo| {
+                    let at = locale::datetime(&at.with_timezone(&Local));
+                    (todo, Action::Updated, format!("due {}", at))
+                }),
+            };
+            match result {
+                Ok((updated, action, outcome)) => {
+                    history::record_todo(&updated, action);
+                    summary.succeeded(format!("{}: {}", item, outcome), todo.id);
+                }
+                Err(err) => summary.failed(item, Some(todo.id), err),
+            }
+        }
+    }
+
+    output::say!();
+    summary.finish(None)
+}
+
+/// Shows `keys` and reads keys until one picks an action. Completed todos
+/// can only be confirmed or reopened.
+fn choose(keys: &str, todo: &Todo, today: NaiveDate, limits: &Limits) -> Result<Choice> {
+    loop {
+        match (prompt::key(keys)?, todo.completed) {
+            ('y' | ' ' | '\n', _) => return Ok(Choice::Confirm),
+            ('r', true) => return Ok(Choice::Reopen),
+            ('d', false) => return Ok(Choice::Due(deferred(todo, today))),
+            ('r', false) => {
+                if let Some(due) = triage::ask_due(limits)? {
+                    return Ok(Choice::Due(due));
+                }
+            }
+            ('c', false) => return Ok(Choice::Complete),
+            ('q', _) => return Ok(Choice::Quit),
+            _ => {}
+        }
+    }
+}
+
+/// The same time of day tomorrow, or the end of tomorrow without a due date.
+fn deferred(todo: &Todo, today: NaiveDate) -> DateTime<Utc> {
+    let tomorrow = today + Duration::days(1);
+    match todo.due() {
+        Some(at) => at + (tomorrow - at.with_timezone(&Local).date_naive()),
+        None => due::start_of(tomorrow + Duration::days(1)) - Duration::minutes(1),
+    }
+}
@@ -0,0 +1,161 @@
+//! Local full-text index of todo titles (SQLite FTS5) behind `todo search
+//! --local`: instant results on large accounts and offline. It's filled from
+//! listings, so it only knows todos this machine has seen.
+
+use std::fs;
+use std::time::Duration;
+
+use anyhow::{Context, Result, anyhow};
+use rusqlite::{Connection, params};
+
+use crate::api::Todo;
+use crate::config;
+use crate::error::ApiError;
+
+pub struct Index {
+    conn: Connection,
+}
+
+impl Index {
+    pub fn open() -> Result<Self> {
+        let path = config::state_dir()?.join("search.db");
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).context("Failed to create state directory")?;
+        }
+        let conn = Connection::open(&path)
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+        // Another `todo` may be writing; wait for it rather than fail
+        conn.busy_timeout(Duration::from_secs(5))
+            .context("Failed to configure search index")?;
+        // The rowid is the todo ID; `todo` keeps the whole todo as JSON so
+        // hits print like any other listing
+        conn.execute_batch(
+            "CREATE VIRTUAL TABLE IF NOT EXISTS todos USING fts5(
+                title,
+                todo UNINDEXED,
+                tokenize = 'unicode61 remove_diacritics 2',
+                prefix = '2 3'
+            );",
+        )
+        .context("Failed to create search index")?;
+
+        Ok(Self { conn })
+    }
+
+    /// Adds or refreshes `todos`. With `complete`, they are every todo the
+    /// account has, so anything else in the index was deleted.
+    pub fn update(&mut self, todos: &[Todo], complete: bool) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        if complete {
+            tx.execute("DELETE FROM todos", [])?;
+        }
+        {
+            let mut delete = tx.prepare("DELETE FROM todos WHERE rowid = ?1")?;
+            let mut insert =
+                tx.prepare("INSERT INTO todos (rowid, title, todo) VALUES (?1, ?2, ?3)")?;
+            for todo in todos {
+                delete.execute([todo.id])?;
+                insert.execute(params![todo.id, todo.title, serde_json::to_string(todo)?])?;
+            }
+        }
+        tx.commit().context("Failed to update search index")
+    }
+
+    pub fn remove(&self, ids: &[i64]) -> Result<()> {
+        let mut delete = self.conn.prepare("DELETE FROM todos WHERE rowid = ?1")?;
+        for id in ids {
+            delete.execute([id])?;
+        }
+        Ok(())
+    }
+
+    /// Runs an FTS5 query (`report*` for prefixes, `"quarterly report"` for
+    /// phrases, AND/OR/NOT), best matches first by BM25.
+    pub fn search(&self, query: &str, limit: usize) -> Result<Vec<Todo>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT todo FROM todos WHERE todos MATCH ?1 ORDER BY rank LIMIT ?2")?;
+        // Syntax errors only surface once the query runs
+        let rows = stmt
+            .query_map(params![query, limit as i64], |row| row.get::<_, String>(0))
+            .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+            .map_err(|err| anyhow!("Invalid search query '{}': {}", query, err))?;
+        decode(&rows)
+    }
+
+    /// Every indexed todo, by ID.
+    pub fn todos(&self) -> Result<Vec<Todo>> {
+        let mut stmt = self.conn.prepare("SELECT todo FROM todos ORDER BY rowid")?;
+        let rows = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        decode(&rows)
+    }
+
+    /// The ID and title of every indexed todo, by ID.
+    pub fn titles(&self) -> Result<Vec<(i64, String)>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT rowid, title FROM todos ORDER BY rowid")?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(rows)
+    }
+
+    /// Indexed todos with the IDs closest to `id`, nearest first.
+    pub fn nearby(&self, id: i64, limit: usize) -> Result<Vec<Todo>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT todo FROM todos ORDER BY abs(rowid - ?1) LIMIT ?2")?;
+        let rows = stmt
+            .query_map(params![id, limit as i64], |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        decode(&rows)
+    }
+}
+
+fn decode(rows: &[String]) -> Result<Vec<Todo>> {
+    rows.iter()
+        .map(|todo| serde_json::from_str(todo).context("Corrupt entry in search index"))
+        .collect()
+}
+
+/// Turns a 404 for todo `id` into a "did you mean" listing the closest IDs
+/// this machine has seen. Other errors pass through unchanged.
+pub fn did_you_mean(err: anyhow::Error, id: i64) -> anyhow::Error {
+    let not_found = matches!(err.downcast_ref::<ApiError>(), Some(ApiError::NotFound { .. }));
+    if !not_found {
+        return err;
+    }
+    let nearby = Index::open().and_then(|index| {
+        // It's gone from the server, so it shouldn't be suggested again
+        index.remove(&[id])?;
+        index.nearby(id, 3)
+    });
+    let nearby = match nearby {
+        Ok(nearby) if !nearby.is_empty() => nearby,
+        _ => return err,
+    };
+
+    let names: Vec<_> = nearby
+        .iter()
+        .map(|todo| format!("#{} \"{}\"", todo.id, todo.title))
+        .collect();
+    err.context(format!("Todo #{} not found; did you mean {}?", id, names.join(", ")))
+}
+
+/// Indexes todos from a listing. The index is a convenience, so failures
+/// are logged, not returned.
+pub fn record(todos: &[Todo], complete: bool) {
+    if let Err(err) = Index::open().and_then(|mut index| index.update(todos, complete)) {
+        tracing::warn!("Failed to update search index: {:#}", err);
+    }
+}
+
+/// Drops deleted todos from the index; failures are logged, not returned.
+pub fn forget(ids: &[i64]) {
+    if let Err(err) = Index::open().and_then(|index| index.remove(ids)) {
+        tracing::warn!("Failed to update search index: {:#}", err);
+    }
+}
@@ -0,0 +1,543 @@
+//! Where credentials live: the OS keyring by default, or `pass`
+//! (password-store) for headless machines without a keyring daemon. Where
+//! the keyring isn't reachable (WSL, containers, SSH sessions without
+//! D-Bus, builds without the `keyring` feature) the default falls through
+//! to an encrypted file, and then to reading them from the environment.
+
+use std::sync::{OnceLock, PoisonError, RwLock};
+
+use anyhow::{Result, bail};
+use serde::{Deserialize, Serialize};
+
+use crate::config::APP_NAME;
+use crate::style::Colorize;
+
+static BACKEND: RwLock<Backend> = RwLock::new(Backend::Keyring);
+/// Why the keyring can't be used, checked once per process
+static KEYRING_UNAVAILABLE: OnceLock<Option<String>> = OnceLock::new();
+
+/// The `secret_backend` config key.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    /// The OS keyring, falling back to `Encrypted` and then `Env` when
+    /// there is none
+    #[default]
+    Keyring,
+    /// `pass`, with entries under `todo-cli/` in the password store
+    Pass,
+    /// A file in the state directory, encrypted with a key derived from
+    /// `TODO_SECRETS_PASSPHRASE` or else this machine's ID
+    Encrypted,
+    /// Read-only: `TODO_TOKEN` and friends, nothing is stored
+    Env,
+    /// Plain JSON in the state directory, for `--test-harness` runs on
+    /// machines without a keyring. Not selectable from the config.
+    #[serde(skip)]
+    File,
+}
+
+impl Backend {
+    /// Selects the backend for the rest of the process, or until `config
+    /// import` brings in a different one.
+    pub fn install(self) {
+        *BACKEND.write().unwrap_or_else(PoisonError::into_inner) = self;
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Backend::Keyring => "keyring",
+            Backend::Pass => "pass",
+            Backend::Encrypted => "encrypted",
+            Backend::Env => "env",
+            Backend::File => "file (test harness)",
+        }
+    }
+}
+
+/// The backend secrets actually go to, and why it isn't the configured one
+/// when the keyring fell through.
+pub fn active() -> (Backend, Option<String>) {
+    let configured = *BACKEND.read().unwrap_or_else(PoisonError::into_inner);
+    if configured != Backend::Keyring {
+        return (configured, None);
+    }
+    let Some(reason) = KEYRING_UNAVAILABLE.get_or_init(keyring_store::unavailable) else {
+        return (Backend::Keyring, None);
+    };
+    let fallback = if encrypted::available() { Backend::Encrypted } else { Backend::Env };
+    (fallback, Some(reason.clone()))
+}
+
+/// What a secret is for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Kind {
+    Token,
+    HmacSecret,
+    BasicAuth,
+    /// Key for the encrypted session file, shared by all servers
+    SessionKey,
+}
+
+impl Kind {
+    fn name(self) -> &'static str {
+        match self {
+            Kind::Token => "api_token",
+            Kind::HmacSecret => "hmac_secret",
+            Kind::BasicAuth => "basic_auth",
+            Kind::SessionKey => "session_key",
+        }
+    }
+
+    /// Variable read in place of a store by builds without a keyring. The
+    /// token's also overrides the store in every build, for CI.
+    pub fn env(self) -> &'static str {
+        match self {
+            Kind::Token => "TODO_TOKEN",
+            Kind::HmacSecret => "TODO_HMAC_SECRET",
+            Kind::BasicAuth => "TODO_BASIC_AUTH",
+            Kind::SessionKey => "TODO_SESSION_KEY",
+        }
+    }
+}
+
+/// One stored secret. Per-server secrets are named `kind@url`, so logging
+/// into a second server doesn't overwrite the first one's.
+#[derive(Debug, Clone, Copy)]
+pub struct Secret<'a> {
+    pub kind: Kind,
+    pub api_url: Option<&'a str>,
+}
+
+impl<'a> Secret<'a> {
+    pub fn new(kind: Kind, api_url: &'a str) -> Self {
+        Self {
+            kind,
+            api_url: Some(api_url),
+        }
+    }
+
+    pub fn global(kind: Kind) -> Self {
+        Self { kind, api_url: None }
+    }
+
+    fn account(&self) -> String {
+        match self.api_url {
+            Some(url) => format!("{}@{}", self.kind.name(), url.trim_end_matches('/')),
+            None => self.kind.name().to_string(),
+        }
+    }
+}
+
+/// Reads `secret`, or `None` if nothing is stored.
+pub fn get(secret: Secret) -> Result<Option<String>> {
+    match active().0 {
+        Backend::Keyring => keyring_store::get(secret),
+        Backend::Pass => pass::get(secret),
+        Backend::Encrypted => encrypted::get(secret),
+        Backend::Env => Ok(std::env::var(secret.kind.env()).ok()),
+        Backend::File => file::get(secret),
+    }
+}
+
+pub fn set(secret: Secret, value: &str) -> Result<()> {
+    match active() {
+        (Backend::Keyring, _) => keyring_store::set(secret, value),
+        (Backend::Pass, _) => pass::set(secret, value),
+        (Backend::Encrypted, reason) => {
+            if let Some(reason) = reason {
+                let note = format!("No keyring ({}); using an encrypted file instead", reason);
+                eprintln!("{}", note.dimmed());
+            }
+            encrypted::set(secret, value)
+        }
+        (Backend::Env, Some(reason)) => bail!(
+            "No keyring ({}); export {} instead, or set TODO_SECRETS_PASSPHRASE to keep \
+             credentials in an encrypted file",
+            reason,
+            secret.kind.env()
+        ),
+        (Backend::Env, None) => {
+            bail!("secret_backend is env; export {} instead", secret.kind.env())
+        }
+        (Backend::File, _) => file::set(secret, value),
+    }
+}
+
+/// Removes `secret`; removing one that isn't stored is not an error.
+pub fn delete(secret: Secret) -> Result<()> {
+    match active().0 {
+        Backend::Keyring => keyring_store::delete(secret),
+        Backend::Pass => pass::delete(secret),
+        Backend::Encrypted => encrypted::delete(secret),
+        Backend::Env => Ok(()),
+        Backend::File => file::delete(secret),
+    }
+}
+
+/// Why a keyring daemon is unlikely to be reachable, checked before asking
+/// it so a missing D-Bus doesn't mean waiting on a timeout.
+#[cfg(all(feature = "keyring", target_os = "linux"))]
+fn headless() -> Option<String> {
+    let runtime_bus = std::env::var_os("XDG_RUNTIME_DIR")
+        .is_some_and(|dir| std::path::Path::new(&dir).join("bus").exists());
+    if std::env::var_os("DBUS_SESSION_BUS_ADDRESS").is_some() || runtime_bus {
+        return None;
+    }
+
+    let osrelease = std::fs::read_to_string("/proc/sys/kernel/osrelease").unwrap_or_default();
+    let place = if osrelease.to_lowercase().contains("microsoft") {
+        " under WSL"
+    } else if ["/.dockerenv", "/run/.containerenv"]
+        .iter()
+        .any(|marker| std::path::Path::new(marker).exists())
+    {
+        " in a container"
+    } else if std::env::var_os("SSH_CONNECTION").is_some() {
+        " in an SSH session"
+    } else {
+        ""
+    };
+    Some(format!("no D-Bus session{}", place))
+}
+
+#[cfg(all(feature = "keyring", not(target_os = "linux")))]
+fn headless() -> Option<String> {
+    None
+}
+
+#[cfg(feature = "keyring")]
+mod keyring_store {
+    use anyhow::{Context, Result};
+
+    use super::{APP_NAME, Kind, Secret};
+
+    /// Account used for the token before tokens were stored per server
+    const LEGACY_TOKEN_ACCOUNT: &str = "api_token";
+
+    /// Why the keyring can't be used, or `None` if a lookup gets an answer.
+    pub fn unavailable() -> Option<String> {
+        if let Some(reason) = super::headless() {
+            return Some(reason);
+        }
+        match entry("probe").map(|entry| entry.get_password()) {
+            Ok(Ok(_) | Err(keyring::Error::NoEntry)) => None,
+            Ok(Err(err)) => Some(err.to_string()),
+            Err(err) => Some(format!("{:#}", err)),
+        }
+    }
+
+    pub fn get(secret: Secret) -> Result<Option<String>> {
+        match entry(&secret.account())?.get_password() {
+            Ok(value) => Ok(Some(value)),
+            Err(keyring::Error::NoEntry) if secret.kind == Kind::Token => {
+                migrate_legacy_token(secret)
+            }
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(err) => Err(err).context("Failed to read from keyring"),
+        }
+    }
+
+    pub fn set(secret: Secret, value: &str) -> Result<()> {
+        entry(&secret.account())?
+            .set_password(value)
+            .context("Failed to save to keyring")
+    }
+
+    pub fn delete(secret: Secret) -> Result<()> {
+        match entry(&secret.account())?.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(err) => Err(err).context("Failed to delete from keyring"),
+        }
+    }
+
+    /// Moves a token stored under the old server-agnostic entry to
+    /// `secret`, so the first server used after upgrading keeps its login.
+    fn migrate_legacy_token(secret: Secret) -> Result<Option<String>> {
+        let legacy = entry(LEGACY_TOKEN_ACCOUNT)?;
+        let token = match legacy.get_password() {
+            Ok(token) => token,
+            Err(keyring::Error::NoEntry) => return Ok(None),
+            Err(err) => return Err(err).context("Failed to read from keyring"),
+        };
+
+        set(secret, &token)?;
+        let _ = legacy.delete_credential();
+        Ok(Some(token))
+    }
+
+    fn entry(account: &str) -> Result<keyring::Entry> {
+        keyring::Entry::new(APP_NAME, account).context("Failed to create keyring entry")
+    }
+}
+
+/// Builds without a keyring always fall through to the next backend.
+#[cfg(not(feature = "keyring"))]
+mod keyring_store {
+    use anyhow::Result;
+
+    use super::Secret;
+
+    pub fn unavailable() -> Option<String> {
+        Some("this build has no keyring support".to_string())
+    }
+
+    pub fn get(_secret: Secret) -> Result<Option<String>> {
+        unreachable!("keyring backend used without keyring support")
+    }
+
+    pub fn set(_secret: Secret, _value: &str) -> Result<()> {
+        unreachable!("keyring backend used without keyring support")
+    }
+
+    pub fn delete(_secret: Secret) -> Result<()> {
+        unreachable!("keyring backend used without keyring support")
+    }
+}
+
+/// Shells out to `pass`, which handles the gpg encryption and any git sync.
+mod pass {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    use anyhow::{Context, Result, bail};
+
+    use super::{APP_NAME, Secret};
+
+    pub fn get(secret: Secret) -> Result<Option<String>> {
+        let output = Command::new("pass")
+            .args(["show", &path(secret)])
+            .output()
+            .context("Failed to run `pass`; is password-store installed?")?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            if stderr.contains("is not in the password store") {
+                return Ok(None);
+            }
+            bail!("`pass show` failed: {}", stderr.trim());
+        }
+        // By convention the secret is the first line; the rest is metadata
+        let stdout = String::from_utf8(output.stdout).context("`pass` output is not UTF-8")?;
+        Ok(stdout.lines().next().map(str::to_string))
+    }
+
+    pub fn set(secret: Secret, value: &str) -> Result<()> {
+        let mut child = Command::new("pass")
+            .args(["insert", "--multiline", "--force", &path(secret)])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .context("Failed to run `pass`; is password-store installed?")?;
+        child
+            .stdin
+            .take()
+            .context("Failed to open `pass` stdin")?
+            .write_all(value.as_bytes())?;
+
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            bail!("`pass insert` failed: {}", stderr.trim());
+        }
+        Ok(())
+    }
+
+    pub fn delete(secret: Secret) -> Result<()> {
+        let output = Command::new("pass")
+            .args(["rm", "--force", &path(secret)])
+            .output()
+            .context("Failed to run `pass`; is password-store installed?")?;
+
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if !output.status.success() && !stderr.contains("is not in the password store") {
+            bail!("`pass rm` failed: {}", stderr.trim());
+        }
+        Ok(())
+    }
+
+    /// `todo-cli/api_token@https:__todo.example.com`; slashes would nest
+    /// directories in the store.
+    fn path(secret: Secret) -> String {
+        format!("{}/{}", APP_NAME, secret.account().replace('/', "_"))
+    }
+}
+
+/// One JSON object keyed by account, sealed with ChaCha20-Poly1305: a salt
+/// for the key, a nonce, then the ciphertext. Keyed by the machine ID it
+/// keeps a copied state directory useless elsewhere, but not other local
+/// users who can read the file; `TODO_SECRETS_PASSPHRASE` covers that.
+mod encrypted {
+    use std::collections::BTreeMap;
+    use std::fs;
+    use std::path::PathBuf;
+
+    use anyhow::{Context, Result, anyhow, bail};
+    use argon2::Argon2;
+    use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+    use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+
+    use super::Secret;
+
+    const PASSPHRASE_ENV: &str = "TODO_SECRETS_PASSPHRASE";
+    const SALT_LEN: usize = 16;
+    const NONCE_LEN: usize = 12;
+
+    /// Whether there is anything to derive a key from.
+    pub fn available() -> bool {
+        key_material().is_some()
+    }
+
+    pub fn get(secret: Secret) -> Result<Option<String>> {
+        Ok(read()?.remove(&secret.account()))
+    }
+
+    pub fn set(secret: Secret, value: &str) -> Result<()> {
+        let mut secrets = read()?;
+        secrets.insert(secret.account(), value.to_string());
+        write(&secrets)
+    }
+
+    pub fn delete(secret: Secret) -> Result<()> {
+        let mut secrets = read()?;
+        if secrets.remove(&secret.account()).is_some() {
+            write(&secrets)?;
+        }
+        Ok(())
+    }
+
+    /// The passphrase, or else the machine ID and user name.
+    fn key_material() -> Option<String> {
+        if let Ok(passphrase) = std::env::var(PASSPHRASE_ENV)
+            && !passphrase.is_empty()
+        {
+            return Some(passphrase);
+        }
+        let machine_id = ["/etc/machine-id", "/var/lib/dbus/machine-id"]
+            .iter()
+            .find_map(|path| fs::read_to_string(path).ok())
+            .filter(|id| !id.trim().is_empty())?;
+        let user = std::env::var("USER").unwrap_or_default();
+        Some(format!("{}:{}", machine_id.trim(), user))
+    }
+
+    fn cipher(salt: &[u8]) -> Result<ChaCha20Poly1305> {
+        let Some(material) = key_material() else {
+            bail!("Set {} to use the encrypted secrets file", PASSPHRASE_ENV);
+        };
+        let mut key = [0u8; 32];
+        Argon2::default()
+            .hash_password_into(material.as_bytes(), salt, &mut key)
+            .map_err(|err| anyhow!("Failed to derive key: {}", err))?;
+        Ok(ChaCha20Poly1305::new(Key::from_slice(&key)))
+    }
+
+    fn path() -> Result<PathBuf> {
+        Ok(crate::config::state_dir()?.join("secrets.bin"))
+    }
+
+    fn read() -> Result<BTreeMap<String, String>> {
+        let path = path()?;
+        if !path.exists() {
+            return Ok(BTreeMap::new());
+        }
+
+        let data = fs::read(&path).context("Failed to read secrets file")?;
+        if data.len() < SALT_LEN + NONCE_LEN {
+            bail!("Secrets file {} is corrupt", path.display());
+        }
+        let (salt, rest) = data.split_at(SALT_LEN);
+        let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+        let plaintext = cipher(salt)?
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| {
+                anyhow!(
+                    "Failed to decrypt {}; was it written with another {} or on another \
+                     machine?",
+                    path.display(),
+                    PASSPHRASE_ENV
+                )
+            })?;
+
+        serde_json::from_slice(&plaintext).context("Corrupt secrets file")
+    }
+
+    fn write(secrets: &BTreeMap<String, String>) -> Result<()> {
+        let mut salt = [0u8; SALT_LEN];
+        getrandom::getrandom(&mut salt).context("Failed to generate random bytes")?;
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = cipher(&salt)?
+            .encrypt(&nonce, serde_json::to_vec(secrets)?.as_slice())
+            .map_err(|_| anyhow!("Failed to encrypt secrets"))?;
+
+        let path = path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).context("Failed to create state directory")?;
+        }
+        let mut data = salt.to_vec();
+        data.extend(nonce);
+        data.extend(ciphertext);
+        fs::write(&path, data).context("Failed to write secrets file")?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o600))
+                .context("Failed to restrict secrets file permissions")?;
+        }
+        Ok(())
+    }
+}
+
+/// One JSON object keyed by account. Unencrypted, so only for throwaway
+/// state directories.
+mod file {
+    use std::collections::BTreeMap;
+    use std::fs;
+    use std::io::ErrorKind;
+    use std::path::PathBuf;
+
+    use anyhow::{Context, Result};
+
+    use super::Secret;
+
+    pub fn get(secret: Secret) -> Result<Option<String>> {
+        Ok(read()?.remove(&secret.account()))
+    }
+
+    pub fn set(secret: Secret, value: &str) -> Result<()> {
+        let mut secrets = read()?;
+        secrets.insert(secret.account(), value.to_string());
+        write(&secrets)
+    }
+
+    pub fn delete(secret: Secret) -> Result<()> {
+        let mut secrets = read()?;
+        if secrets.remove(&secret.account()).is_some() {
+            write(&secrets)?;
+        }
+        Ok(())
+    }
+
+    fn path() -> Result<PathBuf> {
+        Ok(crate::config::state_dir()?.join("harness-secrets.json"))
+    }
+
+    fn read() -> Result<BTreeMap<String, String>> {
+        match fs::read_to_string(path()?) {
+            Ok(content) => serde_json::from_str(&content).context("Corrupt secrets file"),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(BTreeMap::new()),
+            Err(err) => Err(err).context("Failed to read secrets file"),
+        }
+    }
+
+    fn write(secrets: &BTreeMap<String, String>) -> Result<()> {
+        let path = path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).context("Failed to create state directory")?;
+        }
+        fs::write(&path, serde_json::to_string_pretty(secrets)?)
+            .context("Failed to write secrets file")
+    }
+}
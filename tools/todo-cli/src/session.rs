@@ -0,0 +1,100 @@
+//! Session cookies for `auth_scheme = "session"`, one per server. They are
+//! kept in a file encrypted with a key from the secret store, so a copied
+//! state directory doesn't carry a usable login.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result, anyhow, bail};
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+
+use crate::config;
+use crate::secrets::{self, Kind, Secret};
+
+const NONCE_LEN: usize = 12;
+
+/// The stored `Cookie` header value for `api_url`, if logged in.
+pub fn load(api_url: &str) -> Option<String> {
+    match read() {
+        Ok(mut sessions) => sessions.remove(api_url.trim_end_matches('/')),
+        Err(err) => {
+            tracing::warn!(error = %err, "Failed to read sessions");
+            None
+        }
+    }
+}
+
+pub fn save(api_url: &str, cookie: &str) -> Result<()> {
+    let mut sessions = read().unwrap_or_default();
+    sessions.insert(api_url.trim_end_matches('/').to_string(), cookie.to_string());
+    write(&sessions)
+}
+
+pub fn clear(api_url: &str) -> Result<()> {
+    let mut sessions = read().unwrap_or_default();
+    if sessions.remove(api_url.trim_end_matches('/')).is_some() {
+        write(&sessions)?;
+    }
+    Ok(())
+}
+
+fn path() -> Result<PathBuf> {
+    Ok(config::state_dir()?.join("sessions.bin"))
+}
+
+/// Decrypts the session file: a random nonce followed by the ciphertext of
+/// a JSON map from server URL to cookie.
+fn read() -> Result<BTreeMap<String, String>> {
+    let path = path()?;
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+
+    let data = fs::read(&path).context("Failed to read session file")?;
+    if data.len() < NONCE_LEN {
+        bail!("Session file {} is corrupt", path.display());
+    }
+    let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+    let plaintext = cipher(false)?
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| anyhow!("Failed to decrypt session file; log in again"))?;
+
+    Ok(serde_json::from_slice(&plaintext)?)
+}
+
+fn write(sessions: &BTreeMap<String, String>) -> Result<()> {
+    let cipher = cipher(true)?;
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, serde_json::to_vec(sessions)?.as_slice())
+        .map_err(|_| anyhow!("Failed to encrypt sessions"))?;
+
+    let path = path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).context("Failed to create state directory")?;
+    }
+    let mut data = nonce.to_vec();
+    data.extend(ciphertext);
+    fs::write(&path, data).context("Failed to write session file")
+}
+
+/// The file's key, generated and put in the secret store on first write.
+fn cipher(create: bool) -> Result<ChaCha20Poly1305> {
+    let secret = Secret::global(Kind::SessionKey);
+    let key = match secrets::get(secret)? {
+        Some(key) => hex::decode(key).context("Stored session key is not hex")?,
+        None if create => {
+            let key = ChaCha20Poly1305::generate_key(&mut OsRng);
+            secrets::set(secret, &hex::encode(key))?;
+            key.to_vec()
+        }
+        None => bail!("No session key stored; log in again"),
+    };
+    if key.len() != 32 {
+        bail!("Stored session key has the wrong length");
+    }
+
+    Ok(ChaCha20Poly1305::new(Key::from_slice(&key)))
+}
@@ -0,0 +1,194 @@
+//! `todo shell`: a prompt that runs todo commands a line at a time, so a
+//! session of `list`, `done 5` and `create buy milk` reuses the config,
+//! token and connection loaded once. Lines are kept in a history file in
+//! the state directory, and Tab completes subcommands and flags.
+
+use std::path::PathBuf;
+
+use anyhow::{Context as _, Result};
+use clap::{CommandFactory, Parser};
+use rustyline::completion::{Completer, Pair};
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
+
+use crate::api::TodoApi;
+use crate::config::{self, Config};
+use crate::style::Colorize;
+use crate::{Commands, bulk, goals, history, output, prompt};
+
+/// Lines kept in the history file.
+const HISTORY_SIZE: usize = 1000;
+
+/// One line typed at the prompt: a command without the `todo`.
+#[derive(Parser)]
+#[command(no_binary_name = true, name = "todo", disable_version_flag = true)]
+struct Line {
+    #[command(subcommand)]
+    command: Commands,
+}
+
+/// Completes subcommand names, then the flags of the one typed.
+struct Completion {
+    command: clap::Command,
+}
+
+impl Completer for Completion {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let line = &line[..pos];
+        let start = line.rfind(char::is_whitespace).map_or(0, |i| i + 1);
+        let word = &line[start..];
+
+        // Walk down through the subcommands already typed
+        let mut command = &self.command;
+        for typed in line[..start].split_whitespace() {
+            if let Some(sub) = command.find_subcommand(typed) {
+                command = sub;
+            }
+        }
+
+        let candidates: Vec<String> = if word.starts_with('-') {
+            command
+                .get_arguments()
+                .filter(|arg| !arg.is_hide_set())
+                .filter_map(|arg| arg.get_long())
+                .map(|long| format!("--{}", long))
+                .collect()
+        } else {
+            let mut names: Vec<String> = command
+                .get_subcommands()
+                .filter(|sub| !sub.is_hide_set())
+                .map(|sub| sub.get_name().to_string())
+                .collect();
+            if start == 0 {
+                names.push("exit".to_string());
+            }
+            names
+        };
+        let pairs = candidates
+            .into_iter()
+            .filter(|candidate| candidate.starts_with(word))
+            .map(|candidate| Pair {
+                display: candidate.clone(),
+                replacement: format!("{} ", candidate),
+            })
+            .collect();
+        Ok((start, pairs))
+    }
+}
+
+// Completion is all the shell adds; the rest keeps rustyline's defaults
+impl Helper for Completion {}
+impl Highlighter for Completion {}
+impl Validator for Completion {}
+
+impl Hinter for Completion {
+    type Hint = String;
+}
+
+/// Reads and runs commands until `exit` or Ctrl+D. A failed command prints
+/// its error and the prompt comes back.
+pub async fn run<A: TodoApi>(format: &str, mut config: Config, client: &A) -> Result<()> {
+    if !prompt::interactive() {
+        return Err(prompt::required("`todo shell` reads commands from a terminal"));
+    }
+
+    let settings = rustyline::Config::builder().max_history_size(HISTORY_SIZE)?.build();
+    let mut editor: Editor<Completion, DefaultHistory> = Editor::with_config(settings)?;
+    editor.set_helper(Some(Completion {
+        command: Line::command(),
+    }));
+    let history_path = history_path()?;
+    // A missing or unreadable history just starts empty
+    let _ = editor.load_history(&history_path);
+
+    output::say!("{}", "Type a todo command, `help` for the list, `exit` to leave.".dimmed());
+    loop {
+        // With a weekly goal, progress toward it: `todo 7/15> `
+        let label = match goals::segment(&config) {
+            Some(segment) => format!("todo {}> ", segment),
+            None => "todo> ".to_string(),
+        };
+        let line = match editor.readline(&label) {
+            Ok(line) => line,
+            // Ctrl+C drops the line being typed, as in other shells
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(err) => return Err(err.into()),
+        };
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let _ = editor.add_history_entry(line);
+        if matches!(line, "exit" | "quit") {
+            break;
+        }
+
+        let words = match shell_words::split(line) {
+            Ok(words) => words,
+            Err(err) => {
+                eprintln!("{}", err.to_string().red());
+                continue;
+            }
+        };
+        let command = match Line::try_parse_from(&words) {
+            Ok(line) => line.command,
+            // Help output, or clap's usual message for a mistyped command
+            Err(err) => {
+                let _ = err.print();
+                continue;
+            }
+        };
+        match command {
+            Commands::Shell => {
+                eprintln!("{}", "Already in the shell.".dimmed());
+                continue;
+            }
+            // The shell keeps the token it started with
+            Commands::Auth { .. } => {
+                eprintln!("{}", "Run `todo auth` outside the shell, then start it again.".red());
+                continue;
+            }
+            _ => {}
+        }
+        let rereads_config = matches!(
+            command,
+            Commands::Config { .. }
+                | Commands::Filter { .. }
+                | Commands::Place { .. }
+                | Commands::Goals { .. }
+        );
+
+        let result = crate::run(command, format, config.clone(), client).await;
+        bulk::reset();
+        history::record_command(words, result.is_ok());
+        if let Err(ref err) = result {
+            output::print_error(err);
+        }
+        // So later commands see settings changed from inside the shell
+        if rereads_config {
+            config = Config::load()?;
+            config.load_token(client.base_url());
+        }
+    }
+
+    editor
+        .save_history(&history_path)
+        .with_context(|| format!("Failed to save shell history to {:?}", history_path))?;
+    Ok(())
+}
+
+fn history_path() -> Result<PathBuf> {
+    Ok(config::state_dir()?.join("shell_history"))
+}
@@ -0,0 +1,138 @@
+//! `todo standup`: what was done yesterday, what's planned for today and
+//! what's in the way, ready to paste into Slack or a markdown document.
+
+use chrono::{Duration, Local, NaiveDate};
+use serde::Serialize;
+
+use crate::api::{Todo, TodoStatus};
+use crate::{depends, due, locale, stats};
+
+/// Markup the standup is written in.
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum Format {
+    /// Headings in bold, `-` bullets
+    Markdown,
+    /// Slack's mrkdwn: `*bold*` headings, `•` bullets
+    Slack,
+}
+
+/// The three standup sections.
+#[derive(Debug, Serialize)]
+pub struct Standup {
+    pub date: NaiveDate,
+    /// Completed the day before `date`
+    pub done: Vec<Todo>,
+    /// Open and due on `date` or overdue, or already in progress
+    pub planned: Vec<Todo>,
+    pub blocked: Vec<Blocked>,
+}
+
+/// An open todo that can't move: blocked with a reason, or waiting on
+/// todos that are still open.
+#[derive(Debug, Serialize)]
+pub struct Blocked {
+    #[serde(flatten)]
+    pub todo: Todo,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub waiting_on: Vec<i64>,
+}
+
+impl Standup {
+    pub fn compile(todos: Vec<Todo>, date: NaiveDate) -> Self {
+        let yesterday = date - Duration::days(1);
+        let mut blockers = depends::open_blockers(&todos);
+
+        let done = todos
+            .iter()
+            .filter(|todo| todo.completed && stats::completed_on(todo) == Some(yesterday))
+            .cloned()
+            .collect();
+
+        let mut blocked = Vec::new();
+        let mut open = Vec::new();
+        for todo in todos.into_iter().filter(|todo| !todo.completed) {
+            let waiting_on = blockers.remove(&todo.id).unwrap_or_default();
+            if todo.status == Some(TodoStatus::Blocked) || !waiting_on.is_empty() {
+                blocked.push(Blocked { todo, waiting_on });
+            } else {
+                open.push(todo);
+            }
+        }
+
+        // Started work first, then the day's agenda in due order
+        let (mut planned, rest): (Vec<_>, Vec<_>) = open
+            .into_iter()
+            .partition(|todo| todo.status == Some(TodoStatus::InProgress));
+        let agenda = due::Agenda::for_day(rest, date);
+        planned.extend(agenda.overdue);
+        planned.extend(agenda.due);
+
+        Self {
+            date,
+            done,
+            planned,
+            blocked,
+        }
+    }
+
+    pub fn render(&self, format: Format) -> String {
+        let (bold, bullet) = match format {
+            Format::Markdown => ("**", "-"),
+            Format::Slack => ("*", "•"),
+        };
+        let done: Vec<_> = self.done.iter().map(|todo| item(todo, None)).collect();
+        let planned: Vec<_> = self
+            .planned
+            .iter()
+            .map(|todo| item(todo, Some(self.date)))
+            .collect();
+        let blocked: Vec<_> = self.blocked.iter().map(blocker).collect();
+
+        let mut out = String::new();
+        for (heading, lines) in [("Yesterday", done), ("Today", planned), ("Blockers", blocked)] {
+            if !out.is_empty() {
+                out.push('\n');
+            }
+            out.push_str(&format!("{}{}{}\n", bold, heading, bold));
+            if lines.is_empty() {
+                out.push_str(&format!("{} Nothing\n", bullet));
+            }
+            for line in lines {
+                out.push_str(&format!("{} {}\n", bullet, line));
+            }
+        }
+        out
+    }
+}
+
+/// `#12 Title`, with when it's due for planned todos: the time when due on
+/// `date`, the date when overdue.
+fn item(todo: &Todo, date: Option<NaiveDate>) -> String {
+    // A newline in a title would end the bullet early
+    let title = todo.title.split_whitespace().collect::<Vec<_>>().join(" ");
+    let due = date.zip(todo.due()).map(|(date, at)| {
+        let at = at.with_timezone(&Local);
+        if at.date_naive() < date {
+            format!(" (overdue since {})", locale::date(at.date_naive()))
+        } else {
+            format!(" (due {})", at.format("%H:%M"))
+        }
+    });
+    let status = match todo.status {
+        Some(TodoStatus::InProgress) => " (in progress)",
+        _ => "",
+    };
+    format!("#{} {}{}{}", todo.id, title, due.unwrap_or_default(), status)
+}
+
+fn blocker(blocked: &Blocked) -> String {
+    let mut line = item(&blocked.todo, None);
+    if !blocked.waiting_on.is_empty() {
+        line.push_str(&format!(": waiting on {}", depends::list(&blocked.waiting_on)));
+    }
+    if let Some(ref reason) = blocked.todo.blocked_reason {
+        let reason = reason.split_whitespace().collect::<Vec<_>>().join(" ");
+        line.push_str(&format!(": {}", reason));
+    }
+    line
+}
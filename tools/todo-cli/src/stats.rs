@@ -0,0 +1,176 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use chrono::{DateTime, Local, NaiveDate, Utc};
+use serde::Serialize;
+
+use crate::api::Todo;
+use crate::due;
+use crate::locale;
+use crate::output;
+use crate::style::Colorize;
+
+/// What `todo stats --by` splits the counts on.
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum GroupBy {
+    /// Each tag; a todo with several counts under each, one with none
+    /// under `untagged`
+    Tag,
+}
+
+/// Counts for one group of todos.
+#[derive(Debug, Serialize)]
+pub struct GroupStats {
+    pub group: String,
+    pub open: usize,
+    pub done: usize,
+    /// Share of the group completed, 0.0–1.0
+    pub completion_rate: f64,
+    /// Mean days since open todos were created; `None` with nothing open
+    pub average_age_days: Option<f64>,
+    /// Estimated minutes of the open todos
+    pub estimated_minutes: i64,
+}
+
+/// Splits `todos` by `by` (or keeps them as one `all` group) and counts each.
+pub fn compute(todos: &[Todo], by: Option<GroupBy>, now: DateTime<Utc>) -> Vec<GroupStats> {
+    let mut groups: BTreeMap<String, Vec<&Todo>> = BTreeMap::new();
+    for todo in todos {
+        let keys = match by {
+            None => vec!["all".to_string()],
+            Some(GroupBy::Tag) if todo.tags.is_empty() => vec!["untagged".to_string()],
+            Some(GroupBy::Tag) => todo.tags.clone(),
+        };
+        for key in keys {
+            groups.entry(key).or_default().push(todo);
+        }
+    }
+
+    groups
+        .into_iter()
+        .map(|(group, todos)| {
+            let done = todos.iter().filter(|todo| todo.completed).count();
+            let ages: Vec<f64> = todos
+                .iter()
+                .filter(|todo| !todo.completed)
+                .filter_map(|todo| created(todo))
+                .map(|at| (now - at).num_seconds() as f64 / 86_400.0)
+                .collect();
+
+            GroupStats {
+                group,
+                open: todos.len() - done,
+                done,
+                completion_rate: done as f64 / todos.len() as f64,
+                average_age_days: (!ages.is_empty())
+                    .then(|| ages.iter().sum::<f64>() / ages.len() as f64),
+                estimated_minutes: due::open_estimate(todos.iter().copied()),
+            }
+        })
+        .collect()
+}
+
+/// The local day a completed todo was last updated, taken as the day it
+/// was completed.
+pub fn completed_on(todo: &Todo) -> Option<NaiveDate> {
+    DateTime::parse_from_rfc3339(&todo.updated_at)
+        .ok()
+        .map(|at| at.with_timezone(&Local).date_naive())
+}
+
+fn created(todo: &Todo) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(&todo.created_at)
+        .ok()
+        .map(|at| at.with_timezone(&Utc))
+}
+
+pub fn print(rows: &[GroupStats], format: &str) -> Result<()> {
+    if format == "json" {
+        return output::print_json("stats", rows);
+    }
+
+    if rows.is_empty() {
+        println!("{}", "No todos found.".dimmed());
+        return Ok(());
+    }
+
+    let width = rows.iter().map(|row| row.group.len()).max().unwrap_or(0).max(5);
+    println!(
+        "{}",
+        format!(
+            "{:<width$}  {:>6}  {:>6}  {:>6}  {:>8}  {:>8}",
+            "GROUP", "OPEN", "DONE", "RATE", "AVG AGE", "EST LEFT"
+        )
+        .bold()
+    );
+    for row in rows {
+        let age = match row.average_age_days {
+            Some(days) => format!("{}d", locale::decimal(days, 1)),
+            None => "-".to_string(),
+        };
+        let estimate = match row.estimated_minutes {
+            0 => "-".to_string(),
+            minutes => due::format_effort(minutes),
+        };
+        println!(
+            "{:<width$}  {:>6}  {:>6}  {:>6}  {:>8}  {:>8}",
+            row.group,
+            locale::integer(row.open),
+            locale::integer(row.done),
+            format!("{}%", locale::decimal(row.completion_rate * 100.0, 0)),
+            age,
+            estimate
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn todo(id: i64, completed: bool, tags: &[&str]) -> Todo {
+        serde_json::from_value(json!({
+            "id": id,
+            "user_id": 1,
+            "title": format!("todo {}", id),
+            "completed": completed,
+            "created_at": "2025-12-01T09:00:00Z",
+            "updated_at": "2025-12-01T09:00:00Z",
+            "tags": tags,
+        }))
+        .unwrap()
+    }
+
+    fn counts(rows: &[GroupStats]) -> Vec<(&str, usize, usize)> {
+        rows.iter()
+            .map(|row| (row.group.as_str(), row.open, row.done))
+            .collect()
+    }
+
+    #[test]
+    fn without_by_everything_is_one_group() {
+        let todos = [todo(1, false, &["work"]), todo(2, true, &[])];
+        let rows = compute(&todos, None, Utc::now());
+        assert_eq!(counts(&rows), [("all", 1, 1)]);
+    }
+
+    #[test]
+    fn by_tag_counts_a_todo_under_each_of_its_tags() {
+        let todos = [
+            todo(1, false, &["work", "urgent"]),
+            todo(2, true, &["work"]),
+            todo(3, false, &[]),
+            todo(4, true, &[]),
+        ];
+        let rows = compute(&todos, Some(GroupBy::Tag), Utc::now());
+        assert_eq!(
+            counts(&rows),
+            [("untagged", 1, 1), ("urgent", 1, 0), ("work", 1, 1)]
+        );
+        let work = rows.iter().find(|row| row.group == "work").unwrap();
+        assert_eq!(work.completion_rate, 0.5);
+    }
+}
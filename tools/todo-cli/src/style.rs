@@ -0,0 +1,122 @@
+//! Terminal styling. Without the `color` feature the same method names
+//! resolve to a stand-in that leaves text untouched.
+
+#[cfg(feature = "color")]
+pub use colored::Colorize;
+#[cfg(feature = "color")]
+pub use styled::Style;
+
+#[cfg(not(feature = "color"))]
+pub use plain::{Colorize, Style};
+
+/// Prepares the console for styled output and reports whether it can show
+/// emoji. The legacy Windows console (conhost) needs VT processing switched
+/// on and draws emoji as boxes; Windows Terminal and VS Code render both.
+pub fn init_terminal() -> bool {
+    #[cfg(all(windows, feature = "color"))]
+    if colored::control::set_virtual_terminal(true).is_err() {
+        // Pre-Windows 10 consoles would print the escape codes literally
+        colored::control::set_override(false);
+    }
+
+    !cfg!(windows)
+        || std::env::var_os("WT_SESSION").is_some()
+        || std::env::var("TERM_PROGRAM").is_ok_and(|program| program == "vscode")
+}
+
+#[cfg(feature = "color")]
+mod styled {
+    use anyhow::{Result, anyhow};
+    use colored::{Color, ColoredString, Colorize};
+
+    /// A style spec from the config, e.g. `"bold blue"`, `"dimmed"` or
+    /// `"#268bd2"`: any number of modifiers plus at most one color.
+    #[derive(Debug, Clone, Default)]
+    pub struct Style {
+        color: Option<Color>,
+        modifiers: Vec<fn(ColoredString) -> ColoredString>,
+    }
+
+    impl Style {
+        pub fn parse(spec: &str) -> Result<Self> {
+            let mut style = Style::default();
+            for word in spec.split_whitespace() {
+                let modifier: fn(ColoredString) -> ColoredString = match word {
+                    "bold" => ColoredString::bold,
+                    "dimmed" => ColoredString::dimmed,
+                    "italic" => ColoredString::italic,
+                    "underline" => ColoredString::underline,
+                    "strikethrough" => ColoredString::strikethrough,
+                    color => {
+                        style.color = Some(parse_color(color)?);
+                        continue;
+                    }
+                };
+                style.modifiers.push(modifier);
+            }
+            Ok(style)
+        }
+
+        pub fn paint(&self, text: &str) -> String {
+            let mut styled = text.normal();
+            if let Some(color) = self.color {
+                styled = styled.color(color);
+            }
+            for modifier in &self.modifiers {
+                styled = modifier(styled);
+            }
+            styled.to_string()
+        }
+    }
+
+    /// Named colors (`blue`, `bright_blue`) or `#rrggbb` for truecolor terminals.
+    fn parse_color(word: &str) -> Result<Color> {
+        if let Some(hex) = word.strip_prefix('#')
+            && hex.len() == 6
+            && let Ok(rgb) = u32::from_str_radix(hex, 16)
+        {
+            return Ok(Color::TrueColor {
+                r: (rgb >> 16) as u8,
+                g: (rgb >> 8) as u8,
+                b: rgb as u8,
+            });
+        }
+        word.replace('_', " ")
+            .parse()
+            .map_err(|_| anyhow!("Unknown color or style '{}'", word))
+    }
+}
+
+#[cfg(not(feature = "color"))]
+mod plain {
+    macro_rules! plain_styles {
+        ($($style:ident),* $(,)?) => {
+            /// Drop-in for `colored::Colorize` that returns the text unstyled.
+            pub trait Colorize {
+                $(fn $style(self) -> String;)*
+            }
+
+            impl Colorize for &str {
+                $(fn $style(self) -> String {
+                    self.to_string()
+                })*
+            }
+        };
+    }
+
+    plain_styles!(red, green, yellow, bold, dimmed, strikethrough);
+
+    /// Themes still parse without color support; they just have no effect.
+    #[derive(Debug, Clone, Default)]
+    pub struct Style;
+
+    impl Style {
+        pub fn parse(_spec: &str) -> anyhow::Result<Self> {
+            Ok(Style)
+        }
+
+        pub fn paint(&self, text: &str) -> String {
+            text.to_string()
+        }
+    }
+}
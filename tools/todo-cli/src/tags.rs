@@ -0,0 +1,179 @@
+//! `todo tag add/remove/rename`: one tag change across many todos, sent a
+//! few requests at a time, with the per-item summary of the bulk commands.
+
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::Result;
+use futures::{StreamExt, stream};
+
+use crate::aliases::{Aliases, IdArg};
+use crate::api::{RequestOptions, Todo, TodoApi};
+use crate::bulk::Summary;
+use crate::config::Config;
+use crate::filters;
+use crate::history::{self, Action};
+use crate::validation::TodoInput;
+
+/// Requests in flight at once.
+const CONCURRENCY: usize = 8;
+
+/// Which todos a change applies to: listed IDs or a saved filter's matches.
+pub struct Targets<'a> {
+    pub ids: &'a [IdArg],
+    pub filter: Option<&'a str>,
+}
+
+pub async fn add<A: TodoApi>(
+    client: &A,
+    config: &Config,
+    aliases: &Aliases,
+    tag: &str,
+    targets: Targets<'_>,
+    report: Option<&Path>,
+) -> Result<()> {
+    validate(client, tag).await?;
+    let mut summary = Summary::new("tagged");
+    let todos = fetch(client, config, aliases, targets, &mut summary).await?;
+    let change = |tags: &[String]| {
+        (!tags.iter().any(|t| t == tag)).then(|| {
+            let mut tags = tags.to_vec();
+            tags.push(tag.to_string());
+            tags
+        })
+    };
+    apply(client, todos, change, "already tagged", &mut summary).await;
+    summary.finish(report)
+}
+
+pub async fn remove<A: TodoApi>(
+    client: &A,
+    config: &Config,
+    aliases: &Aliases,
+    tag: &str,
+    targets: Targets<'_>,
+    report: Option<&Path>,
+) -> Result<()> {
+    let mut summary = Summary::new("untagged");
+    let todos = fetch(client, config, aliases, targets, &mut summary).await?;
+    let change = |tags: &[String]| {
+        tags.iter()
+            .any(|t| t == tag)
+            .then(|| tags.iter().filter(|t| *t != tag).cloned().collect::<Vec<_>>())
+    };
+    apply(client, todos, change, "not tagged", &mut summary).await;
+    summary.finish(report)
+}
+
+/// Renames `old` to `new` on every todo that has it.
+pub async fn rename<A: TodoApi>(
+    client: &A,
+    old: &str,
+    new: &str,
+    report: Option<&Path>,
+) -> Result<()> {
+    validate(client, new).await?;
+    let mut todos = client.list_todos(None, &RequestOptions::new().retries(2)).await?;
+    todos.retain(|todo| todo.tags.iter().any(|t| t == old));
+
+    let change = |tags: &[String]| {
+        let mut renamed = Vec::with_capacity(tags.len());
+        for tag in tags {
+            let tag = if tag == old { new } else { tag.as_str() };
+            if !renamed.iter().any(|t: &String| t == tag) {
+                renamed.push(tag.to_string());
+            }
+        }
+        Some(renamed)
+    };
+    let mut summary = Summary::new("retagged");
+    apply(client, todos, change, "unchanged", &mut summary).await;
+    summary.finish(report)
+}
+
+async fn validate<A: TodoApi>(client: &A, tag: &str) -> Result<()> {
+    let limits = client
+        .limits(&RequestOptions::new().timeout(Duration::from_secs(2)))
+        .await
+        .unwrap_or_default();
+    let tags = [tag.to_string()];
+    let input = TodoInput {
+        tags: &tags,
+        ..Default::default()
+    };
+    input.validate(&limits)?;
+    Ok(())
+}
+
+/// The targeted todos with their current tags. IDs that can't be fetched
+/// are recorded as failures rather than stopping the rest.
+async fn fetch<A: TodoApi>(
+    client: &A,
+    config: &Config,
+    aliases: &Aliases,
+    targets: Targets<'_>,
+    summary: &mut Summary,
+) -> Result<Vec<Todo>> {
+    if let Some(name) = targets.filter {
+        return filters::matching(client, config, name).await;
+    }
+
+    let ids = targets
+        .ids
+        .iter()
+        .map(|id| aliases.resolve(id))
+        .collect::<Result<Vec<_>>>()?;
+    let opts = RequestOptions::new().retries(2);
+    let results: Vec<_> = stream::iter(&ids)
+        .map(|&id| client.get_todo(id, &opts))
+        .buffered(CONCURRENCY)
+        .collect()
+        .await;
+
+    let mut todos = Vec::new();
+    for (id, result) in ids.into_iter().zip(results) {
+        match result {
+            Ok(todo) => todos.push(todo),
+            Err(err) => summary.failed(format!("#{}", id), Some(id), err),
+        }
+    }
+    Ok(todos)
+}
+
+/// Sends the tags `change` gives for each todo, skipping those it returns
+/// `None` for with `unchanged` as the reason.
+async fn apply<A: TodoApi>(
+    client: &A,
+    todos: Vec<Todo>,
+    change: impl Fn(&[String]) -> Option<Vec<String>>,
+    unchanged: &str,
+    summary: &mut Summary,
+) {
+    let mut updates = Vec::new();
+    for todo in todos {
+        match change(&todo.tags) {
+            Some(tags) => updates.push((todo, tags)),
+            None => summary.skipped(item(&todo), Some(todo.id), unchanged),
+        }
+    }
+
+    let opts = RequestOptions::default();
+    let results: Vec<_> = stream::iter(&updates)
+        .map(|(todo, tags)| client.set_tags(todo.id, tags, &opts))
+        .buffered(CONCURRENCY)
+        .collect()
+        .await;
+    for ((todo, _), result) in updates.iter().zip(results) {
+        match result {
+            Ok(updated) => {
+                history::record_todo(&updated, Action::Updated);
+                summary.succeeded(item(&updated), todo.id);
+            }
+            Err(err) => summary.failed(item(todo), Some(todo.id), err),
+        }
+    }
+}
+
+fn item(todo: &Todo) -> String {
+    format!("#{} {}", todo.id, todo.title)
+}
@@ -0,0 +1,73 @@
+//! Time spent on todos, kept per machine in the state directory. The API
+//! has nowhere to store it, so intervals (from `todo focus`) are appended
+//! to a local JSON-lines file.
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::lock;
+
+/// One line of the time log: a stretch of work on one todo.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Interval {
+    pub todo_id: i64,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: DateTime<Utc>,
+}
+
+impl Interval {
+    pub fn minutes(&self) -> i64 {
+        (self.ended_at - self.started_at).num_minutes()
+    }
+}
+
+/// Appends `interval` to the log.
+pub fn record(interval: &Interval) -> Result<()> {
+    let path = state_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).context("Failed to create state directory")?;
+    }
+    let line = serde_json::to_string(interval)?;
+    lock::exclusive(&path, || {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+        writeln!(file, "{}", line)?;
+        Ok(())
+    })
+}
+
+/// Reads every interval, oldest first, skipping lines that don't parse.
+pub fn load() -> Result<Vec<Interval>> {
+    let path = state_path()?;
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err).context("Failed to read time log"),
+    };
+
+    Ok(content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+/// Minutes logged against `todo_id` so far.
+pub fn tracked_minutes(intervals: &[Interval], todo_id: i64) -> i64 {
+    intervals
+        .iter()
+        .filter(|interval| interval.todo_id == todo_id)
+        .map(Interval::minutes)
+        .sum()
+}
+
+fn state_path() -> Result<PathBuf> {
+    Ok(crate::config::state_dir()?.join("timelog.jsonl"))
+}
@@ -0,0 +1,158 @@
+//! `todo triage`: steps through open todos without a due date, oldest
+//! first, acting on each with a single key so a backlog can be worked
+//! through without typing a command per item.
+
+use std::time::Duration as StdDuration;
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, Local, NaiveDate, Utc};
+
+use crate::aliases::Aliases;
+use crate::api::{RequestOptions, Todo, TodoApi};
+use crate::bulk::Summary;
+use crate::history::{self, Action};
+use crate::style::Colorize;
+use crate::validation::{Limits, TodoInput};
+use crate::{due, locale, output, prompt, search};
+
+const KEYS: &str = "[d]ue  [c]omplete  [x] delete  [s]kip  [q]uit";
+
+enum Choice {
+    Due(DateTime<Utc>),
+    Complete,
+    Delete,
+    Skip,
+    Quit,
+}
+
+/// `confirm` asks before each delete.
+pub async fn run<A: TodoApi>(client: &A, aliases: &mut Aliases, confirm: bool) -> Result<()> {
+    if !prompt::interactive() {
+        return Err(prompt::required("`todo triage` reads keys from a terminal"));
+    }
+
+    let mut todos: Vec<Todo> = client
+        .list_todos(Some(false), &RequestOptions::new().retries(2))
+        .await?
+        .into_iter()
+        // The server may not filter by completion, so check here too
+        .filter(|todo| !todo.completed && todo.due_at.is_none())
+        .collect();
+    todos.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+    let limits = client
+        .limits(&RequestOptions::new().timeout(StdDuration::from_secs(2)))
+        .await
+        .unwrap_or_default();
+
+    let opts = RequestOptions::default();
+    let mut summary = Summary::new("triaged");
+    let mut deleted = Vec::new();
+    let total = todos.len();
+    for (i, todo) in todos.into_iter().enumerate() {
+        let item = format!("#{} {}", todo.id, todo.title);
+        output::say!();
+        output::say!("{} {}", format!("[{}/{}]", i + 1, total).dimmed(), item);
+
+        match choose(&limits, confirm)? {
+            Choice::Due(due) => match client.set_due(todo.id, due, &opts).await {
+                Ok(updated) => {
+                    history::record_todo(&updated, Action::Updated);
+                    let due = locale::datetime(&due.with_timezone(&Local));
+                    summary.succeeded(format!("{}: due {}", item, due), todo.id);
+                }
+                Err(err) => summary.failed(item, Some(todo.id), err),
+            },
+            Choice::Complete => match client.update_todo(todo.id, None, Some(true), &opts).await {
+                Ok(updated) => {
+                    history::record_todo(&updated, Action::Completed);
+                    summary.succeeded(format!("{}: completed", item), todo.id);
+                }
+                Err(err) => summary.failed(item, Some(todo.id), err),
+            },
+            Choice::Delete => match client.delete_todo(todo.id, &opts).await {
+                Ok(()) => {
+                    history::record_deleted(todo.id, Some(todo.title));
+                    aliases.forget(todo.id);
+                    deleted.push(todo.id);
+                    summary.succeeded(format!("{}: deleted", item), todo.id);
+                }
+                Err(err) => summary.failed(item, Some(todo.id), err),
+            },
+            Choice::Skip => summary.skipped(item, Some(todo.id), "skipped"),
+            Choice::Quit => break,
+        }
+    }
+    aliases.save();
+    search::forget(&deleted);
+
+    if total == 0 {
+        output::say!("{}", "Nothing to triage; every open todo has a due date.".dimmed());
+    } else {
+        output::say!();
+    }
+    summary.finish(None)
+}
+
+/// Reads keys until one picks an action.
+fn choose(limits: &Limits, confirm: bool) -> Result<Choice> {
+    loop {
+        match prompt::key(KEYS)? {
+            'd' => {
+                if let Some(due) = ask_due(limits)? {
+                    return Ok(Choice::Due(due));
+                }
+            }
+            'c' => return Ok(Choice::Complete),
+            'x' if !confirm || prompt::confirm("Delete it?", false)? => {
+                return Ok(Choice::Delete);
+            }
+            's' | ' ' | '\n' => return Ok(Choice::Skip),
+            'q' => return Ok(Choice::Quit),
+            _ => {}
+        }
+    }
+}
+
+/// Asks for a due date until one parses and isn't in the past; an empty
+/// answer goes back to the action keys.
+pub fn ask_due(limits: &Limits) -> Result<Option<DateTime<Utc>>> {
+    loop {
+        let answer = prompt::input("Due (today, tomorrow, YYYY-MM-DD or a span like 3d)", None)?;
+        if answer.is_empty() {
+            return Ok(None);
+        }
+        let due = match parse_due(&answer) {
+            Ok(due) => due,
+            Err(message) => {
+                eprintln!("{}", message.red());
+                continue;
+            }
+        };
+        let input = TodoInput {
+            due: Some(due),
+            ..Default::default()
+        };
+        match input.validate(limits) {
+            Ok(()) => return Ok(Some(due)),
+            Err(invalid) => {
+                for field in invalid.fields {
+                    eprintln!("{}", field.message.red());
+                }
+            }
+        }
+    }
+}
+
+/// A date means the end of that local day; a span counts from now.
+pub fn parse_due(s: &str) -> Result<DateTime<Utc>, String> {
+    let today = Local::now().date_naive();
+    let date = match s {
+        "today" => today,
+        "tomorrow" => today + Duration::days(1),
+        _ => match NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+            Ok(date) => date,
+            Err(_) => return due::parse_span(s).map(|span| Utc::now() + span),
+        },
+    };
+    Ok(due::start_of(date + Duration::days(1)) - Duration::minutes(1))
+}
@@ -0,0 +1,419 @@
+//! `todo tui`: a full-screen todo list that refreshes itself, for working
+//! through todos with single keys instead of a command per change.
+
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use chrono::{DateTime, Local, Utc};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{List, ListItem, ListState, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+
+use crate::aliases::Aliases;
+use crate::api::{RequestOptions, Todo, TodoApi, TodoStatus};
+use crate::history::{self, Action};
+use crate::validation::{Limits, TodoInput};
+use crate::{prompt, search};
+
+const KEYS: &str = "↑/↓ move  space done  n new  e edit  d delete  r refresh  q quit";
+
+/// How long to wait for a key before checking whether a refresh is due.
+const TICK: Duration = Duration::from_millis(250);
+
+/// What keys currently go to.
+enum Mode {
+    Browse,
+    /// Typing a title: for a new todo, or the new title of `id`
+    Input {
+        id: Option<i64>,
+        text: String,
+    },
+    /// Waiting for `y` to delete `id`
+    ConfirmDelete(i64),
+}
+
+/// The outcome of the last action, shown in the footer until the next one.
+struct Status {
+    message: String,
+    error: bool,
+}
+
+struct App {
+    todos: Vec<Todo>,
+    list: ListState,
+    mode: Mode,
+    status: Option<Status>,
+    loaded_at: Instant,
+    /// `loaded_at` on the clock, for the header
+    refreshed: DateTime<Local>,
+    limits: Limits,
+    confirm: bool,
+    deleted: Vec<i64>,
+}
+
+/// `confirm` asks before each delete; the list reloads every `refresh`
+/// while no title is being typed.
+pub async fn run<A: TodoApi>(
+    client: &A,
+    aliases: &mut Aliases,
+    confirm: bool,
+    refresh: Duration,
+) -> Result<()> {
+    if !prompt::interactive() {
+        return Err(prompt::required("`todo tui` needs a terminal"));
+    }
+
+    let limits = client
+        .limits(&RequestOptions::new().timeout(Duration::from_secs(2)))
+        .await
+        .unwrap_or_default();
+    let mut app = App {
+        todos: Vec::new(),
+        list: ListState::default().with_selected(Some(0)),
+        mode: Mode::Browse,
+        status: None,
+        loaded_at: Instant::now(),
+        refreshed: Local::now(),
+        limits,
+        confirm,
+        deleted: Vec::new(),
+    };
+    // Before taking over the screen, so a failure prints like any other
+    app.reload(client).await?;
+
+    let mut terminal = ratatui::init();
+    let result = app
+        .event_loop(client, aliases, &mut terminal, refresh)
+        .await;
+    ratatui::restore();
+
+    aliases.save();
+    search::forget(&app.deleted);
+    result
+}
+
+impl App {
+    async fn event_loop<A: TodoApi>(
+        &mut self,
+        client: &A,
+        aliases: &mut Aliases,
+        terminal: &mut DefaultTerminal,
+        refresh: Duration,
+    ) -> Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+
+            if matches!(self.mode, Mode::Browse) && self.loaded_at.elapsed() >= refresh {
+                if let Err(err) = self.reload(client).await {
+                    self.fail(err);
+                }
+                continue;
+            }
+            if !event::poll(TICK)? {
+                continue;
+            }
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            if !self.handle(client, aliases, key).await {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Acts on a key; `false` once it's time to quit.
+    async fn handle<A: TodoApi>(
+        &mut self,
+        client: &A,
+        aliases: &mut Aliases,
+        key: KeyEvent,
+    ) -> bool {
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            return false;
+        }
+
+        match std::mem::replace(&mut self.mode, Mode::Browse) {
+            Mode::Input { id, mut text } => match key.code {
+                KeyCode::Enter => self.save_title(client, id, text.trim()).await,
+                KeyCode::Esc => {}
+                KeyCode::Backspace => {
+                    text.pop();
+                    self.mode = Mode::Input { id, text };
+                }
+                KeyCode::Char(c) => {
+                    text.push(c);
+                    self.mode = Mode::Input { id, text };
+                }
+                _ => self.mode = Mode::Input { id, text },
+            },
+            Mode::ConfirmDelete(id) => {
+                if key.code == KeyCode::Char('y') {
+                    self.delete(client, aliases, id).await;
+                }
+            }
+            Mode::Browse => {
+                self.status = None;
+                match key.code {
+                    KeyCode::Char('q') | KeyCode::Esc => return false,
+                    KeyCode::Down | KeyCode::Char('j') => self.list.select_next(),
+                    KeyCode::Up | KeyCode::Char('k') => self.list.select_previous(),
+                    KeyCode::Home | KeyCode::Char('g') => self.list.select_first(),
+                    KeyCode::End | KeyCode::Char('G') => self.list.select_last(),
+                    KeyCode::Char(' ') | KeyCode::Enter => self.toggle(client).await,
+                    KeyCode::Char('n') => {
+                        self.mode = Mode::Input {
+                            id: None,
+                            text: String::new(),
+                        };
+                    }
+                    KeyCode::Char('e') => {
+                        if let Some(todo) = self.selected() {
+                            self.mode = Mode::Input {
+                                id: Some(todo.id),
+                                text: todo.title.clone(),
+                            };
+                        }
+                    }
+                    KeyCode::Char('d') => {
+                        if let Some(id) = self.selected().map(|todo| todo.id) {
+                            if self.confirm {
+                                self.mode = Mode::ConfirmDelete(id);
+                            } else {
+                                self.delete(client, aliases, id).await;
+                            }
+                        }
+                    }
+                    KeyCode::Char('r') => match self.reload(client).await {
+                        Ok(()) => self.done("Refreshed".to_string()),
+                        Err(err) => self.fail(err),
+                    },
+                    _ => {}
+                }
+            }
+        }
+        true
+    }
+
+    /// Fetches every todo, open ones first, keeping the same todo selected
+    /// when it's still there.
+    async fn reload<A: TodoApi>(&mut self, client: &A) -> Result<()> {
+        // Counted from the attempt, so a failing server isn't asked every tick
+        self.loaded_at = Instant::now();
+        let mut todos = client
+            .list_todos(None, &RequestOptions::new().retries(2))
+            .await?;
+        search::record(&todos, true);
+        // Stable, so the server's order holds within each half
+        todos.sort_by_key(|todo| todo.completed);
+
+        let selected = self.selected().map(|todo| todo.id);
+        self.todos = todos;
+        self.refreshed = Local::now();
+        let index = selected.and_then(|id| self.todos.iter().position(|todo| todo.id == id));
+        self.list.select(index.or(self.list.selected()));
+        Ok(())
+    }
+
+    fn selected(&self) -> Option<&Todo> {
+        self.list.selected().and_then(|index| self.todos.get(index))
+    }
+
+    async fn toggle<A: TodoApi>(&mut self, client: &A) {
+        let Some((id, completed)) = self.selected().map(|todo| (todo.id, todo.completed)) else {
+            return;
+        };
+        match client
+            .update_todo(id, None, Some(!completed), &RequestOptions::default())
+            .await
+        {
+            Ok(todo) => {
+                let action = if todo.completed {
+                    Action::Completed
+                } else {
+                    Action::Reopened
+                };
+                history::record_todo(&todo, action);
+                self.done(format!(
+                    "#{} {}",
+                    todo.id,
+                    if todo.completed {
+                        "completed"
+                    } else {
+                        "reopened"
+                    }
+                ));
+                self.replace(todo);
+            }
+            Err(err) => self.fail(err.into()),
+        }
+    }
+
+    /// Creates a todo when `id` is `None`, otherwise renames it.
+    async fn save_title<A: TodoApi>(&mut self, client: &A, id: Option<i64>, title: &str) {
+        let unchanged = |todo: &Todo| Some(todo.id) == id && todo.title == title;
+        if title.is_empty() || self.todos.iter().any(unchanged) {
+            return;
+        }
+        let input = TodoInput {
+            title: Some(title),
+            ..Default::default()
+        };
+        if let Err(invalid) = input.validate(&self.limits) {
+            let message = invalid
+                .fields
+                .first()
+                .map(|field| field.message.clone())
+                .unwrap_or_else(|| invalid.to_string());
+            self.fail(anyhow::anyhow!(message));
+            return;
+        }
+
+        let opts = RequestOptions::default();
+        match id {
+            None => match client.create_todo(title, &opts).await {
+                Ok(todo) => {
+                    history::record_todo(&todo, Action::Created);
+                    self.done(format!("#{} created", todo.id));
+                    // Last among the open todos, where a refresh would put it
+                    let index = self.todos.iter().take_while(|todo| !todo.completed).count();
+                    self.todos.insert(index, todo);
+                    self.list.select(Some(index));
+                }
+                Err(err) => self.fail(err.into()),
+            },
+            Some(id) => match client.update_todo(id, Some(title), None, &opts).await {
+                Ok(todo) => {
+                    history::record_todo(&todo, Action::Updated);
+                    self.done(format!("#{} renamed", todo.id));
+                    self.replace(todo);
+                }
+                Err(err) => self.fail(err.into()),
+            },
+        }
+    }
+
+    async fn delete<A: TodoApi>(&mut self, client: &A, aliases: &mut Aliases, id: i64) {
+        match client.delete_todo(id, &RequestOptions::default()).await {
+            Ok(()) => {
+                let title = self
+                    .todos
+                    .iter()
+                    .position(|todo| todo.id == id)
+                    .map(|index| self.todos.remove(index).title);
+                history::record_deleted(id, title);
+                aliases.forget(id);
+                self.deleted.push(id);
+                self.done(format!("#{} deleted", id));
+            }
+            Err(err) => self.fail(err.into()),
+        }
+    }
+
+    fn replace(&mut self, todo: Todo) {
+        if let Some(slot) = self.todos.iter_mut().find(|slot| slot.id == todo.id) {
+            *slot = todo;
+        }
+    }
+
+    fn done(&mut self, message: String) {
+        self.status = Some(Status {
+            message,
+            error: false,
+        });
+    }
+
+    fn fail(&mut self, err: anyhow::Error) {
+        self.status = Some(Status {
+            message: format!("{:#}", err),
+            error: true,
+        });
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [header, body, footer] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(1),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        let open = self.todos.iter().filter(|todo| !todo.completed).count();
+        let title = format!(
+            " {} open, {} done · refreshed {}",
+            open,
+            self.todos.len() - open,
+            self.refreshed.format("%H:%M:%S")
+        );
+        frame.render_widget(
+            Paragraph::new(title).style(Style::new().add_modifier(Modifier::BOLD)),
+            header,
+        );
+
+        let now = Utc::now();
+        let items: Vec<ListItem> = self.todos.iter().map(|todo| item(todo, now)).collect();
+        let list = List::new(items)
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+            .highlight_symbol("> ");
+        frame.render_stateful_widget(list, body, &mut self.list);
+
+        let line = match self.mode {
+            Mode::Input { id, ref text } => {
+                let label = match id {
+                    Some(id) => format!("Title for #{}: ", id),
+                    None => "New todo: ".to_string(),
+                };
+                Line::from(vec![
+                    Span::styled(label, Style::new().add_modifier(Modifier::BOLD)),
+                    Span::raw(format!("{}▏", text)),
+                    Span::styled(
+                        "  Enter saves, Esc cancels",
+                        Style::new().fg(Color::DarkGray),
+                    ),
+                ])
+            }
+            Mode::ConfirmDelete(id) => Line::styled(
+                format!("Delete #{}? y/n", id),
+                Style::new().fg(Color::Yellow),
+            ),
+            Mode::Browse => match self.status {
+                Some(ref status) if status.error => {
+                    Line::styled(status.message.clone(), Style::new().fg(Color::Red))
+                }
+                Some(ref status) => {
+                    Line::styled(status.message.clone(), Style::new().fg(Color::Green))
+                }
+                None => Line::styled(KEYS, Style::new().fg(Color::DarkGray)),
+            },
+        };
+        frame.render_widget(Paragraph::new(line), footer);
+    }
+}
+
+/// `[x] #12 Title`: done todos dimmed and struck through, overdue ones in
+/// red and todos in progress in yellow.
+fn item(todo: &Todo, now: DateTime<Utc>) -> ListItem<'static> {
+    let check = if todo.completed { "[x]" } else { "[ ]" };
+    let style = if todo.completed {
+        Style::new()
+            .fg(Color::DarkGray)
+            .add_modifier(Modifier::CROSSED_OUT)
+    } else if todo.is_overdue(now) {
+        Style::new().fg(Color::Red)
+    } else if todo.status == Some(TodoStatus::InProgress) {
+        Style::new().fg(Color::Yellow)
+    } else {
+        Style::new()
+    };
+    // A newline in a title would spill into the next row
+    let title = todo.title.split_whitespace().collect::<Vec<_>>().join(" ");
+    ListItem::new(Line::styled(
+        format!("{} #{} {}", check, todo.id, title),
+        style,
+    ))
+}
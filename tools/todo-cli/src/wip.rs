@@ -0,0 +1,101 @@
+//! Work-in-progress limits: `wip_limit` caps how many todos can be in
+//! progress at once. `start-work` warns past it, or refuses with
+//! `wip_policy = block`, and `list` shows how much capacity is left.
+
+use anyhow::{Result, bail};
+use serde::{Deserialize, Serialize};
+
+use crate::api::{RequestOptions, Todo, TodoApi, TodoStatus};
+use crate::config::Config;
+use crate::output;
+use crate::style::Colorize;
+
+/// What `start-work` does when the limit is already reached.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Policy {
+    /// Start it anyway, with a warning
+    #[default]
+    Warn,
+    /// Refuse unless `--force` is given
+    Block,
+}
+
+impl Policy {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Policy::Warn => "warn",
+            Policy::Block => "block",
+        }
+    }
+}
+
+/// Open todos in progress against the configured limit.
+#[derive(Debug, Clone, Copy)]
+pub struct Capacity {
+    pub in_progress: usize,
+    pub limit: usize,
+}
+
+impl Capacity {
+    /// Counts in-progress todos in `todos`, which should be every open todo
+    /// rather than a filtered view. `None` without a configured limit.
+    pub fn of(todos: &[Todo], config: &Config) -> Option<Self> {
+        let limit = config.wip_limit?;
+        let in_progress = todos.iter().filter(|todo| in_progress(todo)).count();
+        Some(Self { in_progress, limit })
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.in_progress >= self.limit
+    }
+
+    /// `In progress: 3/5`, yellow once full and red past the limit.
+    pub fn print_header(&self) {
+        let text = format!("In progress: {}/{}", self.in_progress, self.limit);
+        let text = if self.in_progress > self.limit {
+            text.as_str().red()
+        } else if self.is_full() {
+            text.as_str().yellow()
+        } else {
+            text.as_str().dimmed()
+        };
+        output::say!("{}", text);
+    }
+}
+
+fn in_progress(todo: &Todo) -> bool {
+    !todo.completed && todo.status == Some(TodoStatus::InProgress)
+}
+
+/// Run before starting `id`: warns when that would go past the limit, or
+/// fails under the `block` policy. Starting a todo that's already in
+/// progress never counts against it.
+pub async fn check<A: TodoApi>(client: &A, config: &Config, id: i64) -> Result<()> {
+    let Some(limit) = config.wip_limit else {
+        return Ok(());
+    };
+    let todos = client.list_todos(Some(false), &RequestOptions::new().retries(2)).await?;
+    if todos.iter().any(|todo| todo.id == id && in_progress(todo)) {
+        return Ok(());
+    }
+    let capacity = Capacity {
+        in_progress: todos.iter().filter(|todo| in_progress(todo)).count(),
+        limit,
+    };
+    if !capacity.is_full() {
+        return Ok(());
+    }
+
+    let message = format!(
+        "{} todos are already in progress (limit {})",
+        capacity.in_progress, capacity.limit
+    );
+    match config.wip_policy {
+        Policy::Block => bail!("{}; finish one first or use --force", message),
+        Policy::Warn => {
+            eprintln!("{}", output::with_icon("⚠️ ", &message).yellow());
+            Ok(())
+        }
+    }
+}
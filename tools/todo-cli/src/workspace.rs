@@ -0,0 +1,261 @@
+use anyhow::{Result, bail};
+use chrono::{DateTime, Utc};
+
+use crate::api::{Invitation, Member, RequestOptions, Role, TodoApi, Workspace};
+use crate::config::Config;
+use crate::locale;
+use crate::output;
+use crate::style::Colorize;
+
+pub async fn list<A: TodoApi>(client: &A, format: &str) -> Result<()> {
+    let workspaces = client.list_workspaces(&RequestOptions::new().retries(2)).await?;
+    if format == "json" {
+        return output::print_json("workspace_list", &workspaces);
+    }
+
+    if workspaces.is_empty() {
+        println!("{}", "No workspaces yet.".dimmed());
+        return Ok(());
+    }
+
+    for workspace in &workspaces {
+        let current = if client.workspace() == Some(workspace.id) { "*" } else { " " };
+        println!(
+            "{} {}  {}",
+            current,
+            format!("#{}", workspace.id).dimmed(),
+            workspace.name
+        );
+    }
+    Ok(())
+}
+
+pub async fn create<A: TodoApi>(client: &A, name: &str, format: &str) -> Result<()> {
+    let workspace = client.create_workspace(name, &RequestOptions::default()).await?;
+    if format == "json" {
+        return output::print_result("created", &[workspace.id], &workspace);
+    }
+
+    output::success(&format!(
+        "Workspace #{} created; `todo workspace switch {}` to use it.",
+        workspace.id, workspace.id
+    ));
+    Ok(())
+}
+
+/// Makes `target` (an ID or name) the default scope for todo commands, or
+/// goes back to personal todos without one.
+pub async fn switch<A: TodoApi>(client: &A, target: Option<&str>) -> Result<()> {
+    let Some(target) = target else {
+        Config::update(|config| {
+            config.workspace = None;
+            Ok(())
+        })?;
+        output::success("Switched to personal todos.");
+        return output::print_result("switched", &[], ());
+    };
+
+    let workspaces = client.list_workspaces(&RequestOptions::default()).await?;
+    let workspace = resolve(&workspaces, target)?;
+    Config::update(|config| {
+        config.workspace = Some(workspace.id);
+        Ok(())
+    })?;
+    output::success(&format!("Switched to workspace {}.", workspace.name));
+    output::print_result("switched", &[workspace.id], workspace)
+}
+
+pub async fn members<A: TodoApi>(client: &A, workspace: Option<i64>, format: &str) -> Result<()> {
+    let Some(id) = workspace.or(client.workspace()) else {
+        bail!("No workspace selected; pass one or run `todo workspace switch` first");
+    };
+    let members = client.workspace_members(id, &RequestOptions::new().retries(2)).await?;
+    print_members(&members, format)
+}
+
+/// Changes the role of the member with `email` in the current workspace.
+pub async fn set_role<A: TodoApi>(client: &A, email: &str, role: Role) -> Result<()> {
+    let Some(id) = client.workspace() else {
+        bail!("No workspace selected; pass --workspace or run `todo workspace switch` first");
+    };
+    require_admin(client, id, "change roles").await?;
+
+    let members = client.workspace_members(id, &RequestOptions::default()).await?;
+    let Some(member) = members
+        .iter()
+        .find(|member| member.email.eq_ignore_ascii_case(email))
+    else {
+        bail!("{} is not a member of this workspace", email);
+    };
+    if member.role == role {
+        output::say!("{} is already {}.", member.email, role);
+        return output::print_result("unchanged", &[member.user_id], member);
+    }
+
+    let member = client
+        .set_member_role(id, member.user_id, role, &RequestOptions::default())
+        .await?;
+    output::success(&format!("{} is now {}.", member.email, member.role));
+    output::print_result("role_changed", &[member.user_id], &member)
+}
+
+fn print_members(members: &[Member], format: &str) -> Result<()> {
+    if format == "json" {
+        return output::print_json("member_list", members);
+    }
+
+    for member in members {
+        let role = format!("{:<6}", member.role.as_str());
+        match member.name {
+            Some(ref name) => println!("  {}  {} <{}>", role.dimmed(), name, member.email),
+            None => println!("  {}  {}", role.dimmed(), member.email),
+        }
+    }
+    Ok(())
+}
+
+/// Fails early with a readable error when the current user's role in
+/// workspace `id` can't `action`. Servers that don't report roles are left
+/// to refuse the request themselves.
+async fn require_admin<A: TodoApi>(client: &A, id: i64, action: &str) -> Result<()> {
+    let workspaces = client.list_workspaces(&RequestOptions::default()).await?;
+    let Some(workspace) = workspaces.iter().find(|workspace| workspace.id == id) else {
+        bail!("You are not a member of workspace #{}", id);
+    };
+    match workspace.role {
+        Some(role) if role != Role::Admin => bail!(
+            "You are {} in {}; only admins can {}",
+            with_article(role),
+            workspace.name,
+            action
+        ),
+        _ => Ok(()),
+    }
+}
+
+fn with_article(role: Role) -> String {
+    match role {
+        Role::Admin => "an admin".to_string(),
+        role => format!("a {}", role),
+    }
+}
+
+pub async fn invite<A: TodoApi>(client: &A, email: &str, role: Role, format: &str) -> Result<()> {
+    let Some(id) = client.workspace() else {
+        bail!("No workspace selected; pass --workspace or run `todo workspace switch` first");
+    };
+    require_admin(client, id, "invite members").await?;
+    let invitation = client
+        .invite_member(id, email, role, &RequestOptions::default())
+        .await?;
+    if format == "json" {
+        return output::print_result("invited", &[invitation.id], &invitation);
+    }
+
+    output::success(&format!("Invited {} as {}.", invitation.email, invitation.role));
+    if let Some(expires) = invitation.expires_at.as_deref().and_then(parse_time) {
+        println!("  {}", format!("Pending until {}", locale::date(expires.date_naive())).dimmed());
+    }
+    Ok(())
+}
+
+/// Lists invitations addressed to you; answered and expired ones only with `all`.
+pub async fn invitations<A: TodoApi>(client: &A, all: bool, format: &str) -> Result<()> {
+    let mut invitations = client.list_invitations(&RequestOptions::new().retries(2)).await?;
+    if !all {
+        invitations.retain(Invitation::is_pending);
+    }
+    if format == "json" {
+        return output::print_json("invitation_list", &invitations);
+    }
+
+    if invitations.is_empty() {
+        println!("{}", "No pending invitations.".dimmed());
+        return Ok(());
+    }
+
+    let now = Utc::now();
+    for invitation in &invitations {
+        let workspace = match invitation.workspace_name {
+            Some(ref name) => name.clone(),
+            None => format!("workspace #{}", invitation.workspace_id),
+        };
+        let from = invitation
+            .invited_by
+            .as_deref()
+            .map(|by| format!(" from {}", by))
+            .unwrap_or_default();
+        println!(
+            "  {} {} as {}{}  {}",
+            format!("#{}", invitation.id).dimmed(),
+            workspace,
+            invitation.role,
+            from,
+            status(invitation, now)
+        );
+    }
+    if invitations.iter().any(Invitation::is_pending) {
+        println!();
+        println!("{}", "Answer with `todo invites accept <id>` or `decline <id>`.".dimmed());
+    }
+    Ok(())
+}
+
+pub async fn respond<A: TodoApi>(client: &A, id: i64, accept: bool) -> Result<()> {
+    let invitation = client
+        .respond_to_invitation(id, accept, &RequestOptions::default())
+        .await?;
+    let workspace = invitation
+        .workspace_name
+        .clone()
+        .unwrap_or_else(|| format!("workspace #{}", invitation.workspace_id));
+    if accept {
+        output::success(&format!("Joined {} as {}.", workspace, invitation.role));
+        output::say!("Run `todo workspace switch {}` to use it.", invitation.workspace_id);
+    } else {
+        output::success(&format!("Declined the invitation to {}.", workspace));
+    }
+    let action = if accept { "joined" } else { "declined" };
+    output::print_result(action, &[invitation.id], &invitation)
+}
+
+/// Colored status for the invitation list. The server may not have marked
+/// an overdue invitation expired yet, so the expiry time is checked too.
+fn status(invitation: &Invitation, now: DateTime<Utc>) -> String {
+    let expires = invitation.expires_at.as_deref().and_then(parse_time);
+    match invitation.status.as_str() {
+        "pending" => match expires {
+            Some(at) if at < now => "expired".dimmed().to_string(),
+            Some(at) => format!("pending, expires {}", locale::date(at.date_naive()))
+                .yellow()
+                .to_string(),
+            None => "pending".yellow().to_string(),
+        },
+        "accepted" => "accepted".green().to_string(),
+        other => other.dimmed().to_string(),
+    }
+}
+
+fn parse_time(timestamp: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(timestamp)
+        .ok()
+        .map(|at| at.with_timezone(&Utc))
+}
+
+/// Finds a workspace by ID, or else by case-insensitive name.
+fn resolve<'a>(workspaces: &'a [Workspace], target: &str) -> Result<&'a Workspace> {
+    if let Ok(id) = target.parse::<i64>()
+        && let Some(workspace) = workspaces.iter().find(|workspace| workspace.id == id)
+    {
+        return Ok(workspace);
+    }
+
+    let mut matches = workspaces
+        .iter()
+        .filter(|workspace| workspace.name.eq_ignore_ascii_case(target));
+    match (matches.next(), matches.next()) {
+        (Some(workspace), None) => Ok(workspace),
+        (Some(_), Some(_)) => bail!("Several workspaces are named '{}'; use its ID", target),
+        (None, _) => bail!("No workspace '{}'; see `todo workspace list`", target),
+    }
+}
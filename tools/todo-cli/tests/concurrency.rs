@@ -0,0 +1,77 @@
+//! Several `todo` processes at once, as a script in a loop or a few open
+//! terminals would run them, sharing one config and state directory.
+
+use std::path::Path;
+use std::thread;
+
+use assert_cmd::Command;
+use serde_json::Value;
+use tempfile::TempDir;
+
+const PROCESSES: usize = 16;
+
+/// The CLI with its config and state inside `home`, pointed at a port
+/// nothing listens on so requests fail fast.
+fn todo(home: &Path) -> Command {
+    let mut cmd = assert_cmd::cargo::cargo_bin_cmd!("todo-cli");
+    cmd.env_clear()
+        .env("HOME", home)
+        .env("XDG_CONFIG_HOME", home.join("config"))
+        .env("XDG_STATE_HOME", home.join("state"))
+        .env("TODO_CONFIG", home.join("config.toml"))
+        .env("TODO_API_URL", "http://127.0.0.1:9")
+        .env("TODO_TEST_HARNESS", "1")
+        .env("NO_COLOR", "1");
+    cmd
+}
+
+/// Runs `args(i)` in `PROCESSES` processes at once.
+fn in_parallel(home: &Path, args: impl Fn(usize) -> Vec<String> + Sync) {
+    thread::scope(|scope| {
+        for i in 0..PROCESSES {
+            let args = args(i);
+            scope.spawn(move || todo(home).args(args).output().expect("todo runs"));
+        }
+    });
+}
+
+fn json(home: &Path, args: &[&str]) -> Value {
+    let output = todo(home).args(["--format", "json"]).args(args).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    serde_json::from_slice(&output.stdout).expect("valid JSON")
+}
+
+#[test]
+fn parallel_config_updates_keep_every_key() {
+    let home = TempDir::new().unwrap();
+    in_parallel(home.path(), |i| {
+        vec!["place".into(), "add".into(), format!("place{}", i), format!("{}.5,13.4", i)]
+    });
+
+    let places = json(home.path(), &["place", "list"]);
+    let places = places.as_object().expect("places by name");
+    for i in 0..PROCESSES {
+        let position = places.get(&format!("place{}", i)).and_then(Value::as_str);
+        assert_eq!(position, Some(format!("{}.5,13.4", i).as_str()), "{:?}", places);
+    }
+}
+
+#[test]
+fn parallel_appends_keep_every_entry() {
+    let home = TempDir::new().unwrap();
+    in_parallel(home.path(), |i| vec!["create".into(), format!("todo {}", i)]);
+
+    let audit = json(home.path(), &["audit", "show", "-n", "100"]);
+    let mut titles: Vec<_> = audit
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|entry| entry["command"].as_array().unwrap().last().unwrap().clone())
+        .collect();
+    titles.sort_by_key(|title| title.as_str().unwrap().to_string());
+    titles.dedup();
+    assert_eq!(titles.len(), PROCESSES);
+
+    let history = json(home.path(), &["history", "--commands", "-n", "100"]);
+    assert_eq!(history.as_array().unwrap().len(), PROCESSES);
+}
@@ -0,0 +1,214 @@
+//! End-to-end tests against the real API: Postgres plus the go-api-starter
+//! image, started with testcontainers. Ignored by default since they need
+//! Docker and a built image:
+//!
+//! ```bash
+//! docker build -t go-api-starter .   # from the repository root
+//! cargo test --test e2e -- --ignored
+//! ```
+//!
+//! `TODO_E2E_IMAGE` picks another image, e.g. one pushed by CI.
+
+use std::path::Path;
+use std::time::Duration;
+
+use assert_cmd::Command;
+use predicates::prelude::*;
+use tempfile::TempDir;
+use testcontainers::core::wait::LogWaitStrategy;
+use testcontainers::core::{IntoContainerPort, Mount, WaitFor};
+use testcontainers::runners::AsyncRunner;
+use testcontainers::{ContainerAsync, GenericImage, ImageExt};
+
+const DEFAULT_IMAGE: &str = "go-api-starter:latest";
+const EMAIL: &str = "e2e@example.com";
+const PASSWORD: &str = "correct-horse-battery";
+
+/// Exit statuses the CLI promises to scripts.
+const EXIT_FAILURE: i32 = 1;
+const EXIT_USAGE: i32 = 2;
+const EXIT_INPUT_REQUIRED: i32 = 3;
+
+/// A running API plus an isolated home for the CLI. Containers stop when
+/// this is dropped.
+struct Harness {
+    api_url: String,
+    home: TempDir,
+    _db: ContainerAsync<GenericImage>,
+    _api: ContainerAsync<GenericImage>,
+}
+
+impl Harness {
+    async fn start() -> Self {
+        let network = format!("todo-e2e-{}", std::process::id());
+        let db_host = format!("{}-db", network);
+        let migrations = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../db/migrations");
+        let migrations = migrations.canonicalize().expect("db/migrations exists");
+
+        // Postgres reports ready once for the init server and again for
+        // the real one, after the migrations ran
+        let db = GenericImage::new("postgres", "16-alpine")
+            .with_wait_for(WaitFor::log(
+                LogWaitStrategy::stderr("database system is ready to accept connections")
+                    .with_times(2),
+            ))
+            .with_env_var("POSTGRES_USER", "postgres")
+            .with_env_var("POSTGRES_PASSWORD", "postgres")
+            .with_env_var("POSTGRES_DB", "go_api_starter")
+            .with_mount(Mount::bind_mount(
+                migrations.to_string_lossy(),
+                "/docker-entrypoint-initdb.d",
+            ))
+            .with_network(&network)
+            .with_container_name(&db_host)
+            .start()
+            .await
+            .expect("Postgres starts");
+
+        let image = std::env::var("TODO_E2E_IMAGE").unwrap_or_else(|_| DEFAULT_IMAGE.into());
+        let (name, tag) = image.rsplit_once(':').unwrap_or((&image, "latest"));
+        let database_url = format!(
+            "postgres://postgres:postgres@{}:5432/go_api_starter?sslmode=disable",
+            db_host
+        );
+        let api = GenericImage::new(name, tag)
+            .with_exposed_port(8080.tcp())
+            .with_wait_for(WaitFor::Nothing)
+            .with_env_var("PORT", "8080")
+            .with_env_var("DATABASE_URL", database_url)
+            .with_env_var("JWT_SECRET", "e2e-secret")
+            .with_network(&network)
+            .start()
+            .await
+            .expect("API container starts");
+        let port = api.get_host_port_ipv4(8080).await.expect("API port is mapped");
+        let api_url = format!("http://127.0.0.1:{}", port);
+        wait_until_healthy(&api_url).await;
+
+        Self {
+            api_url,
+            home: TempDir::new().expect("temp dir"),
+            _db: db,
+            _api: api,
+        }
+    }
+
+    /// The CLI with its config, state and credentials inside `home`.
+    fn todo(&self) -> Command {
+        let home = self.home.path();
+        let mut cmd = assert_cmd::cargo::cargo_bin_cmd!("todo-cli");
+        cmd.env_clear()
+            .env("HOME", home)
+            .env("XDG_CONFIG_HOME", home.join("config"))
+            .env("XDG_STATE_HOME", home.join("state"))
+            .env("TODO_CONFIG", home.join("config.toml"))
+            .env("TODO_API_URL", &self.api_url)
+            .env("TODO_TEST_HARNESS", "1")
+            .env("NO_COLOR", "1");
+        cmd
+    }
+}
+
+async fn wait_until_healthy(api_url: &str) {
+    let url = format!("{}/health", api_url);
+    for _ in 0..60 {
+        let healthy = reqwest::get(&url)
+            .await
+            .is_ok_and(|response| response.status().is_success());
+        if healthy {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+    panic!("API at {} did not become healthy", api_url);
+}
+
+/// Reads the ID of the todo a `-f json` command changed. Its stdout is
+/// nothing but the JSON result.
+fn todo_id(output: &[u8]) -> i64 {
+    let result: serde_json::Value = serde_json::from_slice(output).expect("JSON output");
+    result["ids"][0].as_i64().expect("result has an id")
+}
+
+#[tokio::test]
+#[ignore = "needs Docker and the go-api-starter image"]
+async fn command_matrix() {
+    let harness = Harness::start().await;
+
+    // Usage errors come from clap, before anything is sent
+    harness.todo().arg("lst").assert().code(EXIT_USAGE);
+
+    // Not logged in yet
+    harness.todo().arg("list").assert().code(EXIT_FAILURE);
+
+    harness
+        .todo()
+        .args(["auth", "register", "-e", EMAIL, "-p", PASSWORD])
+        .assert()
+        .success()
+        // The API's register returns the user without a token
+        .stdout(predicate::str::contains("todo auth login"));
+    let password_file = harness.home.path().join("password");
+    std::fs::write(&password_file, format!("{}\n", PASSWORD)).unwrap();
+    harness
+        .todo()
+        .args(["auth", "login", "-e", EMAIL, "--password-file"])
+        .arg(&password_file)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Login successful"));
+    harness
+        .todo()
+        .args(["auth", "login", "-e", EMAIL])
+        .env("TODO_PASSWORD", "wrong-password")
+        .assert()
+        .code(EXIT_FAILURE);
+
+    let created = harness
+        .todo()
+        .args(["-f", "json", "create", "Write the e2e suite"])
+        .assert()
+        .success();
+    let id = todo_id(&created.get_output().stdout).to_string();
+
+    harness
+        .todo()
+        .args(["-f", "json", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Write the e2e suite"));
+    harness
+        .todo()
+        .args(["-f", "json", "done", &id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"action\": \"completed\""))
+        .stdout(predicate::str::contains("\"completed\": true"));
+    harness
+        .todo()
+        .args(["get", "999999"])
+        .assert()
+        .code(EXIT_FAILURE);
+
+    // Without a terminal, confirmations fail instead of waiting on stdin
+    harness
+        .todo()
+        .args(["delete", &id])
+        .assert()
+        .code(EXIT_INPUT_REQUIRED)
+        .stderr(predicate::str::contains("--force"));
+    harness
+        .todo()
+        .args(["delete", "--force", &id])
+        .assert()
+        .success();
+    harness
+        .todo()
+        .args(["-f", "json", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Write the e2e suite").not());
+
+    harness.todo().args(["auth", "logout"]).assert().success();
+    harness.todo().arg("list").assert().code(EXIT_FAILURE);
+}